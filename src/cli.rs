@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+
+use concorde::ExecMode;
+
+pub const USAGE: &str = "\
+usage: concorde [options] [run] <file> [args...]
+       concorde [options] -e <code> [args...]
+       concorde [options] check <file>
+       concorde [options]
+
+Runs a script, or starts an interactive session when none is given.
+
+commands:
+  run <file> [args...]  run a script, with the arguments in Core::argv
+  check <file>          parse a script without running it
+  -e <code> [args...]   run code given on the command line
+
+options:
+  --no-std              do not load the standard prelude
+  --std-path <path>     load the standard prelude from <path> instead of the
+                        one built in
+  --tree-walk           run code by walking the AST instead of compiling it
+  -h, --help            print this message
+";
+
+#[derive(thiserror::Error, Debug)]
+pub enum UsageError {
+    #[error("missing value for '{0}'")]
+    MissingValue(&'static str),
+    #[error("unknown option '{0}'")]
+    UnknownOption(String),
+    #[error("unexpected argument '{0}'")]
+    UnexpectedArgument(String),
+}
+
+#[derive(Debug)]
+pub enum Command {
+    Run { file: PathBuf },
+    Eval { code: String },
+    Check { file: PathBuf },
+    Repl,
+    Help,
+}
+
+/// The code run before the script, defining the classes it can use.
+#[derive(Debug)]
+pub enum Prelude {
+    /// The standard prelude built into the binary.
+    Std,
+    /// A prelude read from a file instead.
+    File(PathBuf),
+    None,
+}
+
+#[derive(Debug)]
+pub struct Cli {
+    pub command: Command,
+    /// The arguments after the script, for `Core::argv`.
+    pub args: Vec<String>,
+    pub prelude: Prelude,
+    pub exec_mode: ExecMode,
+}
+
+impl Cli {
+    /// Parses the command line, without the program name. Options come first, and everything
+    /// after the script is left for the script.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, UsageError> {
+        let mut args = args.into_iter();
+        let mut cli = Cli {
+            command: Command::Repl,
+            args: Vec::new(),
+            prelude: Prelude::Std,
+            exec_mode: ExecMode::default(),
+        };
+        while matches!(cli.command, Command::Repl) {
+            let Some(arg) = args.next() else {
+                break;
+            };
+            match arg.as_str() {
+                "--no-std" => cli.prelude = Prelude::None,
+                "--std-path" => {
+                    cli.prelude = Prelude::File(next_value(&mut args, "--std-path")?.into());
+                }
+                "--tree-walk" => cli.exec_mode = ExecMode::TreeWalk,
+                "-h" | "--help" => cli.command = Command::Help,
+                "-e" => {
+                    let code = next_value(&mut args, "-e")?;
+                    cli.command = Command::Eval { code };
+                }
+                "run" => {
+                    let file = next_value(&mut args, "run")?.into();
+                    cli.command = Command::Run { file };
+                }
+                "check" => {
+                    let file = next_value(&mut args, "check")?.into();
+                    cli.command = Command::Check { file };
+                }
+                option if option.starts_with('-') => return Err(UsageError::UnknownOption(arg)),
+                // a bare file is short for `run <file>`
+                _ => cli.command = Command::Run { file: arg.into() },
+            }
+        }
+        cli.args = args.collect();
+        if let (Command::Check { .. } | Command::Help, Some(arg)) = (&cli.command, cli.args.first())
+        {
+            return Err(UsageError::UnexpectedArgument(arg.clone()));
+        }
+        Ok(cli)
+    }
+}
+
+fn next_value(
+    args: &mut impl Iterator<Item = String>,
+    option: &'static str,
+) -> Result<String, UsageError> {
+    args.next().ok_or(UsageError::MissingValue(option))
+}
@@ -0,0 +1,79 @@
+use std::fmt::{Display, Formatter};
+
+use crate::parse;
+use crate::runtime::Error::ModuleLoad;
+use crate::types::{NodeMeta, TopError};
+
+/// An error as shown to the user: its message, the file and line it points at with the
+/// offending span underlined, and a hint where there is one.
+pub struct Diagnostic<'a>(pub &'a TopError);
+
+impl Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (kind, message, node, hint) = match self.0 {
+            TopError::Runtime(error) => {
+                if let ModuleLoad { module, error } = error.root() {
+                    writeln!(f, "runtime error: could not load module '{module}'")?;
+                    return write!(f, "{}", Diagnostic(error));
+                }
+                ("runtime error", error.message(), error.node(), error.hint())
+            }
+            // pest points at the error in the source itself
+            TopError::Parse(parse::Error::Pest(error)) => {
+                return writeln!(f, "parse error:\n{error}")
+            }
+            TopError::Parse(error) => ("parse error", error.message(), error.node(), None),
+            TopError::IO(_) => return writeln!(f, "{}", self.0),
+        };
+        writeln!(f, "{kind}: {message}")?;
+        if let Some(node) = node {
+            write!(f, "{}", Snippet(node))?;
+        }
+        if let Some(hint) = hint {
+            writeln!(f, "  = hint: {hint}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A warning as shown to the user, pointing at the code like an error.
+pub struct Warning<'a>(pub &'a parse::Warning);
+
+impl Display for Warning<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "warning: {}", self.0.message)?;
+        write!(f, "{}", Snippet(&self.0.node))
+    }
+}
+
+/// The line a node starts on, with the node underlined.
+struct Snippet<'a>(&'a NodeMeta);
+
+impl Display for Snippet<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let node = self.0;
+        let (line, column) = node.line_col;
+        let gutter = " ".repeat(line.to_string().len());
+        writeln!(f, "{gutter}--> {}:{line}:{column}", node.file.name)?;
+        let Some(text) = node.file.line(line) else {
+            return Ok(());
+        };
+        let indent = column.saturating_sub(1);
+        // keep tabs so that the underline lines up with the text above it
+        let padding: String = text
+            .chars()
+            .take(indent)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = node
+            .source
+            .lines()
+            .next()
+            .map_or(0, |first_line| first_line.chars().count())
+            .min(text.chars().count().saturating_sub(indent))
+            .max(1);
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{line} | {text}")?;
+        writeln!(f, "{gutter} | {padding}{}", "^".repeat(width))
+    }
+}
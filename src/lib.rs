@@ -0,0 +1,29 @@
+#![feature(decl_macro)]
+#![feature(new_uninit)]
+#![feature(strict_provenance)]
+#![feature(iter_next_chunk)]
+#![feature(let_chains)]
+#![feature(map_try_insert)]
+#![feature(iter_array_chunks)]
+#![feature(iter_map_windows)]
+#![feature(iterator_try_collect)]
+#![feature(yeet_expr)]
+#![feature(try_blocks)]
+
+//! An interpreter for the concorde language, which applications can embed as a scripting layer.
+//!
+//! A [`Runtime`] runs scripts and exchanges [`Value`]s with the host: Rust values convert to and
+//! from concorde ones through [`IntoValue`] and [`FromValue`], host functions are registered
+//! with [`Runtime::define_function`] and [`Runtime::define_method`], and concorde functions and
+//! methods are called by name with [`Runtime::call_function`] and [`Runtime::call_method_named`].
+//! Rust types become classes of their own by implementing [`NativeClass`].
+
+pub mod diagnostic;
+pub mod parse;
+pub mod runtime;
+pub mod types;
+
+pub use crate::runtime::{
+    Error, ExecMode, FromValue, IntoValue, NativeClass, NativeClassBuilder, Runtime, Value,
+};
+pub use crate::types::TopError;
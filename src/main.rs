@@ -1,34 +1,76 @@
-#![feature(decl_macro)]
-#![feature(new_uninit)]
-#![feature(strict_provenance)]
-#![feature(iter_next_chunk)]
-#![feature(let_chains)]
-#![feature(map_try_insert)]
-#![feature(iter_array_chunks)]
-#![feature(iter_map_windows)]
-#![feature(iterator_try_collect)]
-#![feature(yeet_expr)]
-#![feature(try_blocks)]
+use std::env;
+use std::path::Path;
+use std::process::ExitCode;
 
-use std::env::args;
+use concorde::diagnostic::{Diagnostic, Warning};
+use concorde::{parse, Runtime, TopError};
 
-use crate::runtime::Runtime;
-use crate::types::TopError;
+use crate::cli::{Cli, Command, Prelude, USAGE};
 
-mod parse;
-mod runtime;
-mod types;
+mod cli;
+mod repl;
 
-fn run() -> Result<(), TopError> {
-    let [_executable, filename] = args().next_chunk().unwrap_or_default();
-    let mut runtime = Runtime::new();
-    runtime.exec_file("./examples/std.concorde")?;
-    runtime.exec_file(filename)?;
-    Ok(())
+/// Extra directories to search for imported modules, separated like `PATH`.
+const MODULE_PATH_VAR: &str = "CONCORDE_PATH";
+/// What diagnostics call code given with `-e`.
+const EVAL_FILE_NAME: &str = "<-e>";
+
+fn run(cli: Cli) -> Result<(), TopError> {
+    if let Command::Help = cli.command {
+        print!("{USAGE}");
+        return Ok(());
+    }
+    if let Command::Check { file } = &cli.command {
+        let program = parse::parse_file(file)?;
+        program.v.warnings.iter().for_each(print_warning);
+        return Ok(());
+    }
+    let mut runtime = match &cli.prelude {
+        Prelude::Std => Runtime::new(),
+        Prelude::File(_) | Prelude::None => Runtime::without_prelude(),
+    };
+    runtime.set_exec_mode(cli.exec_mode);
+    runtime.set_warning_handler(print_warning);
+    if let Prelude::File(path) = &cli.prelude {
+        runtime.exec_prelude_file(path)?;
+    }
+    runtime.set_argv(cli.args);
+    // modules are found next to the main file first
+    let main_dir = match &cli.command {
+        Command::Run { file } => file.parent().filter(|dir| !dir.as_os_str().is_empty()),
+        _ => None,
+    };
+    runtime.add_module_path(main_dir.unwrap_or(Path::new(".")));
+    if let Some(module_paths) = env::var_os(MODULE_PATH_VAR) {
+        env::split_paths(&module_paths).for_each(|path| runtime.add_module_path(path));
+    }
+    match cli.command {
+        Command::Run { file } => runtime.exec_file(file),
+        // statements end at a newline
+        Command::Eval { code } => runtime.exec_source(&format!("{code}\n"), EVAL_FILE_NAME),
+        Command::Repl => repl::run(runtime),
+        Command::Check { .. } | Command::Help => unreachable!("handled before the runtime starts"),
+    }
+}
+
+fn print_warning(warning: &parse::Warning) {
+    eprint!("{}", Warning(warning));
 }
 
-fn main() {
-    if let Err(error) = run() {
-        eprintln!("{error}");
+fn main() -> ExitCode {
+    let cli = match Cli::parse(env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(error) => {
+            eprint!("error: {error}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    if let Err(error) = run(cli) {
+        if let Some(traceback) = error.traceback() {
+            eprint!("{traceback}");
+        }
+        eprint!("{}", Diagnostic(&error));
+        return ExitCode::FAILURE;
     }
+    ExitCode::SUCCESS
 }
@@ -1,17 +1,33 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::fs;
+use std::mem;
 use std::num::ParseFloatError;
+use std::rc::Rc;
 
+use num_bigint::{BigInt, ParseBigIntError};
+use num_rational::BigRational;
+use num_traits::{Num, ToPrimitive, Zero};
+use pest::error::InputLocation;
 use pest::iterators::{Pair, Pairs};
 use pest::{Parser, RuleType};
 use pest_derive::Parser;
 
-use crate::parse::Error::{ClassHasTwoInitializers, IllegalBinding, IllegalLValue, RuleMismatch};
+use crate::parse::Error::{
+    ClassHasTwoInitializers, DuplicateParameter, IllegalBinding, IllegalLValue,
+    IllegalParameterOrder, IllegalPattern, InvalidEscape, PositionalAfterKeyword, RuleMismatch,
+    ZeroDenominator,
+};
 use crate::runtime::builtin;
 use crate::types::{
-    Access, Array, Assignment, Binary, Binding, Block, Boolean, Break, Call, ClassDefinition,
-    Closure, Continue, Dictionary, Expression, ForIn, Ident, IfElse, Index, LValue, Literal,
-    MethodDefinition, Nil, Node, NodeMeta, NodeVariant, Number, Operator, Parameter, Path, Program,
-    Return, Statement, StringLit, TopError, Tuple, Unary, Use, Variable, WhileLoop,
+    with_parsed_file, Access, AlternativePattern, Array, ArrayPattern, Assignment,
+    BigInt as BigIntLit, Binary, Binding, Block, Boolean, Break, Call, ClassDefinition,
+    ClassPattern, Closure, Continue, Dictionary, DictionaryPattern, Expression, Float, ForIn,
+    Ident, IfElse, Import, Include, Index, Int, InterpolatedString, LValue, Literal, Match,
+    MatchCase, MethodDefinition, Nil, Node, NodeMeta, NodeVariant, Operator, Parameter,
+    ParameterKind, Path, Pattern, Program, Propagate, Raise, Rational, Rescue, Return, SourceFile,
+    Statement, StringLit, Super, TopError, TraitDefinition, Try, Tuple, TuplePattern, Unary, Use,
+    ValuePattern, Variable, WhileLoop, Wildcard,
 };
 
 #[derive(thiserror::Error, Debug)]
@@ -20,6 +36,17 @@ pub enum Error {
     Pest(#[from] Box<pest::error::Error<Rule>>),
     #[error("parse float error: {0}")]
     ParseFloat(#[from] ParseFloatError),
+    #[error("parse int error: {0}")]
+    ParseInt(#[from] ParseBigIntError),
+    #[error("rational literal with a zero denominator: '{literal}'")]
+    ZeroDenominator { literal: String },
+    #[error("escape is not a character: '{node}'")]
+    InvalidEscape { node: NodeMeta },
+    #[error("illegal pattern, {reason}: '{node}'")]
+    IllegalPattern {
+        reason: &'static str,
+        node: NodeMeta,
+    },
     #[error("illegal lvalue for assignment: {lvalue}")]
     IllegalLValue { lvalue: NodeMeta },
     #[error("rule mismatch: expected '{expected:?}', got '{actual:?}'")]
@@ -28,6 +55,71 @@ pub enum Error {
     ClassHasTwoInitializers { class: String },
     #[error("syntax error, illegal multi-variable binding expression: '{node}'")]
     IllegalBinding { node: NodeMeta },
+    #[error("parameters go in the order required, optional, *rest, keywords: '{node}'")]
+    IllegalParameterOrder { node: NodeMeta },
+    #[error("positional argument after keyword arguments: '{node}'")]
+    PositionalAfterKeyword { node: NodeMeta },
+    #[error("duplicate parameter '{name}': '{node}'")]
+    DuplicateParameter { name: String, node: NodeMeta },
+}
+
+impl Error {
+    /// The node the error is about, if it is about one.
+    pub fn node(&self) -> Option<&NodeMeta> {
+        match self {
+            IllegalLValue { lvalue: node }
+            | IllegalBinding { node }
+            | IllegalParameterOrder { node }
+            | PositionalAfterKeyword { node }
+            | DuplicateParameter { node, .. }
+            | InvalidEscape { node }
+            | IllegalPattern { node, .. } => Some(node),
+            _ => None,
+        }
+    }
+
+    /// The error's message, without the location that its `Display` ends with.
+    pub fn message(&self) -> String {
+        let mut message = self.to_string();
+        if let Some(node) = self.node() {
+            let suffix = [format!(": '{node}'"), format!(": {node}")]
+                .into_iter()
+                .find(|suffix| message.ends_with(suffix.as_str()));
+            if let Some(suffix) = suffix {
+                message.truncate(message.len() - suffix.len());
+            }
+        }
+        message
+    }
+
+    /// Whether parsing failed only because the source ended early, e.g. inside an open block.
+    pub fn is_unexpected_eoi(&self, source: &str) -> bool {
+        let Error::Pest(error) = self else {
+            return false;
+        };
+        match error.location {
+            InputLocation::Pos(pos) => pos >= source.len(),
+            InputLocation::Span((_, end)) => end >= source.len(),
+        }
+    }
+}
+
+/// Code that parses but is likely a mistake. The program keeps its warnings for whoever runs it
+/// to report.
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub message: &'static str,
+    pub node: NodeMeta,
+}
+
+thread_local! {
+    /// The warnings about the file being parsed.
+    static WARNINGS: RefCell<Vec<Warning>> = const { RefCell::new(Vec::new()) };
+}
+
+fn warn(message: &'static str, node: &NodeMeta) {
+    let node = node.clone();
+    WARNINGS.with_borrow_mut(|warnings| warnings.push(Warning { message, node }));
 }
 
 type Result<T, E = Error> = std::result::Result<T, E>;
@@ -49,8 +141,9 @@ impl<'a> PairsExt<'a, Rule> for Pairs<'a, Rule> {
 }
 
 pub fn parse_file(path: impl AsRef<std::path::Path>) -> Result<Node<Program>, TopError> {
+    let path = path.as_ref();
     let source = fs::read_to_string(path)?;
-    parse_source(&source)
+    parse_source(&source, &path.display().to_string())
 }
 
 pub fn pretty_print_pair<R: RuleType>(pair: Pair<R>) {
@@ -86,14 +179,27 @@ pub fn pretty_print_pair<R: RuleType>(pair: Pair<R>) {
     pp(pair, 0);
 }
 
-pub fn parse_source(source: &str) -> Result<Node<Program>, TopError> {
+/// Parses a program. `file_name` is what diagnostics call the file it came from.
+pub fn parse_source(source: &str, file_name: &str) -> Result<Node<Program>, TopError> {
     let pair = ConcordeParser::parse(Rule::program, source)
-        .map_err(|err| Error::Pest(Box::new(err)))?
+        .map_err(|err| Error::Pest(Box::new(err.with_path(file_name))))?
         .next()
         .unwrap();
     // pretty_print_pair(pair.clone());
-    let body = parse_block(pair.clone().into_inner().next().unwrap())?;
-    Ok(Program { body }.into_node(&pair))
+    let file = Rc::new(SourceFile {
+        name: file_name.to_string(),
+        text: source.to_string(),
+    });
+    with_parsed_file(file, || {
+        let previous = WARNINGS.take();
+        let body = parse_block(pair.clone().into_inner().next().unwrap());
+        let warnings = WARNINGS.replace(previous);
+        Ok(Program {
+            body: body?,
+            warnings,
+        }
+        .into_node(&pair))
+    })
 }
 
 fn parse_block(pair: Pair<Rule>) -> Result<Node<Block>> {
@@ -123,73 +229,54 @@ fn parse_statement(pair: Pair<Rule>) -> Result<Node<Statement>> {
         }
         Rule::class_def => {
             let mut inner = pair.clone().into_inner();
-            let name_pair = inner.next().unwrap();
-            let name = parse_ident(&name_pair)?;
-            let param_list = inner.next_if_rule(Rule::param_list);
-            let body = inner.next().unwrap();
-            let mut body = parse_block(body)?;
-            let fields;
-            if let Some(param_list) = param_list {
-                fields = parse_list(param_list.clone(), parse_param)?;
-                let has_init_method = body.v.statements.iter().any(|stmt| {
-                    let Statement::MethodDefinition(method_def) = &stmt.v else {
-                        return false;
-                    };
-                    method_def.v.name.v.name == builtin::method::init
+            let name = parse_ident(&inner.next().unwrap())?;
+            let fields = inner
+                .next_if_rule(Rule::param_list)
+                .map(parse_params)
+                .transpose()?
+                .unwrap_or_default();
+            let superclass = inner
+                .next_if_rule(Rule::path)
+                .map(parse_expression)
+                .transpose()?;
+            let body = parse_block(inner.next().unwrap())?;
+            // the initializer setting the fields is generated when the class is defined, as it
+            // also sets those of the superclass
+            let has_init_method = body.v.statements.iter().any(|stmt| {
+                let Statement::MethodDefinition(method_def) = &stmt.v else {
+                    return false;
+                };
+                method_def.v.name.v.name == builtin::method::init
+            });
+            if !fields.is_empty() && has_init_method {
+                return Err(ClassHasTwoInitializers {
+                    class: name.v.name.clone(),
                 });
-                if has_init_method {
-                    return Err(ClassHasTwoInitializers {
-                        class: name.v.name.clone(),
-                    });
-                }
-
-                let init_source = fields
-                    .iter()
-                    .map(|field| {
-                        let name = field.v.name.v.name.clone();
-                        let value = field
-                            .v
-                            .default
-                            .as_ref()
-                            .map(|default| default.meta.source.clone())
-                            .unwrap_or(name.clone());
-                        format!("self.{name} = {value}\n")
-                    })
-                    .collect::<Vec<String>>()
-                    .join("");
-                let block = ConcordeParser::parse(Rule::stmts, &init_source)
-                    .unwrap()
-                    .next()
-                    .unwrap();
-                let init_body = parse_block(block)?;
-                let parameters = fields
-                    .iter()
-                    .filter(|field| field.v.default.is_none())
-                    .cloned()
-                    .collect();
-                body.v.statements.push(
-                    Statement::MethodDefinition(
-                        MethodDefinition {
-                            name: Ident {
-                                name: builtin::method::init.into(),
-                            }
-                            .into_node(&name_pair),
-                            is_class_method: false,
-                            parameters,
-                            body: init_body,
-                        }
-                        .into_node(&param_list),
-                    )
-                    .into_node(&param_list),
-                )
-            } else {
-                fields = Vec::new();
             }
+            Ok(Statement::ClassDefinition(
+                ClassDefinition {
+                    name,
+                    fields,
+                    superclass,
+                    body,
+                }
+                .into_node(&pair),
+            )
+            .into_node(&pair))
+        }
+        Rule::trait_def => {
+            let [name, body] = pair.clone().into_inner().next_chunk().unwrap();
+            let name = parse_ident(&name)?;
+            let body = parse_block(body)?;
             Ok(
-                Statement::ClassDefinition(ClassDefinition { name, fields, body }.into_node(&pair))
+                Statement::TraitDefinition(TraitDefinition { name, body }.into_node(&pair))
                     .into_node(&pair),
             )
         }
+        Rule::include_stmt => {
+            let target = parse_expression(pair.clone().into_inner().next().unwrap())?;
+            Ok(Statement::Include(Include { target }.into_node(&pair)).into_node(&pair))
+        }
         Rule::method_def => {
             Ok(Statement::MethodDefinition(parse_method_def(pair.clone())?).into_node(&pair))
         }
@@ -230,6 +317,34 @@ fn parse_statement(pair: Pair<Rule>) -> Result<Node<Statement>> {
                 .transpose()?;
             Ok(Statement::Return(Return { retval }.into_node(&pair)).into_node(&pair))
         }
+        Rule::raise_stmt => {
+            let value = parse_expression(pair.clone().into_inner().next().unwrap())?;
+            Ok(Statement::Raise(Raise { value }.into_node(&pair)).into_node(&pair))
+        }
+        Rule::try_stmt => {
+            let mut inner = pair.clone().into_inner();
+            let body = parse_block(inner.next().unwrap())?;
+            let mut rescues = Vec::new();
+            let mut ensure = None;
+            for clause in inner {
+                match clause.as_rule() {
+                    Rule::rescue_clause => rescues.push(parse_rescue_clause(clause)?),
+                    Rule::ensure_clause => {
+                        ensure = Some(parse_block(clause.into_inner().next().unwrap())?);
+                    }
+                    rule => unreachable!("{:?}", rule),
+                }
+            }
+            Ok(Statement::Try(
+                Try {
+                    body,
+                    rescues,
+                    ensure,
+                }
+                .into_node(&pair),
+            )
+            .into_node(&pair))
+        }
         Rule::expr => Ok(Statement::Expression(parse_expression(pair.clone())?).into_node(&pair)),
         Rule::use_stmt => {
             let path = pair.clone().into_inner().next().unwrap();
@@ -237,20 +352,83 @@ fn parse_statement(pair: Pair<Rule>) -> Result<Node<Statement>> {
             let path = Path { components }.into_node(&path);
             Ok(Statement::Use(Use { path }.into_node(&pair)).into_node(&pair))
         }
+        Rule::import_stmt => {
+            let path = pair.clone().into_inner().next().unwrap();
+            let components = parse_list(path.clone(), parse_variable)?;
+            let path = Path { components }.into_node(&path);
+            Ok(Statement::Import(Import { path }.into_node(&pair)).into_node(&pair))
+        }
         rule => unreachable!("{:?}", rule),
     }
 }
 
+fn parse_rescue_clause(pair: Pair<Rule>) -> Result<Node<Rescue>> {
+    let mut inner = pair.clone().into_inner();
+    let binding = inner
+        .next_if_rule(Rule::variable)
+        .map(parse_variable)
+        .transpose()?;
+    let class = inner
+        .next_if_rule(Rule::path)
+        .map(parse_expression)
+        .transpose()?;
+    let body = parse_block(inner.next().unwrap())?;
+    Ok(Rescue {
+        binding,
+        class,
+        body,
+    }
+    .into_node(&pair))
+}
+
 fn parse_binding(pair: Pair<Rule>) -> Result<Vec<Node<Variable>>, Error> {
     parse_list(pair, parse_variable)
 }
 
+/// Parses a parameter list, which must put required parameters first, then optional ones, then
+/// at most one `*rest`, then keywords.
+fn parse_params(pair: Pair<Rule>) -> Result<Vec<Node<Parameter>>> {
+    let params = parse_list(pair, parse_param)?;
+    for pair in params.windows(2) {
+        let (before, after) = (pair[0].v.rank(), pair[1].v.rank());
+        if before > after || (before, after) == (2, 2) {
+            return Err(IllegalParameterOrder {
+                node: pair[1].meta.clone(),
+            });
+        }
+    }
+    check_unique_params(params.iter().map(|param| (&param.v.name.v.name, &param.meta)))?;
+    Ok(params)
+}
+
+/// Fails on a name given to two parameters, of which only one could be seen.
+fn check_unique_params<'a>(
+    params: impl IntoIterator<Item = (&'a String, &'a NodeMeta)>,
+) -> Result<()> {
+    let mut seen = HashSet::new();
+    for (name, node) in params {
+        if !seen.insert(name) {
+            return Err(DuplicateParameter {
+                name: name.clone(),
+                node: node.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
 fn parse_param(pair: Pair<Rule>) -> Result<Node<Parameter>> {
-    let mut inner = pair.clone().into_inner();
+    let first = pair.clone().into_inner().next().unwrap();
+    let (kind, mut inner) = match first.as_rule() {
+        Rule::rest_param => (ParameterKind::Rest, first.into_inner()),
+        Rule::keyword_param => (ParameterKind::Keyword, first.into_inner()),
+        _ => (ParameterKind::Positional, pair.clone().into_inner()),
+    };
     let name = inner.next_if_rule(Rule::ident).unwrap();
     let default = inner.next_if_rule(Rule::expr);
     Ok(Parameter {
         name: parse_ident(&name)?,
+        kind,
         default: default.map(|pair| parse_expression(pair)).transpose()?,
     }
     .into_node(&pair))
@@ -261,7 +439,7 @@ fn parse_method_def(pair: Pair<Rule>) -> Result<Node<MethodDefinition>> {
     let is_class_method = inner.next_if_rule(Rule::class_method_spec).is_some();
     let [name, param_list, body] = inner.next_chunk().unwrap();
     let name = parse_ident(&name)?;
-    let parameters = parse_list(param_list, parse_param)?;
+    let parameters = parse_params(param_list)?;
     let body = parse_stmts_or_short_stmt(body)?;
     Ok(MethodDefinition {
         is_class_method,
@@ -300,6 +478,13 @@ fn parse_operator(pair: &Pair<Rule>) -> Node<Operator> {
         Rule::op_star => Operator::Star,
         Rule::op_percent => Operator::Percent,
         Rule::op_slash => Operator::Slash,
+        Rule::op_slash_slash => Operator::SlashSlash,
+        Rule::op_pipe => Operator::Pipe,
+        Rule::op_ampersand => Operator::Ampersand,
+        Rule::op_caret => Operator::Caret,
+        Rule::op_shift_left => Operator::ShiftLeft,
+        Rule::op_shift_right => Operator::ShiftRight,
+        Rule::op_tilde => Operator::Tilde,
         Rule::op_minus_eq => Operator::MinusEqual,
         Rule::op_plus_eq => Operator::PlusEqual,
         Rule::op_star_eq => Operator::StarEqual,
@@ -350,6 +535,10 @@ fn parse_expression(pair: Pair<Rule>) -> Result<Node<Expression>> {
         | Rule::logical_and
         | Rule::equality
         | Rule::comparison
+        | Rule::bit_or
+        | Rule::bit_xor
+        | Rule::bit_and
+        | Rule::shift
         | Rule::term
         | Rule::factor => parse_left_assoc(pair),
         Rule::logical_not | Rule::unary_minus => {
@@ -367,6 +556,15 @@ fn parse_expression(pair: Pair<Rule>) -> Result<Node<Expression>> {
             }
             Ok(expr)
         }
+        Rule::propagate => {
+            let mut inner = pair.clone().into_inner();
+            let value = parse_expression(inner.next().unwrap())?;
+            if inner.next().is_none() {
+                return Ok(value);
+            }
+            let value = Box::new(value);
+            Ok(Expression::Propagate(Propagate { value }.into_node(&pair)).into_node(&pair))
+        }
         Rule::closure => parse_closure(pair),
         Rule::index => parse_index(pair),
         Rule::access => parse_access(pair),
@@ -375,6 +573,15 @@ fn parse_expression(pair: Pair<Rule>) -> Result<Node<Expression>> {
             let literal = parse_literal(pair.clone())?;
             Ok(Expression::Literal(literal).into_node(&pair))
         }
+        Rule::super_ref => {
+            let method = pair
+                .clone()
+                .into_inner()
+                .next()
+                .map(|ident| parse_ident(&ident))
+                .transpose()?;
+            Ok(Expression::Super(Super { method }.into_node(&pair)).into_node(&pair))
+        }
         Rule::path => {
             let mut components = parse_list(pair.clone(), parse_variable)?;
             if components.len() > 1 {
@@ -383,6 +590,7 @@ fn parse_expression(pair: Pair<Rule>) -> Result<Node<Expression>> {
                 Ok(Expression::Variable(components.pop().unwrap()).into_node(&pair))
             }
         }
+        Rule::match_expr => parse_match(pair),
         Rule::if_else => {
             let mut inner = pair.clone().into_inner();
             let [condition, then_body] = inner.next_chunk().unwrap();
@@ -406,21 +614,168 @@ fn parse_expression(pair: Pair<Rule>) -> Result<Node<Expression>> {
     }
 }
 
+/// Parses `match`, with a warning about each case that an earlier one without a guard leaves
+/// nothing to match.
+fn parse_match(pair: Pair<Rule>) -> Result<Node<Expression>> {
+    let mut inner = pair.clone().into_inner();
+    let subject = Box::new(parse_expression(inner.next().unwrap())?);
+    let cases: Vec<_> = inner.map(parse_match_case).try_collect()?;
+    for (i, case) in cases.iter().enumerate() {
+        let shadowed = cases[..i].iter().any(|earlier| {
+            earlier.v.guard.is_none() && earlier.v.pattern.v.covers(&case.v.pattern.v)
+        });
+        if shadowed {
+            let message = "unreachable case, an earlier case matches everything it does";
+            warn(message, &case.meta);
+        }
+    }
+    Ok(Expression::Match(Match { subject, cases }.into_node(&pair)).into_node(&pair))
+}
+
+fn parse_match_case(pair: Pair<Rule>) -> Result<Node<MatchCase>> {
+    let mut inner = pair.clone().into_inner();
+    let pattern = parse_pattern(inner.next().unwrap())?;
+    let guard = inner
+        .next_if_rule(Rule::logical_or)
+        .map(parse_expression)
+        .transpose()?;
+    let body = parse_stmts_or_short_stmt(inner.next().unwrap())?;
+    Ok(MatchCase {
+        pattern,
+        guard,
+        body,
+    }
+    .into_node(&pair))
+}
+
+/// Parses a pattern, which may bind a name only once, and whose alternatives must all bind the
+/// same names.
+fn parse_pattern(pair: Pair<Rule>) -> Result<Node<Pattern>> {
+    let pattern = match pair.as_rule() {
+        Rule::pattern => {
+            let mut alternatives = parse_list(pair.clone(), parse_pattern)?;
+            if alternatives.len() == 1 {
+                return Ok(alternatives.pop().unwrap());
+            }
+            let mut names = alternatives[0].v.bound_names();
+            names.sort();
+            for alternative in &alternatives[1..] {
+                let mut alternative_names = alternative.v.bound_names();
+                alternative_names.sort();
+                if alternative_names != names {
+                    return Err(IllegalPattern {
+                        reason: "alternatives bind different names",
+                        node: (&pair).into(),
+                    });
+                }
+            }
+            Pattern::AlternativePattern(AlternativePattern { alternatives }.into_node(&pair))
+        }
+        Rule::variable if pair.as_str() == "_" => Pattern::Wildcard(Wildcard {}.into_node(&pair)),
+        // a capitalized name such as `None` stands for its value rather than binding a variable
+        Rule::variable if pair.as_str().starts_with(char::is_uppercase) => {
+            let variable = parse_variable(pair.clone())?;
+            Pattern::ValuePattern(ValuePattern { variable }.into_node(&pair))
+        }
+        Rule::variable => Pattern::Variable(parse_variable(pair.clone())?),
+        Rule::literal_pattern => Pattern::Literal(parse_literal(pair.clone())?),
+        Rule::tuple_pattern => {
+            let items = parse_list(pair.clone(), parse_pattern)?;
+            Pattern::TuplePattern(TuplePattern { items }.into_node(&pair))
+        }
+        Rule::array_pattern => {
+            let (mut before, mut rest, mut after) = (Vec::new(), None, Vec::new());
+            for element in pair.clone().into_inner() {
+                if element.as_rule() == Rule::rest_pattern {
+                    if rest.is_some() {
+                        return Err(IllegalPattern {
+                            reason: "an array pattern has one *rest at most",
+                            node: (&pair).into(),
+                        });
+                    }
+                    rest = Some(Box::new(parse_pattern(
+                        element.into_inner().next().unwrap(),
+                    )?));
+                } else if rest.is_none() {
+                    before.push(parse_pattern(element)?);
+                } else {
+                    after.push(parse_pattern(element)?);
+                }
+            }
+            Pattern::ArrayPattern(
+                ArrayPattern {
+                    before,
+                    rest,
+                    after,
+                }
+                .into_node(&pair),
+            )
+        }
+        Rule::dict_pattern => {
+            let entries: Vec<_> = pair
+                .clone()
+                .into_inner()
+                .array_chunks()
+                .map(|[key, value]| {
+                    let key = match key.as_rule() {
+                        Rule::dict_name => {
+                            let name = parse_ident(&key.clone().into_inner().next().unwrap())?;
+                            let string = StringLit { value: name.v.name }.into_node(&key);
+                            Literal::StringLit(string).into_node(&key)
+                        }
+                        _ => parse_literal(key)?,
+                    };
+                    Ok::<_, Error>((key, parse_pattern(value)?))
+                })
+                .try_collect()?;
+            Pattern::DictionaryPattern(DictionaryPattern { entries }.into_node(&pair))
+        }
+        Rule::class_pattern => {
+            let mut inner = pair.clone().into_inner();
+            let class = parse_expression(inner.next().unwrap())?;
+            let fields = inner.map(parse_pattern).try_collect()?;
+            Pattern::ClassPattern(ClassPattern { class, fields }.into_node(&pair))
+        }
+        rule => unreachable!("{rule:?} in a pattern"),
+    };
+    let names = pattern.bound_names();
+    if names
+        .iter()
+        .enumerate()
+        .any(|(i, name)| names[..i].contains(name))
+    {
+        return Err(IllegalPattern {
+            reason: "a name is bound twice",
+            node: (&pair).into(),
+        });
+    }
+    Ok(pattern.into_node(&pair))
+}
+
 fn parse_call(pair: &Pair<Rule>) -> Result<Node<Expression>> {
     assert_rule(pair, Rule::call)?;
     let mut inner = pair.clone().into_inner();
     let mut expr = parse_expression(inner.next().unwrap())?;
     for arg_list in inner {
-        let arguments = arg_list
-            .into_inner()
-            .next()
-            .map(|expr_list| parse_list(expr_list.clone(), parse_expression))
-            .transpose()?
-            .unwrap_or_default();
+        let mut arguments = Vec::new();
+        let mut keywords = Vec::new();
+        for arg in arg_list.into_inner() {
+            if arg.as_rule() == Rule::keyword_arg {
+                let [name, value] = arg.into_inner().next_chunk().unwrap();
+                keywords.push((parse_ident(&name)?, parse_expression(value)?));
+            } else if keywords.is_empty() {
+                arguments.push(parse_expression(arg)?);
+            } else {
+                return Err(PositionalAfterKeyword {
+                    node: NodeMeta::from(&arg),
+                });
+            }
+        }
         expr = Expression::Call(
             Call {
                 target: Box::new(expr),
                 arguments,
+                keywords,
             }
             .into_node(pair),
         )
@@ -465,8 +820,108 @@ fn parse_access(pair: Pair<Rule>) -> Result<Node<Expression>> {
     Ok(expr)
 }
 
+/// The value of an `int` literal, which may be in hex or binary and have `_` between digits.
+fn parse_int(literal: &str) -> Result<BigInt> {
+    let digits = literal.replace('_', "");
+    let (sign, digits) = match digits.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", digits.as_str()),
+    };
+    let (radix, digits) = if let Some(digits) = digits.strip_prefix("0x") {
+        (16, digits)
+    } else if let Some(digits) = digits.strip_prefix("0b") {
+        (2, digits)
+    } else {
+        (10, digits)
+    };
+    Ok(BigInt::from_str_radix(&format!("{sign}{digits}"), radix)?)
+}
+
+/// The value of a `rational` literal: a fraction `1/3r` or a decimal `0.75r`, which is exact.
+fn parse_rational(literal: &str) -> Result<BigRational> {
+    let digits = literal.trim_end_matches('r').replace('_', "");
+    let value = if let Some((numerator, denominator)) = digits.split_once('/') {
+        let denominator: BigInt = denominator.parse()?;
+        if denominator.is_zero() {
+            return Err(ZeroDenominator {
+                literal: literal.into(),
+            });
+        }
+        BigRational::new(numerator.parse()?, denominator)
+    } else if let Some((whole, fraction)) = digits.split_once('.') {
+        let scale = BigInt::from(10).pow(fraction.len() as u32);
+        BigRational::new(format!("{whole}{fraction}").parse()?, scale)
+    } else {
+        BigRational::from_integer(digits.parse()?)
+    };
+    Ok(value)
+}
+
+/// A string literal, or an interpolated string if it has any `{expr}` in it. A line break
+/// right after the opening `"""` is not part of the string.
+fn parse_string(pair: Pair<Rule>) -> Result<Node<Literal>> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    for (i, part) in pair.clone().into_inner().enumerate() {
+        match part.as_rule() {
+            Rule::string_text | Rule::string_inner_single => text.push_str(part.as_str()),
+            Rule::triple_string_text if i == 0 => {
+                let part_text = part.as_str();
+                let part_text = part_text
+                    .strip_prefix('\n')
+                    .or_else(|| part_text.strip_prefix("\r\n"))
+                    .unwrap_or(part_text);
+                text.push_str(part_text);
+            }
+            Rule::triple_string_text => text.push_str(part.as_str()),
+            Rule::string_escape => text.push(parse_escape(&part)?),
+            Rule::interpolation => {
+                if !text.is_empty() {
+                    parts.push(string_part(mem::take(&mut text), &pair));
+                }
+                parts.push(parse_expression(part.into_inner().next().unwrap())?);
+            }
+            rule => unreachable!("{rule:?} in a string"),
+        }
+    }
+    if parts.is_empty() {
+        return Ok(Literal::StringLit(StringLit { value: text }.into_node(&pair)).into_node(&pair));
+    }
+    if !text.is_empty() {
+        parts.push(string_part(text, &pair));
+    }
+    Ok(Literal::InterpolatedString(InterpolatedString { parts }.into_node(&pair)).into_node(&pair))
+}
+
+fn string_part(value: String, pair: &Pair<Rule>) -> Node<Expression> {
+    let string = StringLit { value }.into_node(pair);
+    Expression::Literal(Literal::StringLit(string).into_node(pair)).into_node(pair)
+}
+
+/// The character a `string_escape` such as `\n` or `\u{e9}` stands for.
+fn parse_escape(pair: &Pair<Rule>) -> Result<char> {
+    let escape = &pair.as_str()[1..];
+    let char = match escape {
+        "n" => '\n',
+        "t" => '\t',
+        "r" => '\r',
+        "0" => '\0',
+        _ => match escape.strip_prefix("u{") {
+            Some(code) => u32::from_str_radix(code.trim_end_matches('}'), 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| InvalidEscape { node: pair.into() })?,
+            None => escape.chars().next().unwrap(),
+        },
+    };
+    Ok(char)
+}
+
 fn parse_literal(pair: Pair<Rule>) -> Result<Node<Literal>> {
-    assert_rule(&pair, Rule::literal)?;
+    // a literal pattern takes the same literals, but not collections
+    if pair.as_rule() != Rule::literal_pattern {
+        assert_rule(&pair, Rule::literal)?;
+    }
     let pair = pair.into_inner().next().unwrap();
     let rule = pair.as_rule();
     match rule {
@@ -478,17 +933,24 @@ fn parse_literal(pair: Pair<Rule>) -> Result<Node<Literal>> {
             .into_node(&pair),
         )
         .into_node(&pair)),
-        Rule::number => {
-            let value: f64 = pair.as_str().parse()?;
-            Ok(Literal::Number(Number { value }.into_node(&pair)).into_node(&pair))
+        // an `int` too large for 64 bits is a `BigInt` from the start
+        Rule::int => {
+            let value = parse_int(pair.as_str())?;
+            let literal = match value.to_i64() {
+                Some(value) => Literal::Int(Int { value }.into_node(&pair)),
+                None => Literal::BigInt(BigIntLit { value }.into_node(&pair)),
+            };
+            Ok(literal.into_node(&pair))
         }
-        Rule::string => Ok(Literal::StringLit(
-            StringLit {
-                value: pair.clone().into_inner().next().unwrap().as_str().into(),
-            }
-            .into_node(&pair),
-        )
-        .into_node(&pair)),
+        Rule::rational => {
+            let value = parse_rational(pair.as_str())?;
+            Ok(Literal::Rational(Rational { value }.into_node(&pair)).into_node(&pair))
+        }
+        Rule::float => {
+            let value: f64 = pair.as_str().replace('_', "").parse()?;
+            Ok(Literal::Float(Float { value }.into_node(&pair)).into_node(&pair))
+        }
+        Rule::string => parse_string(pair),
         Rule::array => {
             let elements = pair
                 .clone()
@@ -505,7 +967,13 @@ fn parse_literal(pair: Pair<Rule>) -> Result<Node<Literal>> {
                 .into_inner()
                 .array_chunks()
                 .map(|[key, value]| {
-                    let key = parse_ident(&key)?;
+                    let key = match key.as_rule() {
+                        Rule::dict_name => {
+                            let name = parse_ident(&key.clone().into_inner().next().unwrap())?;
+                            string_part(name.v.name, &key)
+                        }
+                        _ => parse_expression(key)?,
+                    };
                     let value = parse_expression(value)?;
                     Ok::<_, Error>((key, value))
                 })
@@ -586,6 +1054,7 @@ fn assert_rule(pair: &Pair<Rule>, expected: Rule) -> Result<()> {
 fn parse_closure(pair: Pair<Rule>) -> Result<Node<Expression>> {
     let [binding, body] = pair.clone().into_inner().next_chunk().unwrap();
     let binding = parse_list(binding, parse_variable)?;
+    check_unique_params(binding.iter().map(|var| (&var.v.ident.v.name, &var.meta)))?;
     let body = parse_stmts_or_short_stmt(body)?;
     Ok(Expression::Closure(Closure { binding, body }.into_node(&pair)).into_node(&pair))
 }
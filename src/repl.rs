@@ -0,0 +1,76 @@
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+use concorde::diagnostic::Diagnostic;
+use concorde::parse;
+use concorde::runtime::builtin;
+use concorde::types::{Node, Program};
+use concorde::{Runtime, TopError};
+
+const PROMPT: &str = ">> ";
+const CONTINUATION_PROMPT: &str = ".. ";
+const REPL_FILE_NAME: &str = "<stdin>";
+
+pub fn run(mut runtime: Runtime) -> Result<(), TopError> {
+    let mut editor = DefaultEditor::new().map_err(readline_to_io_error)?;
+    let mut source = String::new();
+    loop {
+        let prompt = if source.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION_PROMPT
+        };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => {
+                source.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(error) => return Err(readline_to_io_error(error).into()),
+        };
+        if source.is_empty() && line.trim().is_empty() {
+            continue;
+        }
+        source.push_str(&line);
+        source.push('\n');
+        let program = match parse::parse_source(&source, REPL_FILE_NAME) {
+            Ok(program) => program,
+            Err(TopError::Parse(error)) if error.is_unexpected_eoi(&source) => continue,
+            Err(error) => {
+                eprint!("{}", Diagnostic(&error));
+                let _ = editor.add_history_entry(source.trim_end());
+                source.clear();
+                continue;
+            }
+        };
+        let _ = editor.add_history_entry(source.trim_end());
+        source.clear();
+        if let Err(error) = eval_and_print(&mut runtime, program) {
+            if let Some(traceback) = error.traceback() {
+                eprint!("{traceback}");
+            }
+            eprint!("{}", Diagnostic(&error));
+            runtime.unwind_to_global();
+        }
+    }
+}
+
+fn eval_and_print(runtime: &mut Runtime, program: Node<Program>) -> Result<(), TopError> {
+    let result = runtime.eval_program(program)?;
+    if result == runtime.nil() {
+        return Ok(());
+    }
+    let repr = runtime.call_instance_method(result, builtin::method::repr, None, None)?;
+    if let Some(repr) = repr.borrow().string() {
+        println!("{repr}");
+    }
+    Ok(())
+}
+
+fn readline_to_io_error(error: ReadlineError) -> std::io::Error {
+    match error {
+        ReadlineError::Io(error) => error,
+        error => std::io::Error::other(error),
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt::{Display, Formatter};
+
+use crate::runtime::object::{Method, ObjectRef, Param};
+use crate::runtime::Error::{ArityMismatch, KeywordArgument};
+use crate::runtime::{Error, Result, Runtime};
+
+/// How many positional arguments a method accepts.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Arity {
+    pub min: usize,
+    /// `None` if a `*rest` parameter takes any number beyond `min`.
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub fn exactly(count: usize) -> Self {
+        Arity {
+            min: count,
+            max: Some(count),
+        }
+    }
+
+    pub fn of(params: &[Param]) -> Self {
+        let mut arity = Arity::exactly(0);
+        for param in params {
+            match param {
+                Param::Positional(_) => {
+                    arity.min += 1;
+                    arity.max = arity.max.map(|max| max + 1);
+                }
+                Param::Optional(..) => arity.max = arity.max.map(|max| max + 1),
+                Param::Vararg(_) => arity.max = None,
+                Param::Keyword(..) => {}
+            }
+        }
+        arity
+    }
+
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+impl Display for Arity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{max}"),
+            Some(max) => write!(f, "{} to {max}", self.min),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
+}
+
+fn keyword_error(method: &Method, keyword: String, reason: &'static str) -> Error {
+    KeywordArgument {
+        class_name: method.class_name(),
+        method_name: method.name.clone(),
+        keyword,
+        reason,
+    }
+}
+
+impl Runtime {
+    /// Matches the arguments of a call to the parameters of a user method, giving a value for
+    /// each parameter. Those left out whose default is still to be evaluated get `None`.
+    pub(super) fn bind_arguments(
+        &mut self,
+        method: &Method,
+        arguments: Vec<ObjectRef>,
+        keywords: Vec<(String, ObjectRef)>,
+    ) -> Result<Vec<Option<ObjectRef>>> {
+        let arity = Arity::of(&method.params);
+        if !arity.accepts(arguments.len()) {
+            return Err(ArityMismatch {
+                class_name: method.class_name(),
+                method_name: method.name.clone(),
+                expected: arity,
+                actual: arguments.len(),
+            });
+        }
+        let mut arguments = arguments.into_iter();
+        let mut bound = Vec::with_capacity(method.params.len());
+        for param in &method.params {
+            let value = match param {
+                Param::Positional(_) | Param::Optional(..) => arguments.next(),
+                Param::Vararg(_) => Some(self.create_array(arguments.by_ref().collect())),
+                Param::Keyword(..) => None,
+            };
+            bound.push(value);
+        }
+        for (keyword, value) in keywords {
+            let index = method
+                .params
+                .iter()
+                .position(|param| matches!(param, Param::Keyword(name, _) if *name == keyword));
+            let Some(index) = index else {
+                return Err(keyword_error(method, keyword, "unknown"));
+            };
+            if bound[index].replace(value).is_some() {
+                return Err(keyword_error(method, keyword, "repeated"));
+            }
+        }
+        for (param, value) in method.params.iter().zip(&bound) {
+            if let (Param::Keyword(name, None), None) = (param, value) {
+                return Err(keyword_error(method, name.clone(), "missing"));
+            }
+        }
+        Ok(bound)
+    }
+
+    /// Evaluates the defaults of the parameters that `bind_arguments` left without a value. This
+    /// happens in the method's frame, so a default can refer to the parameters before it.
+    pub(super) fn bind_defaults(
+        &mut self,
+        method: &Method,
+        bound: &[Option<ObjectRef>],
+    ) -> Result<()> {
+        for (slot, (param, value)) in method.params.iter().zip(bound).enumerate() {
+            let Some(default) = param.default().filter(|_| value.is_none()) else {
+                continue;
+            };
+            let value = self.eval(default.clone())?;
+            let frame = self.stack.last_mut().expect("no scope");
+            // the VM keeps parameters in the first slots, the tree-walker by name
+            if frame.slot_names.is_empty() {
+                frame.bind_variable(param.name().into(), value);
+            } else {
+                frame.bind_slot(slot, value);
+            }
+        }
+        Ok(())
+    }
+}
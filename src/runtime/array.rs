@@ -0,0 +1,131 @@
+use std::cell::{Ref, RefMut};
+use std::cmp::Ordering;
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::runtime::number::Number;
+use crate::runtime::object::{receiver_primitive, receiver_primitive_mut, Object, ObjectRef};
+use crate::runtime::Error::TypeMismatch;
+use crate::runtime::{builtin, Result, Runtime};
+
+/// The elements of the `Array` a method is called on.
+pub(super) fn array_receiver(this: &ObjectRef) -> Result<Ref<'_, Vec<ObjectRef>>> {
+    receiver_primitive(this, builtin::class::Array, Object::array)
+}
+
+/// Like `array_receiver`, for a method that changes the elements.
+pub(super) fn array_receiver_mut(this: &ObjectRef) -> Result<RefMut<'_, Vec<ObjectRef>>> {
+    receiver_primitive_mut(this, builtin::class::Array, Object::array_mut)
+}
+
+/// The position `index` refers to among `len` elements, counting back from the end when it is
+/// negative, or `None` when there is no such element.
+pub(super) fn element_position(index: i64, len: usize) -> Option<usize> {
+    let position = from_end(index, len);
+    (0..len as i64)
+        .contains(&position)
+        .then_some(position as usize)
+}
+
+/// The positions from `start` up to `finish`, each counting back from the end when negative
+/// and clamped to the `len` elements there are. Without a `finish` the slice runs to the end.
+pub(super) fn slice_range(start: i64, finish: Option<i64>, len: usize) -> Range<usize> {
+    let clamp = |index: i64| from_end(index, len).clamp(0, len as i64) as usize;
+    let start = clamp(start);
+    let finish = finish.map_or(len, clamp).max(start);
+    start..finish
+}
+
+fn from_end(index: i64, len: usize) -> i64 {
+    if index < 0 {
+        index.saturating_add(len as i64)
+    } else {
+        index
+    }
+}
+
+/// The bounds of `index` if it is a `Range` of the prelude, which slices an array.
+pub(super) fn range_bounds(runtime: &Runtime, index: &ObjectRef) -> Option<(i64, Option<i64>)> {
+    let index_ref = index.borrow();
+    if !Rc::ptr_eq(&index_ref.__class__(), &runtime.builtins.Range) {
+        return None;
+    }
+    let start = index_ref.get_property(builtin::property::start)?;
+    let finish = index_ref.get_property(builtin::property::finish)?;
+    let start = start.borrow().int()?;
+    let finish = finish.borrow().int();
+    Some((start, finish))
+}
+
+/// Sorts `items` stably by what `compare` says of each pair. Comparisons run concorde code,
+/// which may fail or disagree with itself, so this does not use the standard library's sort,
+/// which may panic in the latter case.
+pub(super) fn merge_sort<T>(
+    mut items: Vec<T>,
+    compare: &mut impl FnMut(&T, &T) -> Result<Ordering>,
+) -> Result<Vec<T>> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+    let right = items.split_off(items.len() / 2);
+    let left = merge_sort(items, compare)?;
+    let right = merge_sort(right, compare)?;
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+    while let (Some(first), Some(second)) = (left.peek(), right.peek()) {
+        // ties are taken from the left, which keeps equal items in order
+        let next = if compare(second, first)? == Ordering::Less {
+            right.next()
+        } else {
+            left.next()
+        };
+        merged.extend(next);
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+impl Runtime {
+    /// How two values order by `<`, which is how `sort` orders them.
+    pub(super) fn compare_values(&mut self, a: &ObjectRef, b: &ObjectRef) -> Result<Ordering> {
+        {
+            let (a_ref, b_ref) = (a.borrow(), b.borrow());
+            if let (Some(a), Some(b)) = (a_ref.number(), b_ref.number()) {
+                return Ok(a.compare(&b).unwrap_or(Ordering::Equal));
+            }
+            if let (Some(a), Some(b)) = (a_ref.string(), b_ref.string()) {
+                return Ok(a.cmp(b));
+            }
+        }
+        Ok(if self.is_less(a, b)? {
+            Ordering::Less
+        } else if self.is_less(b, a)? {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        })
+    }
+
+    /// How a comparator closure orders two values: by the sign of the number it returns.
+    pub(super) fn compare_with(
+        &mut self,
+        comparator: &ObjectRef,
+        a: &ObjectRef,
+        b: &ObjectRef,
+    ) -> Result<Ordering> {
+        let result = self.call_callable(comparator.clone(), vec![a.clone(), b.clone()])?;
+        let result_ref = result.borrow();
+        let order = result_ref.number().ok_or_else(|| TypeMismatch {
+            class: result_ref.__class__().borrow().__name__().unwrap(),
+            expected: builtin::class::Number.into(),
+        })?;
+        Ok(order.compare(&Number::Int(0)).unwrap_or(Ordering::Equal))
+    }
+
+    fn is_less(&mut self, a: &ObjectRef, b: &ObjectRef) -> Result<bool> {
+        let less =
+            self.call_instance_method(a.clone(), builtin::op::__lt__, Some(b.clone()), None)?;
+        Ok(self.is_truthy(&less))
+    }
+}
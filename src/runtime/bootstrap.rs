@@ -1,8 +1,25 @@
-use std::collections::HashMap;
-
-use crate::runtime::object::{MethodBody, MethodReceiver, Object, ObjectRef, Param, Primitive};
-use crate::runtime::Error::{ArityMismatch, IllegalConstructorCall, Index, TypeMismatch};
-use crate::runtime::{builtin, Result, Runtime, StackFrame};
+use std::cell::Ref;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::Zero;
+
+use crate::runtime::array::{array_receiver, array_receiver_mut, element_position, merge_sort, range_bounds, slice_range};
+use crate::runtime::dictionary::{hash_of, hash_table_receiver, Dictionary};
+use crate::runtime::gc::GcStats;
+use crate::runtime::number::{int_operand, integer_operand, number_operand, Number};
+use crate::runtime::string::{string_operand, string_receiver};
+use crate::runtime::object::{
+    receiver_primitive, receiver_primitive_mut, MethodBody, MethodReceiver, Object, ObjectRef, Param, Primitive,
+};
+use crate::runtime::Error::{
+    Arithmetic, ArityMismatch, IllegalConstructorCall, Index, InvalidArgument, TypeMismatch,
+    Unhashable,
+};
+use crate::runtime::{builtin, Arity, Error, Result, Runtime, StackFrame};
+use crate::types::{Ident, NodeMeta, NodeVariant, Parameter, ParameterKind};
 
 macro define_builtins(
     $Builtins:ident {
@@ -35,12 +52,18 @@ macro replace_expr($_t:tt $sub:expr) {
 
 macro count($($tts:tt)*) {0usize $(+ replace_expr!($tts 1usize))*}
 
+macro method_name {
+    ($name:ident) => { stringify!($name) },
+    // names that are not identifiers, like `is_a?`, are given after `as`
+    ($name:ident $alias:literal) => { $alias },
+}
+
 macro define_system_methods(
     [runtime=$runtime:ident, method_name=$method_name:ident, this=$this:ident]
     $(
         impl $class:expr => {
             $(
-                fn $name:ident($($param:ident),*) $body:tt
+                fn $name:ident($($param:ident),*) $(as $alias:literal)? $body:block
             )*
         }
     )+
@@ -57,15 +80,15 @@ macro define_system_methods(
                 fn $name() {}
                 class_mut.define_method(
                     MethodReceiver::Instance,
-                    stringify!($name).into(),
+                    method_name!($name $($alias)?).into(),
                     params,
-                    MethodBody::System(|$runtime, $this, $method_name, args| {
+                    MethodBody::system(|$runtime, $this, $method_name, args| {
                         let arg_count = args.len();
                         let Ok([$($param,)*]) = <[ObjectRef; count!($($param)*)]>::try_from(args) else {
                             return Err(ArityMismatch {
                                 class_name: $this.borrow().__class__().borrow().__name__().unwrap(),
                                 method_name: $method_name.into(),
-                                expected: count!($($param)*),
+                                expected: Arity::exactly(count!($($param)*)),
                                 actual: arg_count,
                             });
                         };
@@ -79,19 +102,43 @@ macro define_system_methods(
 
 define_builtins!(Builtins {
     Class,
+    Trait,
     Object,
     String,
     NilClass,
     Bool,
     Number,
+    Int,
+    BigInt,
+    Rational,
+    Float,
     Closure,
     Method,
     Array,
     Tuple,
     Dictionary,
+    Set,
     IO,
     Main,
     Core,
+    Exception,
+    RuntimeError,
+    TypeError,
+    NameError,
+    NoMethodError,
+    ArgumentError,
+    IndexError,
+    ImportError,
+    ArithmeticError,
+    Option,
+    Some,
+    NoneClass,
+    Result,
+    Ok,
+    Err,
+    None,
+    // defined by the prelude
+    Range,
     bool_true,
     bool_false,
     nil,
@@ -112,6 +159,116 @@ fn object_list_to_string(
     Ok(strings.join(", "))
 }
 
+/// The argument of a system method that takes at most one.
+fn optional_argument(
+    this: &ObjectRef,
+    method_name: String,
+    args: Vec<ObjectRef>,
+) -> Result<Option<ObjectRef>> {
+    if args.len() > 1 {
+        return Err(ArityMismatch {
+            class_name: this.borrow().__class__().borrow().__name__().unwrap(),
+            method_name,
+            expected: Arity {
+                min: 0,
+                max: Some(1),
+            },
+            actual: args.len(),
+        });
+    }
+    Ok(args.into_iter().next())
+}
+
+/// The most elements `*` may give an array, or bytes `repeat` may give a string.
+const MAX_REPEAT_LEN: usize = 1 << 24;
+
+/// The length of `count` copies of something `len` long, which fails rather than try to
+/// allocate more than `MAX_REPEAT_LEN`.
+fn repeated_len(len: usize, count: i64) -> Result<usize> {
+    let count = usize::try_from(count).map_err(|_| InvalidArgument {
+        reason: "negative repeat count",
+    })?;
+    len.checked_mul(count)
+        .filter(|&total| total <= MAX_REPEAT_LEN)
+        .ok_or(InvalidArgument {
+            reason: "repeated too many times",
+        })
+}
+
+/// The argument of a collection method that takes another collection of its class.
+fn same_class_operand(this: &ObjectRef, other: &ObjectRef) -> Result<ObjectRef> {
+    let (class, other_class) = (this.borrow().__class__(), other.borrow().__class__());
+    if other_class != class {
+        return Err(TypeMismatch {
+            class: other_class.borrow().__name__().unwrap(),
+            expected: class.borrow().__name__().unwrap(),
+        });
+    }
+    Ok(other.clone())
+}
+
+/// The entries of a dictionary, copied out so that it is not borrowed while they are used.
+fn dictionary_entries(dictionary: &ObjectRef) -> Result<Vec<(ObjectRef, ObjectRef)>> {
+    let entries = hash_table_receiver(dictionary)?;
+    Ok(entries
+        .iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect())
+}
+
+fn set_elements(set: &ObjectRef) -> Result<Vec<ObjectRef>> {
+    let elements = hash_table_receiver(set)?;
+    Ok(elements.iter().map(|(element, _)| element.clone()).collect())
+}
+
+/// Whether `other` is a `class`, which is `Some`, `Ok` or `Err`, holding a value equal to that of
+/// `this` in `field`.
+fn holds_equal(
+    runtime: &mut Runtime,
+    this: &ObjectRef,
+    other: &ObjectRef,
+    class: &ObjectRef,
+    field: &str,
+) -> Result<bool> {
+    if !runtime.inherits_from(&other.borrow().__class__(), class) {
+        return Ok(false);
+    }
+    let held = |object: &ObjectRef| object.borrow().get_property(field);
+    match (held(this), held(other)) {
+        (Some(value), Some(other_value)) => runtime.values_equal(&value, &other_value),
+        _ => Ok(false),
+    }
+}
+
+/// How `Some`, `Ok` and `Err` show: their class name and the `repr` of what they hold.
+fn holder_to_s(runtime: &mut Runtime, this: &ObjectRef, field: &str) -> Result<ObjectRef> {
+    let class = this.borrow().__class__().borrow().__name__().unwrap();
+    let held = this.borrow().get_property(field).unwrap_or_else(|| runtime.nil());
+    let held = object_list_to_string(runtime, [held])?;
+    Ok(runtime.create_string(format!("{class}({held})")))
+}
+
+/// The error of hashing a mutable collection, which would file it under a hash that its later
+/// contents disagree with.
+fn unhashable(this: &ObjectRef) -> Error {
+    Unhashable {
+        class: this.borrow().__class__().borrow().__name__().unwrap(),
+    }
+}
+
+/// The items of the `Tuple` a method is called on.
+fn tuple_receiver(this: &ObjectRef) -> Result<Ref<'_, Vec<ObjectRef>>> {
+    receiver_primitive(this, builtin::class::Tuple, Object::array)
+}
+
+fn is_identifier(string: &str) -> bool {
+    let mut chars = string.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_alphabetic() || first == '_')
+        && chars.all(|char| char.is_alphanumeric() || char == '_')
+}
+
 #[allow(non_snake_case)]
 impl Runtime {
     pub(crate) fn bootstrap(&mut self) {
@@ -150,8 +307,11 @@ impl Runtime {
         self.builtins.Object = self.create_class(name_Object.clone(), None);
         self.assign_global(name_Object, self.builtins.Object.clone());
         self.builtins.String.borrow_mut().superclass = Some(self.builtins.Object.clone());
+        Class.borrow_mut().superclass = Some(self.builtins.Object.clone());
         // now we can create simple classes
 
+        self.builtins.Trait = self.create_simple_class(builtin::class::Trait);
+
         // create nil
         self.builtins.NilClass = self.create_simple_class(builtin::class::NilClass);
         self.builtins.nil = self.create_object(self.builtins.NilClass.clone());
@@ -168,6 +328,9 @@ impl Runtime {
         // create Dictionary
         self.builtins.Dictionary = self.create_simple_class(builtin::class::Dictionary);
 
+        // create Set
+        self.builtins.Set = self.create_simple_class(builtin::class::Set);
+
         // create booleans
         self.builtins.Bool = self.create_simple_class(builtin::class::Bool);
         self.builtins.bool_true = self.create_object(self.builtins.Bool.clone());
@@ -181,8 +344,16 @@ impl Runtime {
             .borrow_mut()
             .set_primitive(Primitive::Boolean(false));
 
-        // create number
+        // create numbers, of which a `BigInt` is an `Int` too
         self.builtins.Number = self.create_simple_class(builtin::class::Number);
+        self.builtins.Int =
+            self.create_class(builtin::class::Int.into(), Some(self.builtins.Number.clone()));
+        self.builtins.BigInt =
+            self.create_class(builtin::class::BigInt.into(), Some(self.builtins.Int.clone()));
+        self.builtins.Rational =
+            self.create_class(builtin::class::Rational.into(), Some(self.builtins.Number.clone()));
+        self.builtins.Float =
+            self.create_class(builtin::class::Float.into(), Some(self.builtins.Number.clone()));
 
         // create Closure
         self.builtins.Closure = self.create_simple_class(builtin::class::Closure);
@@ -197,6 +368,54 @@ impl Runtime {
 
         self.builtins.Core = self.create_simple_class(builtin::class::Core);
         self.stack[0].open_classes.push(self.builtins.Core.clone());
+        self.set_argv(Vec::new());
+
+        // create the exception hierarchy
+        self.builtins.Exception = self.create_simple_class(builtin::class::Exception);
+        self.builtins.RuntimeError = self.create_exception_class(builtin::class::RuntimeError);
+        self.builtins.TypeError = self.create_exception_class(builtin::class::TypeError);
+        self.builtins.NameError = self.create_exception_class(builtin::class::NameError);
+        self.builtins.NoMethodError = self.create_exception_class(builtin::class::NoMethodError);
+        self.builtins.ArgumentError = self.create_exception_class(builtin::class::ArgumentError);
+        self.builtins.IndexError = self.create_exception_class(builtin::class::IndexError);
+        self.builtins.ImportError = self.create_exception_class(builtin::class::ImportError);
+        self.builtins.ArithmeticError =
+            self.create_exception_class(builtin::class::ArithmeticError);
+
+        // create Option and Result, whose variants hold their value in a field
+        self.builtins.Option = self.create_simple_class(builtin::class::Option);
+        let option = self.builtins.Option.clone();
+        self.builtins.Some =
+            self.create_field_class(builtin::class::Some, &option, builtin::property::value);
+        self.builtins.NoneClass =
+            self.create_class(builtin::class::NoneClass.into(), Some(option));
+        self.builtins.None = self.create_object(self.builtins.NoneClass.clone());
+        self.assign_global(builtin::NONE.into(), self.builtins.None.clone());
+        self.builtins.Result = self.create_simple_class(builtin::class::Result);
+        let result = self.builtins.Result.clone();
+        self.builtins.Ok =
+            self.create_field_class(builtin::class::Ok, &result, builtin::property::value);
+        self.builtins.Err =
+            self.create_field_class(builtin::class::Err, &result, builtin::property::error);
+    }
+
+    fn create_exception_class(&mut self, name: &str) -> ObjectRef {
+        self.create_class(name.into(), Some(self.builtins.Exception.clone()))
+    }
+
+    /// Creates a class declared with one field, as `class Some(value) < Option` would.
+    fn create_field_class(&mut self, name: &str, superclass: &ObjectRef, field: &str) -> ObjectRef {
+        let class = self.create_class(name.into(), Some(superclass.clone()));
+        let node = NodeMeta::host(format!("class {name}({field})"));
+        let field = Parameter {
+            name: Ident { name: field.into() }.at(&node),
+            kind: ParameterKind::Positional,
+            default: None,
+        }
+        .at(&node);
+        self.define_field_initializer(&class, vec![field], &node)
+            .expect("a field class defined twice");
+        class
     }
 
     fn bootstrap_stdlib(&mut self) {
@@ -205,99 +424,247 @@ impl Runtime {
 
             impl self.builtins.Number => {
                 fn init() {
-                    this.borrow_mut().set_primitive(Primitive::Number(Default::default()));
-                    this
+                    return Err(IllegalConstructorCall {
+                        class: this.borrow().__class__().borrow().__name__().unwrap(),
+                    });
                 }
 
                 fn __eq__(other) {
-                    if other.borrow().__class__() != runtime.builtins.Number {
-                        return Ok(runtime.create_bool(false));
-                    }
-                    let result = this.borrow().number().unwrap() == other.borrow().number().unwrap();
+                    let Some(other) = other.borrow().number() else {
+                        return Ok(runtime.builtins.bool_false.clone());
+                    };
+                    let number = number_operand(&this)?;
+                    let result = number.compare(&other) == Some(Ordering::Equal);
                     runtime.create_bool(result)
                 }
 
                 fn __neq__(other) {
-                    if other.borrow().__class__() != runtime.builtins.Number {
-                        return Ok(runtime.create_bool(true));
-                    }
-                    let result = this.borrow().number().unwrap() != other.borrow().number().unwrap();
+                    let Some(other) = other.borrow().number() else {
+                        return Ok(runtime.builtins.bool_true.clone());
+                    };
+                    let number = number_operand(&this)?;
+                    let result = number.compare(&other) != Some(Ordering::Equal);
                     runtime.create_bool(result)
                 }
 
+                fn __hash__() {
+                    this
+                }
+
                 fn __lt__(other) {
-                    let result = this.borrow().number().unwrap() < other.borrow().number().unwrap();
-                    runtime.create_bool(result)
+                    let order = number_operand(&this)?.compare(&number_operand(&other)?);
+                    runtime.create_bool(order == Some(Ordering::Less))
                 }
 
                 fn __lte__(other) {
-                    let result = this.borrow().number().unwrap() <= other.borrow().number().unwrap();
-                    runtime.create_bool(result)
+                    let order = number_operand(&this)?.compare(&number_operand(&other)?);
+                    runtime.create_bool(matches!(order, Some(Ordering::Less | Ordering::Equal)))
                 }
 
                 fn __gt__(other) {
-                    let result = this.borrow().number().unwrap() > other.borrow().number().unwrap();
-                    runtime.create_bool(result)
+                    let order = number_operand(&this)?.compare(&number_operand(&other)?);
+                    runtime.create_bool(order == Some(Ordering::Greater))
                 }
 
                 fn __gte__(other) {
-                    let result = this.borrow().number().unwrap() >= other.borrow().number().unwrap();
-                    runtime.create_bool(result)
+                    let order = number_operand(&this)?.compare(&number_operand(&other)?);
+                    runtime.create_bool(matches!(order, Some(Ordering::Greater | Ordering::Equal)))
                 }
 
                 fn __add__(other) {
-                    let result = this.borrow().number().unwrap() + other.borrow().number().unwrap();
+                    let result = number_operand(&this)?.arithmetic(
+                        &number_operand(&other)?,
+                        i64::checked_add,
+                        |a, b| a + b,
+                        |a, b| a + b,
+                        |a, b| a + b,
+                    );
                     runtime.create_number(result)
                 }
 
                 fn __sub__(other) {
-                    let result = this.borrow().number().unwrap() - other.borrow().number().unwrap();
+                    let result = number_operand(&this)?.arithmetic(
+                        &number_operand(&other)?,
+                        i64::checked_sub,
+                        |a, b| a - b,
+                        |a, b| a - b,
+                        |a, b| a - b,
+                    );
                     runtime.create_number(result)
                 }
 
                 fn __mul__(other) {
-                    let result = this.borrow().number().unwrap() * other.borrow().number().unwrap();
+                    let result = number_operand(&this)?.arithmetic(
+                        &number_operand(&other)?,
+                        i64::checked_mul,
+                        |a, b| a * b,
+                        |a, b| a * b,
+                        |a, b| a * b,
+                    );
                     runtime.create_number(result)
                 }
 
+                // `/` gives a `Rational` when either side is one and a `Float` otherwise, `//`
+                // rounds down
                 fn __div__(other) {
-                    let result = this.borrow().number().unwrap() / other.borrow().number().unwrap();
+                    let result = number_operand(&this)?.div(&number_operand(&other)?)?;
+                    runtime.create_number(result)
+                }
+
+                fn __floordiv__(other) {
+                    let number = number_operand(&this)?;
+                    let result = number.floor_div(&number_operand(&other)?)?;
                     runtime.create_number(result)
                 }
 
                 fn __neg__() {
-                    let result = - this.borrow().number().unwrap();
+                    let result = number_operand(&this)?.neg();
                     runtime.create_number(result)
                 }
 
                 fn __mod__(other) {
-                    let result = this.borrow().number().unwrap() % other.borrow().number().unwrap();
+                    let number = number_operand(&this)?;
+                    let result = number.floor_mod(&number_operand(&other)?)?;
                     runtime.create_number(result)
                 }
 
                 fn round() {
-                    let result = this.borrow().number().unwrap().round();
+                    let number = number_operand(&this)?;
+                    let result = number.to_integer(f64::round, BigRational::round)?;
                     runtime.create_number(result)
                 }
 
                 fn ceil() {
-                    let result = this.borrow().number().unwrap().ceil();
+                    let number = number_operand(&this)?;
+                    let result = number.to_integer(f64::ceil, BigRational::ceil)?;
                     runtime.create_number(result)
                 }
 
                 fn floor() {
-                    let result = this.borrow().number().unwrap().floor();
+                    let number = number_operand(&this)?;
+                    let result = number.to_integer(f64::floor, BigRational::floor)?;
                     runtime.create_number(result)
                 }
 
+                fn to_i() {
+                    let number = number_operand(&this)?;
+                    let result = number.to_integer(f64::trunc, BigRational::trunc)?;
+                    runtime.create_number(result)
+                }
+
+                fn to_f() {
+                    let result = number_operand(&this)?.as_f64();
+                    runtime.create_float(result)
+                }
+
+                fn to_r() {
+                    let result = number_operand(&this)?.to_rational().ok_or(Arithmetic {
+                        reason: "NaN or infinity has no exact value",
+                    })?;
+                    runtime.create_number(Number::Rational(result))
+                }
+
                 fn pow(power) {
-                    let power = power.borrow().number().unwrap();
-                    let result = this.borrow().number().unwrap().powf(power);
+                    let result = number_operand(&this)?.pow(&number_operand(&power)?)?;
                     runtime.create_number(result)
                 }
 
                 fn to_s() {
-                    runtime.create_string(this.borrow().number().unwrap().to_string())
+                    runtime.create_string(number_operand(&this)?.to_string())
+                }
+            }
+            impl self.builtins.Int => {
+                fn init() {
+                    this.borrow_mut().set_primitive(Primitive::Number(Number::Int(0)));
+                    this
+                }
+
+                fn __bitor__(other) {
+                    let number = number_operand(&this)?;
+                    let other = integer_operand(&other)?;
+                    let result = number.bitwise(&other, |a, b| a | b, |a, b| a | b);
+                    runtime.create_number(result)
+                }
+
+                fn __bitand__(other) {
+                    let number = number_operand(&this)?;
+                    let other = integer_operand(&other)?;
+                    let result = number.bitwise(&other, |a, b| a & b, |a, b| a & b);
+                    runtime.create_number(result)
+                }
+
+                fn __bitxor__(other) {
+                    let number = number_operand(&this)?;
+                    let other = integer_operand(&other)?;
+                    let result = number.bitwise(&other, |a, b| a ^ b, |a, b| a ^ b);
+                    runtime.create_number(result)
+                }
+
+                fn __shl__(other) {
+                    let result = number_operand(&this)?.shift_left(int_operand(&other)?)?;
+                    runtime.create_number(result)
+                }
+
+                fn __shr__(other) {
+                    let result = number_operand(&this)?.shift_right(int_operand(&other)?)?;
+                    runtime.create_number(result)
+                }
+
+                fn __invert__() {
+                    let result = number_operand(&this)?.invert();
+                    runtime.create_number(result)
+                }
+            }
+            impl self.builtins.BigInt => {
+                // a `BigInt` only comes of an `Int` that has outgrown its range
+                fn init() {
+                    return Err(IllegalConstructorCall {
+                        class: this.borrow().__class__().borrow().__name__().unwrap(),
+                    });
+                }
+            }
+            impl self.builtins.Rational => {
+                fn init(numerator, denominator) {
+                    let numerator = integer_operand(&numerator)?.to_bigint().unwrap();
+                    let denominator = integer_operand(&denominator)?.to_bigint().unwrap();
+                    if denominator.is_zero() {
+                        return Err(Arithmetic {
+                            reason: "division by zero",
+                        });
+                    }
+                    let value = BigRational::new(numerator, denominator);
+                    this.borrow_mut().set_primitive(Primitive::Number(Number::Rational(value)));
+                    this
+                }
+
+                fn numerator() {
+                    let Some(Number::Rational(value)) = this.borrow().number() else {
+                        unreachable!()
+                    };
+                    runtime.create_number(Number::integer(value.numer().clone()))
+                }
+
+                fn denominator() {
+                    let Some(Number::Rational(value)) = this.borrow().number() else {
+                        unreachable!()
+                    };
+                    runtime.create_number(Number::integer(value.denom().clone()))
+                }
+
+                // `1/3r` is how the literal is written
+                fn repr() {
+                    runtime.create_string(format!("{}r", number_operand(&this)?))
+                }
+            }
+            impl self.builtins.Float => {
+                fn init() {
+                    this.borrow_mut().set_primitive(Primitive::Number(Number::Float(0.0)));
+                    this
+                }
+
+                fn nan() as "nan?" {
+                    let result = number_operand(&this)?.as_f64().is_nan();
+                    runtime.create_bool(result)
                 }
             }
             impl self.builtins.String => {
@@ -307,27 +674,152 @@ impl Runtime {
                 }
 
                 fn trim() {
-                    let this_ref = this.borrow();
-                    let string = this_ref.string().unwrap();
+                    let string = string_receiver(&this)?;
                     let result = string.trim();
                     runtime.create_string(result)
                 }
 
                 fn __eq__(other) {
-                    let this_ref = this.borrow();
-                    if other.borrow().class != this_ref.class {
+                    if other.borrow().class != this.borrow().class {
                         return Ok(runtime.builtins.bool_false.clone());
                     }
-                    let result = this_ref.string().unwrap() == other.borrow().string().unwrap();
+                    let result = *string_receiver(&this)? == *string_receiver(&other)?;
                     runtime.create_bool(result)
                 }
 
                 fn __neq__(other) {
-                    let this_ref = this.borrow();
-                    let result = this_ref.string().unwrap() != other.borrow().string().unwrap();
+                    let result = this.borrow().string() != other.borrow().string();
+                    runtime.create_bool(result)
+                }
+
+                fn __lt__(other) {
+                    let result = *string_receiver(&this)? < string_operand(&other)?;
+                    runtime.create_bool(result)
+                }
+
+                fn __lte__(other) {
+                    let result = *string_receiver(&this)? <= string_operand(&other)?;
+                    runtime.create_bool(result)
+                }
+
+                fn __gt__(other) {
+                    let result = *string_receiver(&this)? > string_operand(&other)?;
+                    runtime.create_bool(result)
+                }
+
+                fn __gte__(other) {
+                    let result = *string_receiver(&this)? >= string_operand(&other)?;
+                    runtime.create_bool(result)
+                }
+
+                fn __index__(index) {
+                    let chars: Vec<char> = string_receiver(&this)?.chars().collect();
+                    if let Some((start, finish)) = range_bounds(runtime, &index) {
+                        let range = slice_range(start, finish, chars.len());
+                        return Ok(runtime.create_string(chars[range].iter().collect::<String>()));
+                    }
+                    let index = int_operand(&index)?;
+                    match element_position(index, chars.len()) {
+                        Some(position) => runtime.create_string(chars[position].to_string()),
+                        None => runtime.nil(),
+                    }
+                }
+
+                fn len() {
+                    let len = string_receiver(&this)?.chars().count();
+                    runtime.create_int(len as i64)
+                }
+
+                fn slice(start, finish) {
+                    let start = int_operand(&start)?;
+                    let finish = (finish != runtime.nil()).then(|| int_operand(&finish)).transpose()?;
+                    let chars: Vec<char> = string_receiver(&this)?.chars().collect();
+                    let range = slice_range(start, finish, chars.len());
+                    runtime.create_string(chars[range].iter().collect::<String>())
+                }
+
+                fn lines() {
+                    let string = string_receiver(&this)?.clone();
+                    let lines = string.lines().map(|line| runtime.create_string(line)).collect();
+                    runtime.create_array(lines)
+                }
+
+                fn chars() {
+                    let string = string_receiver(&this)?.clone();
+                    let chars = string.chars().map(|char| runtime.create_string(char.to_string())).collect();
+                    let chars = runtime.create_array(chars);
+                    runtime.call_instance_method(chars, builtin::method::iter, None, None)?
+                }
+
+                fn starts_with(prefix) as "starts_with?" {
+                    let result = string_receiver(&this)?.starts_with(&string_operand(&prefix)?);
                     runtime.create_bool(result)
                 }
 
+                fn ends_with(suffix) as "ends_with?" {
+                    let result = string_receiver(&this)?.ends_with(&string_operand(&suffix)?);
+                    runtime.create_bool(result)
+                }
+
+                fn contains(substring) as "contains?" {
+                    let result = string_receiver(&this)?.contains(&string_operand(&substring)?);
+                    runtime.create_bool(result)
+                }
+
+                fn replace(from, to) {
+                    let (from, to) = (string_operand(&from)?, string_operand(&to)?);
+                    let result = string_receiver(&this)?.replace(&from, &to);
+                    runtime.create_string(result)
+                }
+
+                fn upper() {
+                    let result = string_receiver(&this)?.to_uppercase();
+                    runtime.create_string(result)
+                }
+
+                fn lower() {
+                    let result = string_receiver(&this)?.to_lowercase();
+                    runtime.create_string(result)
+                }
+
+                fn to_n() {
+                    // digits alone make an integer, any other number a `Float`
+                    let string = string_receiver(&this)?.trim().to_string();
+                    if let Ok(integer) = string.parse::<BigInt>() {
+                        return Ok(runtime.create_number(Number::integer(integer)));
+                    }
+                    match string.parse::<f64>() {
+                        Ok(float) => runtime.create_float(float),
+                        Err(_) => runtime.nil(),
+                    }
+                }
+
+                fn repeat(count) {
+                    let count = int_operand(&count)?;
+                    let result = {
+                        let string = string_receiver(&this)?;
+                        repeated_len(string.len(), count)?;
+                        string.repeat(count as usize)
+                    };
+                    runtime.create_string(result)
+                }
+
+                fn ord() {
+                    let string = string_receiver(&this)?;
+                    let mut chars = string.chars();
+                    let (Some(char), None) = (chars.next(), chars.next()) else {
+                        return Err(InvalidArgument {
+                            reason: "ord of a string that is not one character",
+                        });
+                    };
+                    runtime.create_int(char as i64)
+                }
+
+                fn __hash__() {
+                    let hash = hash_of(&*string_receiver(&this)?);
+                    runtime.create_int(hash)
+                }
+
                 fn __add__(other) {
                     let other_string = runtime.call_instance_method(
                         other.clone(),
@@ -337,7 +829,7 @@ impl Runtime {
                     )?;
                     let other_string_ref = other_string.borrow();
                     let other_string = other_string_ref.string().unwrap();
-                    let mut result = this.borrow().string().unwrap().clone();
+                    let mut result = string_receiver(&this)?.clone();
                     result.push_str(other_string);
                     runtime.create_string(result)
                 }
@@ -359,6 +851,15 @@ impl Runtime {
                     runtime.create_bool(this != other)
                 }
 
+                fn __hash__() {
+                    runtime.create_int(hash_of(Rc::as_ptr(&this)))
+                }
+
+                fn is_a(class) as "is_a?" {
+                    let this_class = this.borrow().__class__();
+                    runtime.create_bool(runtime.inherits_from(&this_class, &class))
+                }
+
                 fn to_s() {
                     runtime.create_string("Object()")
                 }
@@ -389,13 +890,20 @@ impl Runtime {
                 }
 
                 fn to_s() {
-                    runtime.create_string(this.borrow().bool().unwrap().to_string())
+                    let result = this.borrow().bool().ok_or_else(|| TypeMismatch {
+                        class: this.borrow().__class__().borrow().__name__().unwrap(),
+                        expected: builtin::class::Bool.into(),
+                    })?;
+                    runtime.create_string(result.to_string())
                 }
             }
             impl self.builtins.Array => {
+                fn __hash__() {
+                    return Err(unhashable(&this));
+                }
+
                 fn to_s() {
-                    let this_ref = this.borrow();
-                    let elements = this_ref.array().unwrap();
+                    let elements = array_receiver(&this)?;
                     let inner = object_list_to_string(
                         runtime,
                         elements.iter().cloned(),
@@ -404,27 +912,43 @@ impl Runtime {
                 }
 
                 fn __index__(index) {
-                    if index.borrow().__class__() != runtime.builtins.Number {
-                        return Err(TypeMismatch {
-                            class: index.borrow().__class__().borrow().__name__().unwrap(),
-                            expected: builtin::class::Number.into(),
-                        });
+                    let elements = array_receiver(&this)?.clone();
+                    if let Some((start, finish)) = range_bounds(runtime, &index) {
+                        let range = slice_range(start, finish, elements.len());
+                        return Ok(runtime.create_array(elements[range].to_vec()));
                     }
-                    let this_ref = this.borrow();
-                    let elements = this_ref.array().unwrap();
-                    if elements.is_empty() {
-                        return Ok(runtime.nil());
+                    let index = int_operand(&index)?;
+                    match element_position(index, elements.len()) {
+                        Some(position) => elements[position].clone(),
+                        None => runtime.nil(),
                     }
-                    let index = index.borrow().number().unwrap() as isize;
-                    let index = if index < 0 {
-                        index.rem_euclid(elements.len() as isize)
-                    } else {
-                        index
-                    } as usize;
-                    if index >= elements.len() {
-                        return Ok(runtime.nil());
-                    }
-                    elements[index].clone()
+                }
+
+                fn __set_index__(index, element) {
+                    let index = int_operand(&index)?;
+                    let mut elements = array_receiver_mut(&this)?;
+                    let position = element_position(index, elements.len()).ok_or(Index {
+                        error: "assignment index out of bounds",
+                    })?;
+                    elements[position] = element;
+                    runtime.nil()
+                }
+
+                fn __eq__(other) {
+                    let result = runtime.sequences_equal(&this, &other)?;
+                    runtime.create_bool(result)
+                }
+
+                fn __neq__(other) {
+                    let result = !runtime.sequences_equal(&this, &other)?;
+                    runtime.create_bool(result)
+                }
+
+                fn __mul__(count) {
+                    let elements = array_receiver(&this)?.clone();
+                    let len = repeated_len(elements.len(), int_operand(&count)?)?;
+                    let repeated = elements.iter().cycle().take(len);
+                    runtime.create_array(repeated.cloned().collect())
                 }
 
                 fn __add__(other) {
@@ -434,9 +958,9 @@ impl Runtime {
                             expected: builtin::class::Array.into(),
                         });
                     }
-                    let [mut arr1, arr2] = [this, other].map(|obj| obj.borrow().array().unwrap().clone());
-                    arr1.extend(arr2);
-                    runtime.create_array(arr1)
+                    let mut elements = array_receiver(&this)?.clone();
+                    elements.extend(array_receiver(&other)?.iter().cloned());
+                    runtime.create_array(elements)
                 }
 
                 fn init() {
@@ -445,26 +969,134 @@ impl Runtime {
                 }
 
                 fn push(element) {
-                    this.borrow_mut().array_mut().unwrap().push(element);
+                    array_receiver_mut(&this)?.push(element);
                     runtime.nil()
                 }
 
                 fn pop() {
-                    let mut this_ref = this.borrow_mut();
-                    let elements = this_ref.array_mut().unwrap();
+                    let mut elements = array_receiver_mut(&this)?;
                     elements.pop().ok_or(Index {
                         error: "pop from empty list",
                     })?
                 }
 
                 fn len() {
-                    let this_ref = this.borrow();
-                    let elements = this_ref.array().unwrap();
-                    runtime.create_number(elements.len() as _)
+                    let len = array_receiver(&this)?.len();
+                    runtime.create_int(len as i64)
+                }
+
+                fn insert(index, element) {
+                    let index = int_operand(&index)?;
+                    let mut elements = array_receiver_mut(&this)?;
+                    // inserting at the length appends
+                    let position = element_position(index, elements.len() + 1).ok_or(Index {
+                        error: "insert index out of bounds",
+                    })?;
+                    elements.insert(position, element);
+                    runtime.nil()
+                }
+
+                fn remove_at(index) {
+                    let index = int_operand(&index)?;
+                    let mut elements = array_receiver_mut(&this)?;
+                    let position = element_position(index, elements.len()).ok_or(Index {
+                        error: "remove index out of bounds",
+                    })?;
+                    elements.remove(position)
+                }
+
+                fn extend(other) {
+                    let other_ref = other.borrow();
+                    let others = other_ref.array().ok_or_else(|| TypeMismatch {
+                        class: other_ref.__class__().borrow().__name__().unwrap(),
+                        expected: builtin::class::Array.into(),
+                    })?.clone();
+                    drop(other_ref);
+                    array_receiver_mut(&this)?.extend(others);
+                    runtime.nil()
+                }
+
+                fn slice(start, finish) {
+                    let start = int_operand(&start)?;
+                    let finish = (finish != runtime.nil()).then(|| int_operand(&finish)).transpose()?;
+                    let elements = array_receiver(&this)?.clone();
+                    let range = slice_range(start, finish, elements.len());
+                    runtime.create_array(elements[range].to_vec())
+                }
+
+                fn first() {
+                    let first = array_receiver(&this)?.first().cloned();
+                    first.unwrap_or_else(|| runtime.nil())
+                }
+
+                fn last() {
+                    let last = array_receiver(&this)?.last().cloned();
+                    last.unwrap_or_else(|| runtime.nil())
+                }
+
+                fn reverse() {
+                    let mut elements = array_receiver(&this)?.clone();
+                    elements.reverse();
+                    runtime.create_array(elements)
+                }
+
+                fn sort_by(key) {
+                    let elements = array_receiver(&this)?.clone();
+                    let keyed: Vec<_> = elements
+                        .into_iter()
+                        .map(|element| Ok((runtime.call_callable(key.clone(), vec![element.clone()])?, element)))
+                        .try_collect()?;
+                    let sorted = merge_sort(keyed, &mut |(a, _), (b, _)| runtime.compare_values(a, b))?;
+                    runtime.create_array(sorted.into_iter().map(|(_, element)| element).collect())
+                }
+
+                fn contains(element) as "contains?" {
+                    let elements = array_receiver(&this)?.clone();
+                    for other in &elements {
+                        if runtime.values_equal(other, &element)? {
+                            return Ok(runtime.builtins.bool_true.clone());
+                        }
+                    }
+                    runtime.builtins.bool_false.clone()
+                }
+
+                fn index_of(element) {
+                    let elements = array_receiver(&this)?.clone();
+                    for (position, other) in elements.iter().enumerate() {
+                        if runtime.values_equal(other, &element)? {
+                            return Ok(runtime.create_int(position as i64));
+                        }
+                    }
+                    runtime.nil()
                 }
             }
 
             impl self.builtins.Class => {
+                fn superclass() {
+                    let superclass = this.borrow().superclass.clone();
+                    superclass.unwrap_or_else(|| runtime.nil())
+                }
+
+                fn ancestors() {
+                    let ancestors = this.borrow().ancestors();
+                    runtime.create_array(ancestors)
+                }
+
+                fn to_s() {
+                    runtime.create_string(this.borrow().__name__().unwrap())
+                }
+
+                fn repr() {
+                    runtime.create_string(this.borrow().__name__().unwrap())
+                }
+            }
+
+            impl self.builtins.Trait => {
+                fn ancestors() {
+                    let ancestors = this.borrow().ancestors();
+                    runtime.create_array(ancestors)
+                }
+
                 fn to_s() {
                     runtime.create_string(this.borrow().__name__().unwrap())
                 }
@@ -475,69 +1107,96 @@ impl Runtime {
             }
 
             impl self.builtins.Dictionary => {
+                fn __hash__() {
+                    return Err(unhashable(&this));
+                }
+
                 fn init() {
-                    this.borrow_mut().set_primitive(Primitive::Dictionary(HashMap::default()));
+                    this.borrow_mut().set_primitive(Primitive::Dictionary(Dictionary::default()));
                     this
                 }
 
                 fn __index__(key) {
-                    let key_ref = key.borrow();
-                    let key_class = key_ref.__class__();
-                    if key_class != runtime.builtins.String {
-                        return Err(TypeMismatch {
-                            class: key_class.borrow().__name__().unwrap(),
-                            expected: "String".into(),
-                        });
-                    }
-                    let key_string: &String = key_ref.string().unwrap();
-                    let this_ref = this.borrow();
-                    let dict = this_ref.dictionary().unwrap();
-                    dict.get(key_string).cloned().unwrap_or_else(|| runtime.nil())
+                    let value = runtime.dictionary_get(&this, &key)?;
+                    value.unwrap_or_else(|| runtime.nil())
                 }
 
                 fn __set_index__(key, value) {
-                    let key_ref = key.borrow();
-                    let key_class = key_ref.__class__();
-                    if key_class != runtime.builtins.String {
-                        return Err(TypeMismatch {
-                            class: key_class.borrow().__name__().unwrap(),
-                            expected: "String".into(),
-                        });
+                    runtime.dictionary_insert(&this, key, value)?;
+                    runtime.nil()
+                }
+
+                fn __eq__(other) {
+                    let result = other.borrow().__class__() == runtime.builtins.Dictionary
+                        && runtime.dictionaries_equal(&this, &other)?;
+                    runtime.create_bool(result)
+                }
+
+                fn __neq__(other) {
+                    let result = other.borrow().__class__() != runtime.builtins.Dictionary
+                        || !runtime.dictionaries_equal(&this, &other)?;
+                    runtime.create_bool(result)
+                }
+
+                fn len() {
+                    let len = hash_table_receiver(&this)?.len();
+                    runtime.create_int(len as i64)
+                }
+
+                fn has_key(key) as "has_key?" {
+                    let result = runtime.contains_key(&this, &key)?;
+                    runtime.create_bool(result)
+                }
+
+                fn get(key, default) {
+                    let value = runtime.dictionary_get(&this, &key)?;
+                    value.unwrap_or(default)
+                }
+
+                fn delete(key) {
+                    let value = runtime.dictionary_remove(&this, &key)?;
+                    value.unwrap_or_else(|| runtime.nil())
+                }
+
+                fn merge(other) {
+                    let entries = dictionary_entries(&this)?
+                        .into_iter()
+                        .chain(dictionary_entries(&same_class_operand(&this, &other)?)?)
+                        .collect();
+                    runtime.build_dictionary(entries)?
+                }
+
+                fn update(other) {
+                    for (key, value) in dictionary_entries(&same_class_operand(&this, &other)?)? {
+                        runtime.dictionary_insert(&this, key, value)?;
                     }
-                    let key_string: &String = key_ref.string().unwrap();
-                    let mut this_ref = this.borrow_mut();
-                    let dict = this_ref.dictionary_mut().unwrap();
-                    dict.insert(key_string.clone(), value);
+                    runtime.nil()
+                }
+
+                fn clear() {
+                    receiver_primitive_mut(&this, builtin::class::Dictionary, Object::dictionary_mut)?.clear();
                     runtime.nil()
                 }
 
                 fn entries() {
-                    let this_ref = this.borrow();
-                    let hash_map = this_ref.dictionary().unwrap();
-                    let entries = hash_map
-                        .iter()
-                        .map(|(key, value)| {
-                            let key = runtime.create_string(key.clone());
-                            runtime.create_tuple(vec![key, value.clone()])
-                        })
-                        .collect::<Vec<_>>();
+                    let entries: Vec<_> = dictionary_entries(&this)?
+                        .into_iter()
+                        .map(|(key, value)| runtime.create_tuple(vec![key, value]))
+                        .collect();
                     runtime.create_array(entries)
                 }
 
                 fn to_s() {
-                    let this_ref = this.borrow();
-                    let dict = this_ref.dictionary().unwrap();
-                    let entries: Vec<_> = dict
-                        .iter()
+                    let entries: Vec<_> = dictionary_entries(&this)?
+                        .into_iter()
                         .map(|(key, value)| {
-                            let value_obj = runtime.call_instance_method(
-                                value.clone(),
-                                builtin::method::repr,
-                                None,
-                                None,
-                            )?;
-                            let value_ref = value_obj.borrow();
-                            let value = value_ref.string().unwrap();
+                            // a key written `name:` in a literal is shown that way
+                            let name = key.borrow().string().filter(|key| is_identifier(key)).cloned();
+                            let key = match name {
+                                Some(name) => name,
+                                None => object_list_to_string(runtime, [key])?,
+                            };
+                            let value = object_list_to_string(runtime, [value])?;
                             Ok(format!("    {key}: {value},"))
                         })
                         .try_collect()?;
@@ -546,29 +1205,109 @@ impl Runtime {
                 }
             }
 
+            impl self.builtins.Set => {
+                fn __hash__() {
+                    return Err(unhashable(&this));
+                }
+
+                fn add(element) {
+                    runtime.dictionary_insert(&this, element.clone(), element)?;
+                    runtime.nil()
+                }
+
+                fn remove(element) {
+                    let removed = runtime.dictionary_remove(&this, &element)?;
+                    runtime.create_bool(removed.is_some())
+                }
+
+                fn len() {
+                    let len = hash_table_receiver(&this)?.len();
+                    runtime.create_int(len as i64)
+                }
+
+                fn contains(element) as "contains?" {
+                    let result = runtime.contains_key(&this, &element)?;
+                    runtime.create_bool(result)
+                }
+
+                fn to_a() {
+                    runtime.create_array(set_elements(&this)?)
+                }
+
+                fn __bitor__(other) {
+                    let other = same_class_operand(&this, &other)?;
+                    let elements = set_elements(&this)?.into_iter().chain(set_elements(&other)?).collect();
+                    runtime.create_set(elements)?
+                }
+
+                fn __bitand__(other) {
+                    let other = same_class_operand(&this, &other)?;
+                    let mut elements = Vec::new();
+                    for element in set_elements(&this)? {
+                        if runtime.contains_key(&other, &element)? {
+                            elements.push(element);
+                        }
+                    }
+                    runtime.create_set(elements)?
+                }
+
+                fn __sub__(other) {
+                    let other = same_class_operand(&this, &other)?;
+                    let mut elements = Vec::new();
+                    for element in set_elements(&this)? {
+                        if !runtime.contains_key(&other, &element)? {
+                            elements.push(element);
+                        }
+                    }
+                    runtime.create_set(elements)?
+                }
+
+                fn __eq__(other) {
+                    let result = other.borrow().__class__() == runtime.builtins.Set
+                        && runtime.dictionaries_equal(&this, &other)?;
+                    runtime.create_bool(result)
+                }
+
+                fn __neq__(other) {
+                    let result = other.borrow().__class__() != runtime.builtins.Set
+                        || !runtime.dictionaries_equal(&this, &other)?;
+                    runtime.create_bool(result)
+                }
+
+                fn to_s() {
+                    let inner = object_list_to_string(runtime, set_elements(&this)?)?;
+                    runtime.create_string(format!("Set({inner})"))
+                }
+            }
+
             impl self.builtins.Tuple => {
                 fn __index__(index) {
-                    if index.borrow().__class__() != runtime.builtins.Number {
-                        return Err(TypeMismatch {
-                            class: index.borrow().__class__().borrow().__name__().unwrap(),
-                            expected: "Number".to_string(),
-                        });
-                    };
-                    let index: f64 = index.borrow().number().unwrap();
-                    let index = index as usize;
-                    let this_ref = this.borrow();
-                    let array = this_ref.array().unwrap();
-                    if index >= array.len() {
-                        return Err(Index {
-                            error: "tuple index out of bounds"
-                        });
-                    }
-                    array[index].clone()
+                    let index = int_operand(&index)?;
+                    let array = tuple_receiver(&this)?;
+                    let item = usize::try_from(index).ok().and_then(|index| array.get(index));
+                    item.ok_or(Index {
+                        error: "tuple index out of bounds"
+                    })?.clone()
+                }
+
+                fn __eq__(other) {
+                    let result = runtime.sequences_equal(&this, &other)?;
+                    runtime.create_bool(result)
+                }
+
+                fn __neq__(other) {
+                    let result = !runtime.sequences_equal(&this, &other)?;
+                    runtime.create_bool(result)
+                }
+
+                fn __hash__() {
+                    let items = tuple_receiver(&this)?.clone();
+                    let hashes: Vec<_> = items.iter().map(|item| runtime.hash_key(item)).try_collect()?;
+                    runtime.create_int(hash_of(hashes))
                 }
 
                 fn to_s() {
-                    let this_ref = this.borrow();
-                    let items = this_ref.array().unwrap();
+                    let items = tuple_receiver(&this)?;
                     let mut inner = object_list_to_string(
                         runtime,
                         items.iter().cloned(),
@@ -584,9 +1323,224 @@ impl Runtime {
                 fn clone(obj) {
                     Object::clone(&obj)
                 }
+
+                fn gc() {
+                    let stats = runtime.collect_garbage();
+                    runtime.create_gc_stats_dictionary(stats)
+                }
+
+                fn gc_stats() {
+                    let stats = runtime.gc_stats();
+                    runtime.create_gc_stats_dictionary(stats)
+                }
+            }
+
+            impl self.builtins.Some => {
+                fn __eq__(other) {
+                    let class = runtime.builtins.Some.clone();
+                    let result = holds_equal(runtime, &this, &other, &class, builtin::property::value)?;
+                    runtime.create_bool(result)
+                }
+
+                fn to_s() {
+                    holder_to_s(runtime, &this, builtin::property::value)?
+                }
+            }
+
+            impl self.builtins.NoneClass => {
+                fn to_s() {
+                    runtime.create_string(builtin::NONE)
+                }
+            }
+
+            impl self.builtins.Ok => {
+                fn __eq__(other) {
+                    let class = runtime.builtins.Ok.clone();
+                    let result = holds_equal(runtime, &this, &other, &class, builtin::property::value)?;
+                    runtime.create_bool(result)
+                }
+
+                fn to_s() {
+                    holder_to_s(runtime, &this, builtin::property::value)?
+                }
+            }
+
+            impl self.builtins.Err => {
+                fn __eq__(other) {
+                    let class = runtime.builtins.Err.clone();
+                    let result = holds_equal(runtime, &this, &other, &class, builtin::property::error)?;
+                    runtime.create_bool(result)
+                }
+
+                fn to_s() {
+                    holder_to_s(runtime, &this, builtin::property::error)?
+                }
+            }
+
+            impl self.builtins.Exception => {
+                fn to_s() {
+                    let message = this.borrow().get_property(builtin::property::message);
+                    match message {
+                        Some(message) if message != runtime.nil() => message,
+                        _ => this.borrow().__class__().borrow().get_property(builtin::property::__name__).unwrap(),
+                    }
+                }
             }
         );
 
+        // `split()` splits at whitespace, `split(separator)` at each separator
+        self.builtins
+            .String
+            .borrow_mut()
+            .define_method(
+                MethodReceiver::Instance,
+                "split".into(),
+                vec![Param::Vararg("separator".into())],
+                MethodBody::system(|runtime, this, method_name, args| {
+                    let separator = optional_argument(&this, method_name, args)?
+                        .map(|separator| string_operand(&separator))
+                        .transpose()?;
+                    let string = string_receiver(&this)?.clone();
+                    let parts: Vec<_> = match &separator {
+                        // an empty separator splits between characters
+                        Some(separator) if separator.is_empty() => {
+                            string.split("").filter(|part| !part.is_empty()).collect()
+                        }
+                        Some(separator) => string.split(separator.as_str()).collect(),
+                        None => string.split_whitespace().collect(),
+                    };
+                    let parts = parts
+                        .into_iter()
+                        .map(|part| runtime.create_string(part))
+                        .collect();
+                    Ok(runtime.create_array(parts))
+                }),
+            )
+            .unwrap();
+
+        self.builtins
+            .String
+            .borrow_mut()
+            .define_method(
+                MethodReceiver::Instance,
+                "format".into(),
+                vec![Param::Vararg("args".into())],
+                MethodBody::system(|runtime, this, _method_name, args| {
+                    let template = string_receiver(&this)?.clone();
+                    let result = runtime.format_string(&template, args)?;
+                    Ok(runtime.create_string(result))
+                }),
+            )
+            .unwrap();
+
+        self.builtins
+            .String
+            .borrow_mut()
+            .define_method(
+                MethodReceiver::Class,
+                "chr".into(),
+                vec![Param::Positional("code".into())],
+                MethodBody::system(|runtime, this, method_name, args| {
+                    let arg_count = args.len();
+                    let Ok([code]) = <[ObjectRef; 1]>::try_from(args) else {
+                        return Err(ArityMismatch {
+                            class_name: this.borrow().__name__().unwrap(),
+                            method_name,
+                            expected: Arity::exactly(1),
+                            actual: arg_count,
+                        });
+                    };
+                    let code = int_operand(&code)?;
+                    let char = u32::try_from(code)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or(InvalidArgument {
+                            reason: "not a character code",
+                        })?;
+                    Ok(runtime.create_string(char.to_string()))
+                }),
+            )
+            .unwrap();
+
+        self.builtins
+            .Exception
+            .borrow_mut()
+            .define_method(
+                MethodReceiver::Instance,
+                builtin::method::init.into(),
+                vec![Param::Vararg(builtin::property::message.into())],
+                MethodBody::system(|runtime, this, method_name, args| {
+                    let message = optional_argument(&this, method_name, args)?;
+                    let message = message.unwrap_or_else(|| runtime.nil());
+                    this.borrow_mut()
+                        .set_property(builtin::property::message, message);
+                    Ok(this)
+                }),
+            )
+            .unwrap();
+
+        self.builtins
+            .Set
+            .borrow_mut()
+            .define_method(
+                MethodReceiver::Instance,
+                builtin::method::init.into(),
+                vec![Param::Vararg("elements".into())],
+                MethodBody::system(|runtime, this, _method_name, args| {
+                    this.borrow_mut()
+                        .set_primitive(Primitive::Set(Dictionary::default()));
+                    for element in args {
+                        runtime.dictionary_insert(&this, element.clone(), element)?;
+                    }
+                    Ok(this)
+                }),
+            )
+            .unwrap();
+
+        // `sort()` orders by `<`, `sort(comparator)` by the sign of `comparator(a, b)`
+        self.builtins
+            .Array
+            .borrow_mut()
+            .define_method(
+                MethodReceiver::Instance,
+                "sort".into(),
+                vec![Param::Vararg("comparator".into())],
+                MethodBody::system(|runtime, this, method_name, args| {
+                    let comparator = optional_argument(&this, method_name, args)?;
+                    let elements = array_receiver(&this)?.clone();
+                    let sorted = match comparator {
+                        Some(comparator) => merge_sort(elements, &mut |a, b| {
+                            runtime.compare_with(&comparator, a, b)
+                        })?,
+                        None => merge_sort(elements, &mut |a, b| runtime.compare_values(a, b))?,
+                    };
+                    Ok(runtime.create_array(sorted))
+                }),
+            )
+            .unwrap();
+
+        self.builtins
+            .Array
+            .borrow_mut()
+            .define_method(
+                MethodReceiver::Instance,
+                "join".into(),
+                vec![Param::Vararg("separator".into())],
+                MethodBody::system(|runtime, this, method_name, args| {
+                    let separator = match optional_argument(&this, method_name, args)? {
+                        Some(separator) => runtime.string_of(separator)?,
+                        None => String::new(),
+                    };
+                    let elements = array_receiver(&this)?.clone();
+                    let strings: Vec<_> = elements
+                        .into_iter()
+                        .map(|element| runtime.string_of(element))
+                        .try_collect()?;
+                    Ok(runtime.create_string(strings.join(&separator)))
+                }),
+            )
+            .unwrap();
+
         self.builtins
             .IO
             .borrow_mut()
@@ -594,7 +1548,7 @@ impl Runtime {
                 MethodReceiver::Class,
                 "print".into(),
                 vec![Param::Vararg("args".into())],
-                MethodBody::System(|runtime, _this, _method_name, args| {
+                MethodBody::system(|runtime, _this, _method_name, args| {
                     runtime.print_objects(args)?;
                     Ok(runtime.nil())
                 }),
@@ -608,7 +1562,7 @@ impl Runtime {
                 MethodReceiver::Class,
                 "println".into(),
                 vec![Param::Vararg("args".into())],
-                MethodBody::System(|runtime, _this, _method_name, args| {
+                MethodBody::system(|runtime, _this, _method_name, args| {
                     runtime.print_objects(args)?;
                     println!();
                     Ok(runtime.nil())
@@ -623,7 +1577,7 @@ impl Runtime {
                 MethodReceiver::Class,
                 "debug".into(),
                 vec![Param::Vararg("args".into())],
-                MethodBody::System(|runtime, _this, _method_name, args| {
+                MethodBody::system(|runtime, _this, _method_name, args| {
                     print!(">>> ");
                     runtime.print_objects(args)?;
                     println!();
@@ -639,7 +1593,7 @@ impl Runtime {
                 MethodReceiver::Instance,
                 builtin::op::__call__.into(),
                 vec![Param::Vararg("args".into())],
-                MethodBody::System(|runtime, this, _method_name, args| {
+                MethodBody::system(|runtime, this, _method_name, args| {
                     runtime.call_callable(this, args)
                 }),
             )
@@ -652,19 +1606,8 @@ impl Runtime {
                 MethodReceiver::Instance,
                 builtin::op::__call__.into(),
                 vec![Param::Vararg("args".into())],
-                MethodBody::System(|runtime, this, _method_name, args| {
-                    let name = this
-                        .borrow()
-                        .get_property(builtin::property::__name__)
-                        .unwrap();
-                    let receiver = this
-                        .borrow()
-                        .get_property(builtin::property::__receiver__)
-                        .unwrap();
-                    let method = receiver
-                        .borrow()
-                        .resolve_own_method(name.borrow().string().unwrap())
-                        .unwrap();
+                MethodBody::system(|runtime, this, _method_name, args| {
+                    let (receiver, method) = runtime.method_object_target(&this).unwrap();
                     runtime.call_method(receiver, method, args)
                 }),
             )
@@ -677,7 +1620,7 @@ impl Runtime {
         //         MethodReceiver::Class,
         //         builtin::method::to_s.into(),
         //         vec![],
-        //         MethodBody::System(|_runtime, this, _method_name, _args| {
+        //         MethodBody::system(|_runtime, this, _method_name, _args| {
         //             Ok(this
         //                 .borrow()
         //                 .get_property(builtin::property::__name__)
@@ -687,14 +1630,65 @@ impl Runtime {
         //     .unwrap();
     }
 
+    /// Sets `Core::argv`, the arguments given to the script.
+    pub fn set_argv(&mut self, args: Vec<String>) {
+        let args = args
+            .into_iter()
+            .map(|arg| self.create_string(arg))
+            .collect();
+        let argv = self.create_array(args);
+        self.builtins
+            .Core
+            .borrow_mut()
+            .set_property(builtin::property::argv, argv);
+    }
+
+    fn create_gc_stats_dictionary(&mut self, stats: GcStats) -> ObjectRef {
+        let entries = [
+            ("collections", stats.collections),
+            ("last_collected", stats.last_collected),
+            ("total_collected", stats.total_collected),
+            ("live_objects", stats.live_objects),
+        ]
+        .map(|(name, value)| (name.to_string(), self.create_int(value as i64)));
+        self.create_dictionary(entries.into())
+    }
+
+    /// Whether an array or tuple equals `other`, which it does when that is one of its class
+    /// with as many items, equal in turn.
+    fn sequences_equal(&mut self, sequence: &ObjectRef, other: &ObjectRef) -> Result<bool> {
+        let other_ref = other.borrow();
+        if other_ref.__class__() != sequence.borrow().__class__() {
+            return Ok(false);
+        }
+        let others = other_ref.array().unwrap().clone();
+        drop(other_ref);
+        let items = sequence.borrow().array().unwrap().clone();
+        if items.len() != others.len() {
+            return Ok(false);
+        }
+        for (item, other) in items.iter().zip(&others) {
+            if !self.values_equal(item, other)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// What `to_s` gives for `object`.
+    pub(super) fn string_of(&mut self, object: ObjectRef) -> Result<String> {
+        let string_obj = self.call_instance_method(object, builtin::method::to_s, None, None)?;
+        let string_ref = string_obj.borrow();
+        string_ref.string().cloned().ok_or_else(|| TypeMismatch {
+            class: string_ref.__class__().borrow().__name__().unwrap(),
+            expected: builtin::class::String.into(),
+        })
+    }
+
     fn print_objects(&mut self, args: Vec<ObjectRef>) -> Result<()> {
         let arg_count = args.len();
         for (i, arg) in args.into_iter().enumerate() {
-            let string_obj = self.call_instance_method(arg, builtin::method::to_s, None, None)?;
-            let string = string_obj.borrow().string().cloned().ok_or(TypeMismatch {
-                class: string_obj.borrow().__class__().borrow().__name__().unwrap(),
-                expected: builtin::class::String.into(),
-            })?;
+            let string = self.string_of(arg)?;
             print!("{}", string);
             if i < arg_count - 1 {
                 print!(" ");
@@ -1,6 +1,8 @@
 #![allow(non_upper_case_globals)]
 
 pub const SELF: &str = "self";
+/// The global holding the only `NoneClass`.
+pub const NONE: &str = "None";
 
 macro define_string_consts($($name:ident,)+) {
     $(
@@ -13,6 +15,7 @@ pub mod class {
 
     define_string_consts![
         Class,
+        Trait,
         Object,
         String,
         NilClass,
@@ -21,18 +24,55 @@ pub mod class {
         Core,
         Bool,
         Number,
+        Int,
+        BigInt,
+        Rational,
+        Float,
         Closure,
         Dictionary,
+        Set,
         Array,
         Tuple,
         Method,
+        Exception,
+        RuntimeError,
+        TypeError,
+        NameError,
+        NoMethodError,
+        ArgumentError,
+        IndexError,
+        ImportError,
+        ArithmeticError,
+        Option,
+        Some,
+        NoneClass,
+        Result,
+        Ok,
+        Err,
+        // defined by the prelude
+        Range,
     ];
 }
 
 pub mod property {
     use crate::runtime::builtin::define_string_consts;
 
-    define_string_consts![__name__, __class__, __binding__, __receiver__,];
+    define_string_consts![
+        __name__,
+        __class__,
+        __binding__,
+        __receiver__,
+        __file__,
+        __module__,
+        message,
+        line,
+        column,
+        argv,
+        start,
+        finish,
+        value,
+        error,
+    ];
 }
 
 pub mod method {
@@ -50,6 +90,7 @@ pub mod op {
         __sub__,
         __mul__,
         __div__,
+        __floordiv__,
         __mod__,
         __gt__,
         __gte__,
@@ -57,7 +98,14 @@ pub mod op {
         __lte__,
         __eq__,
         __neq__,
+        __hash__,
+        __bitor__,
+        __bitand__,
+        __bitxor__,
+        __shl__,
+        __shr__,
         __neg__,
+        __invert__,
         __not__,
         __index__,
         __set_index__,
@@ -87,6 +135,12 @@ pub mod op {
             Operator::Minus => __sub__,
             Operator::Star => __mul__,
             Operator::Slash => __div__,
+            Operator::SlashSlash => __floordiv__,
+            Operator::Pipe => __bitor__,
+            Operator::Ampersand => __bitand__,
+            Operator::Caret => __bitxor__,
+            Operator::ShiftLeft => __shl__,
+            Operator::ShiftRight => __shr__,
             Operator::LogicalNot => __not__,
             _ => return None,
         })
@@ -95,6 +149,7 @@ pub mod op {
     pub fn method_for_unary_op(op: &Operator) -> Option<&str> {
         Some(match op {
             Operator::Minus => __neg__,
+            Operator::Tilde => __invert__,
             Operator::LogicalNot => __not__,
             _ => return None,
         })
@@ -0,0 +1,295 @@
+use std::rc::Rc;
+
+use crate::runtime::object::{MethodReceiver, MethodRef, ObjectRef};
+use crate::runtime::Error::{
+    CyclicInclude, NoSuchMethod, SuperclassMismatch, SyntaxError, TypeMismatch,
+};
+use crate::runtime::{builtin, Result, Runtime, StackFrame};
+use crate::types::{
+    Access, Assignment, Block, ClassDefinition, Expression, Ident, Include, LValue,
+    MethodDefinition, Node, NodeMeta, NodeVariant, Operator, Parameter, ParameterKind, Statement,
+    TraitDefinition, Variable,
+};
+
+impl Runtime {
+    pub(super) fn exec_class_def(&mut self, class_def: Node<ClassDefinition>) -> Result<()> {
+        let ClassDefinition {
+            name,
+            fields,
+            superclass,
+            body,
+        } = class_def.v;
+        let superclass = superclass
+            .map(|superclass| self.eval_superclass(superclass))
+            .transpose()?;
+        let name = name.v.name;
+        let existing = self
+            .resolve_variable(&name)
+            .filter(|object| self.is_class(object));
+        let class = match existing {
+            Some(class) => {
+                // reopening a class cannot change what it inherits from
+                if let Some(superclass) = &superclass
+                    && class.borrow().superclass.as_ref() != Some(superclass)
+                {
+                    return Err(SuperclassMismatch {
+                        class: name,
+                        node: class_def.meta,
+                    });
+                }
+                class
+            }
+            None => {
+                let superclass = superclass.unwrap_or_else(|| self.builtins.Object.clone());
+                self.create_class(name, Some(superclass))
+            }
+        };
+        if !fields.is_empty() {
+            self.define_field_initializer(&class, fields, &class_def.meta)?;
+        }
+        self.exec_definition_body(class, body, "class definition")
+    }
+
+    /// Defines a trait, or adds to the trait of the same name. Its methods are those it gives
+    /// the classes that include it.
+    pub(super) fn exec_trait_def(&mut self, trait_def: Node<TraitDefinition>) -> Result<()> {
+        let TraitDefinition { name, body } = trait_def.v;
+        let name = name.v.name;
+        let existing = self
+            .resolve_variable(&name)
+            .filter(|object| self.is_trait(object));
+        let trait_obj = match existing {
+            Some(trait_obj) => trait_obj,
+            None => self.create_trait(name),
+        };
+        self.exec_definition_body(trait_obj, body, "trait definition")
+    }
+
+    fn exec_definition_body(
+        &mut self,
+        definition: ObjectRef,
+        body: Node<Block>,
+        context: &'static str,
+    ) -> Result<()> {
+        let stack_id = self.push_stack_frame(StackFrame {
+            class: Some(definition),
+            _context: context,
+            ..StackFrame::default()
+        });
+        body.v
+            .statements
+            .into_iter()
+            .try_for_each(|statement| self.exec(statement))?;
+        self.pop_stack_frame(stack_id);
+        Ok(())
+    }
+
+    /// `include Trait` in the body of a class or trait.
+    pub(super) fn exec_include(&mut self, include: Node<Include>) -> Result<()> {
+        let meta = include.v.target.meta.clone();
+        let included = self.eval(include.v.target)?;
+        if !self.is_trait(&included) {
+            let class = included.borrow().__class__().borrow().__name__().unwrap();
+            return Err(TypeMismatch {
+                expected: builtin::class::Trait.into(),
+                class,
+            }
+            .at(&meta));
+        }
+        let class = self.current_class();
+        let ancestors = included.borrow().ancestors();
+        if ancestors
+            .iter()
+            .any(|ancestor| Rc::ptr_eq(ancestor, &class))
+        {
+            return Err(CyclicInclude {
+                name: class.borrow().__name__().unwrap(),
+                node: include.meta,
+            });
+        }
+        class.borrow_mut().include(included);
+        Ok(())
+    }
+
+    fn eval_superclass(&mut self, superclass: Node<Expression>) -> Result<ObjectRef> {
+        let meta = superclass.meta.clone();
+        let superclass = self.eval(superclass)?;
+        if !self.is_class(&superclass) {
+            let class = superclass.borrow().__class__().borrow().__name__().unwrap();
+            return Err(TypeMismatch {
+                expected: builtin::class::Class.into(),
+                class,
+            }
+            .at(&meta));
+        }
+        Ok(superclass)
+    }
+
+    /// Defines the `init` of a class declared with fields. It takes the fields of the closest
+    /// ancestor declared with them as well as the class's own, and sets them all.
+    pub(super) fn define_field_initializer(
+        &mut self,
+        class: &ObjectRef,
+        own_fields: Vec<Node<Parameter>>,
+        node: &NodeMeta,
+    ) -> Result<()> {
+        let is_redeclared = |field: &Node<Parameter>| {
+            own_fields
+                .iter()
+                .any(|own| own.v.name.v.name == field.v.name.v.name)
+        };
+        let mut fields: Vec<_> = inherited_fields(class)
+            .into_iter()
+            .filter(|field| !is_redeclared(field))
+            .chain(own_fields.iter().cloned())
+            .collect();
+        // the sort is stable, so inherited fields stay ahead of the class's own of their kind
+        fields.sort_by_key(|field| field.v.rank());
+        let rest_fields = fields
+            .iter()
+            .filter(|field| field.v.kind == ParameterKind::Rest)
+            .count();
+        if rest_fields > 1 {
+            return Err(SyntaxError {
+                reason: "a class and its superclass cannot both have a *rest field",
+                node: node.clone().into(),
+            });
+        }
+        let init = MethodDefinition {
+            is_class_method: false,
+            name: Ident {
+                name: builtin::method::init.into(),
+            }
+            .at(node),
+            parameters: fields.clone(),
+            body: field_initializer_body(&fields, node),
+        };
+        class.borrow_mut().fields = fields;
+        self.exec_method_def(class.clone(), init.at(node))
+    }
+
+    /// Finds what `super(...)` or `super.name(...)` calls: the method of that name, or else of
+    /// the running method's name, that comes after the running method's class or trait in the
+    /// ancestors of the receiver. It is called on the running method's receiver.
+    pub(super) fn resolve_super_callee(
+        &self,
+        method_name: Option<&str>,
+        node: &NodeMeta,
+    ) -> Result<(ObjectRef, MethodRef)> {
+        let outside_method = || SyntaxError {
+            reason: "super outside of a method",
+            node: node.clone().into(),
+        };
+        let frame = self
+            .stack
+            .iter()
+            .rev()
+            .find(|frame| frame._method.is_some())
+            .ok_or_else(outside_method)?;
+        let method = frame._method.as_ref().unwrap();
+        let defined_on = method.class.upgrade().expect("method's class was dropped");
+        // a closure's body is defined on the closure, which has no ancestors
+        if !self.is_class(&defined_on) && !self.is_trait(&defined_on) {
+            return Err(outside_method());
+        }
+        let receiver = frame
+            .instance
+            .clone()
+            .expect("method frames have a receiver");
+        // class methods are looked up on the receiver itself
+        let ancestors = match method.receiver {
+            MethodReceiver::Instance => receiver.borrow().__class__().borrow().ancestors(),
+            MethodReceiver::Class => receiver.borrow().ancestors(),
+        };
+        let after = ancestors
+            .iter()
+            .position(|ancestor| Rc::ptr_eq(ancestor, &defined_on))
+            .map_or(&[][..], |index| &ancestors[index + 1..]);
+        let name = method_name.unwrap_or(&method.name);
+        let found = after.iter().find_map(|ancestor| {
+            ancestor
+                .borrow()
+                .own_method(name)
+                .filter(|found| found.receiver == method.receiver)
+        });
+        let found = match found {
+            Some(found) => found,
+            None if name == builtin::method::init => {
+                self.builtins.Object.borrow().get_init_method()
+            }
+            None => {
+                return Err(NoSuchMethod {
+                    node: node.clone().into(),
+                    search: format!(
+                        "{}.{name}",
+                        after
+                            .first()
+                            .unwrap_or(&defined_on)
+                            .borrow()
+                            .__name__()
+                            .unwrap()
+                    ),
+                })
+            }
+        };
+        Ok((receiver, found))
+    }
+}
+
+/// The fields of a class, its own or those it inherits, in the order its initializer takes them.
+pub(super) fn class_fields(class: &ObjectRef) -> Vec<Node<Parameter>> {
+    let own_fields = class.borrow().fields.clone();
+    if own_fields.is_empty() {
+        inherited_fields(class)
+    } else {
+        own_fields
+    }
+}
+
+/// The fields a class inherits: those of its closest ancestor declared with fields.
+fn inherited_fields(class: &ObjectRef) -> Vec<Node<Parameter>> {
+    let mut ancestor = class.borrow().superclass.clone();
+    while let Some(current) = ancestor {
+        let current_ref = current.borrow();
+        if !current_ref.fields.is_empty() {
+            return current_ref.fields.clone();
+        }
+        ancestor = current_ref.superclass.clone();
+    }
+    Vec::new()
+}
+
+/// The body of a generated initializer, `self.field = field` for each of the fields.
+fn field_initializer_body(fields: &[Node<Parameter>], node: &NodeMeta) -> Node<Block> {
+    let statements = fields
+        .iter()
+        .map(|field| {
+            let meta = &field.meta;
+            let variable =
+                |ident: Node<Ident>| Expression::Variable(Variable { ident }.at(meta)).at(meta);
+            let this = variable(
+                Ident {
+                    name: builtin::SELF.into(),
+                }
+                .at(meta),
+            );
+            let target = LValue::Access(
+                Access {
+                    target: Box::new(this),
+                    member: Box::new(variable(field.v.name.clone())),
+                }
+                .at(meta),
+            );
+            Statement::Assignment(
+                Assignment {
+                    target: target.at(meta),
+                    op: Operator::Equal.at(meta),
+                    value: variable(field.v.name.clone()),
+                }
+                .at(meta),
+            )
+            .at(meta)
+        })
+        .collect();
+    Block { statements }.at(node)
+}
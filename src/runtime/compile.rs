@@ -0,0 +1,956 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::runtime::builtin;
+use crate::runtime::number::Number;
+use crate::types::{
+    Assignment, Block, Closure, Expression, ForIn, Ident, LValue, Literal, Match, Node, NodeMeta,
+    Operator, Path, Pattern, Statement, Try, WhileLoop,
+};
+
+/// Where a variable lives: a slot in one of the scopes of the running chunk, or a name that is
+/// looked up dynamically through the stack like the tree-walker does.
+#[derive(Debug, Copy, Clone)]
+pub enum Target {
+    Slot { depth: usize, slot: usize },
+    Name(usize),
+}
+
+/// An assignment operator such as `+=`, applied to the old value before storing.
+#[derive(Debug, Copy, Clone)]
+pub struct Update {
+    pub method: usize,
+    pub meta: usize,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Instruction {
+    Constant(usize),
+    Nil,
+    True,
+    False,
+    Pop,
+    LoadSelf,
+    Load {
+        target: Target,
+        meta: usize,
+    },
+    Store {
+        target: Target,
+        update: Option<Update>,
+        meta: usize,
+    },
+    Define {
+        slot: usize,
+    },
+    Destructure {
+        targets: usize,
+        meta: usize,
+    },
+    BindItem {
+        targets: usize,
+        meta: usize,
+    },
+    GetProperty {
+        name: usize,
+        meta: usize,
+    },
+    SetProperty {
+        name: usize,
+        update: Option<Update>,
+    },
+    GetItem {
+        index: usize,
+        meta: usize,
+    },
+    SetIndex {
+        update: Option<Update>,
+        meta: usize,
+    },
+    /// Calls a method on the receiver below the arguments. The values of keyword arguments, if
+    /// any, come after the positional ones.
+    CallMethod {
+        name: usize,
+        argc: usize,
+        keywords: Option<usize>,
+        meta: usize,
+    },
+    ResolveNamed {
+        target: Target,
+        meta: usize,
+        callee_meta: usize,
+    },
+    ResolvePath {
+        path: usize,
+        meta: usize,
+    },
+    ResolveValue {
+        meta: usize,
+    },
+    /// Resolves `super(...)`, or `super.method(...)` when `method` names one.
+    ResolveSuper {
+        method: Option<usize>,
+        meta: usize,
+    },
+    Call {
+        argc: usize,
+        keywords: Option<usize>,
+        meta: usize,
+    },
+    LoadPath(usize),
+    MakeClosure(usize),
+    BuildArray(usize),
+    BuildTuple(usize),
+    BuildDictionary(usize),
+    /// Replaces an `Option` or `Result` with what it holds, or returns it if it holds nothing.
+    Propagate {
+        meta: usize,
+    },
+    /// Joins the `to_s` of the values on top of the stack into one string.
+    BuildString(usize),
+    /// Pushes whether the value on top of the stack matches a pattern, leaving the value in
+    /// place. On a match what the pattern binds is bound to `targets`, slots of the innermost
+    /// scope which follow the order of its bound names.
+    MatchCase {
+        pattern: usize,
+        targets: usize,
+    },
+    Jump(usize),
+    JumpIfFalse(usize),
+    JumpIfTrueOrPop(usize),
+    JumpIfFalseOrPop(usize),
+    PushScope(usize),
+    PopScope,
+    EnterLoop {
+        next: usize,
+        exit: usize,
+        iterate: Option<usize>,
+    },
+    ExitLoop,
+    Next {
+        exit: usize,
+        meta: usize,
+    },
+    Break,
+    Continue,
+    Return {
+        has_value: bool,
+        meta: usize,
+    },
+    Raise {
+        meta: usize,
+    },
+    /// Starts a `try` region. Errors raised inside it jump to `handler`: every error if
+    /// `catches_all` (for `ensure`), otherwise only those `rescue` can catch, with the exception
+    /// object pushed.
+    EnterTry {
+        handler: usize,
+        catches_all: bool,
+    },
+    ExitTry,
+    /// Pops a class and pushes whether it rescues the exception on top of the stack.
+    Rescues {
+        meta: usize,
+    },
+    /// Forgets the error a handler caught, once a `rescue` clause has taken it.
+    DropCaught,
+    /// Unwinds again with the error the handler caught.
+    Reraise,
+    Exec(usize),
+    Eval(usize),
+}
+
+#[derive(Debug)]
+pub struct ClosurePrototype {
+    pub node: Node<Closure>,
+    pub compiled: OnceCell<Rc<Chunk>>,
+}
+
+/// The value of a literal in the constant pool. Each load creates an object from it, as the
+/// tree-walker does for each evaluation, so that a run never sees what an earlier one did to it.
+#[derive(Debug)]
+pub enum Constant {
+    String(String),
+    Number(Number),
+}
+
+/// A compiled method body or program, along with the pools its instructions index into.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<Instruction>,
+    pub constants: Vec<Constant>,
+    pub names: Vec<String>,
+    pub metas: Vec<NodeMeta>,
+    pub scopes: Vec<Rc<[String]>>,
+    pub targets: Vec<Vec<Target>>,
+    pub keyword_names: Vec<Vec<String>>,
+    pub paths: Vec<Node<Path>>,
+    pub closures: Vec<ClosurePrototype>,
+    pub statements: Vec<Node<Statement>>,
+    pub expressions: Vec<Node<Expression>>,
+    pub patterns: Vec<Node<Pattern>>,
+}
+
+impl Chunk {
+    pub fn name(&self, index: usize) -> &str {
+        &self.names[index]
+    }
+
+    pub fn meta(&self, index: usize) -> &NodeMeta {
+        &self.metas[index]
+    }
+}
+
+pub struct Compiler {
+    chunk: Chunk,
+    is_global: bool,
+    scope_names: Vec<Vec<String>>,
+    active_scopes: Vec<usize>,
+    name_indices: HashMap<String, usize>,
+    string_constants: HashMap<String, usize>,
+    /// The constants of number literals, keyed by whether they are a `Float` and their bits.
+    number_constants: HashMap<(bool, u64), usize>,
+}
+
+impl Compiler {
+    fn new(is_global: bool, params: &[String]) -> Self {
+        let mut compiler = Self {
+            chunk: Chunk::default(),
+            is_global,
+            scope_names: Vec::new(),
+            active_scopes: Vec::new(),
+            name_indices: HashMap::new(),
+            string_constants: HashMap::new(),
+            number_constants: HashMap::new(),
+        };
+        compiler.begin_scope();
+        compiler.scope_names[0].extend(params.iter().cloned());
+        compiler
+    }
+
+    /// Compiles top-level code, whose variables stay in the global frame by name.
+    pub fn compile_program(body: &Node<Block>) -> Chunk {
+        let mut compiler = Self::new(true, &[]);
+        compiler.compile_block_value(body);
+        compiler.finish()
+    }
+
+    /// Compiles a method or closure body, with its parameters occupying the first slots.
+    pub fn compile_method(params: &[String], body: &Node<Block>) -> Chunk {
+        let mut compiler = Self::new(false, params);
+        compiler.compile_block_value(body);
+        compiler.finish()
+    }
+
+    fn finish(mut self) -> Chunk {
+        self.chunk.scopes = self
+            .scope_names
+            .into_iter()
+            .map(|names| names.into())
+            .collect();
+        self.chunk
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        self.chunk.code.push(instruction);
+        self.chunk.code.len() - 1
+    }
+
+    fn here(&self) -> usize {
+        self.chunk.code.len()
+    }
+
+    fn patch_jump(&mut self, at: usize) {
+        let target = self.here();
+        match &mut self.chunk.code[at] {
+            Instruction::Jump(to)
+            | Instruction::JumpIfFalse(to)
+            | Instruction::JumpIfTrueOrPop(to)
+            | Instruction::JumpIfFalseOrPop(to)
+            | Instruction::Next { exit: to, .. } => *to = target,
+            instruction => unreachable!("not a jump: {instruction:?}"),
+        }
+    }
+
+    fn name(&mut self, name: &str) -> usize {
+        if let Some(&index) = self.name_indices.get(name) {
+            return index;
+        }
+        self.chunk.names.push(name.to_string());
+        let index = self.chunk.names.len() - 1;
+        self.name_indices.insert(name.to_string(), index);
+        index
+    }
+
+    fn meta(&mut self, meta: &NodeMeta) -> usize {
+        self.chunk.metas.push(meta.clone());
+        self.chunk.metas.len() - 1
+    }
+
+    fn string_constant(&mut self, value: &str) -> usize {
+        if let Some(&index) = self.string_constants.get(value) {
+            return index;
+        }
+        self.chunk.constants.push(Constant::String(value.to_string()));
+        let index = self.chunk.constants.len() - 1;
+        self.string_constants.insert(value.to_string(), index);
+        index
+    }
+
+    /// The constant of a number literal. `BigInt` and `Rational` literals are rare enough that
+    /// they are not shared.
+    fn number_constant(&mut self, value: Number) -> usize {
+        let key = match value {
+            Number::Int(value) => Some((false, value as u64)),
+            Number::Float(value) => Some((true, value.to_bits())),
+            Number::BigInt(_) | Number::Rational(_) => None,
+        };
+        if let Some(&index) = key.and_then(|key| self.number_constants.get(&key)) {
+            return index;
+        }
+        self.chunk.constants.push(Constant::Number(value));
+        let index = self.chunk.constants.len() - 1;
+        if let Some(key) = key {
+            self.number_constants.insert(key, index);
+        }
+        index
+    }
+
+    fn begin_scope(&mut self) -> usize {
+        self.scope_names.push(Vec::new());
+        let scope = self.scope_names.len() - 1;
+        self.active_scopes.push(scope);
+        scope
+    }
+
+    fn end_scope(&mut self) {
+        self.active_scopes.pop();
+    }
+
+    fn depth(&self) -> usize {
+        self.active_scopes.len() - 1
+    }
+
+    fn has_slots(&self, depth: usize) -> bool {
+        depth > 0 || !self.is_global
+    }
+
+    fn lookup(&self, name: &str) -> Option<Target> {
+        self.active_scopes
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(depth, _)| self.has_slots(*depth))
+            .find_map(|(depth, &scope)| {
+                let slot = self.scope_names[scope].iter().position(|n| n == name)?;
+                Some(Target::Slot { depth, slot })
+            })
+    }
+
+    fn declare(&mut self, name: &str) -> usize {
+        let scope = *self.active_scopes.last().unwrap();
+        self.scope_names[scope].push(name.to_string());
+        self.scope_names[scope].len() - 1
+    }
+
+    fn load_target(&mut self, name: &str) -> Target {
+        self.lookup(name)
+            .unwrap_or_else(|| Target::Name(self.name(name)))
+    }
+
+    fn store_target(&mut self, name: &str) -> Target {
+        if let Some(target) = self.lookup(name) {
+            return target;
+        }
+        if !self.has_slots(self.depth()) {
+            return Target::Name(self.name(name));
+        }
+        Target::Slot {
+            depth: self.depth(),
+            slot: self.declare(name),
+        }
+    }
+
+    fn update(&mut self, op: &Node<Operator>, meta: &NodeMeta) -> Option<Update> {
+        let method = builtin::op::method_for_assignment_op(&op.v)?;
+        Some(Update {
+            method: self.name(method),
+            meta: self.meta(meta),
+        })
+    }
+
+    fn exec_fallback(&mut self, statement: &Node<Statement>) {
+        self.chunk.statements.push(statement.clone());
+        self.emit(Instruction::Exec(self.chunk.statements.len() - 1));
+    }
+
+    fn eval_fallback(&mut self, expression: &Node<Expression>) {
+        self.chunk.expressions.push(expression.clone());
+        self.emit(Instruction::Eval(self.chunk.expressions.len() - 1));
+    }
+
+    /// Leaves the value of the block on the stack, like `Runtime::eval_block`.
+    fn compile_block_value(&mut self, block: &Node<Block>) {
+        let statements = &block.v.statements;
+        for (i, statement) in statements.iter().enumerate() {
+            match &statement.v {
+                Statement::Expression(expression) if i == statements.len() - 1 => {
+                    self.compile_expression(expression);
+                    return;
+                }
+                _ => self.compile_statement(statement),
+            }
+        }
+        self.emit(Instruction::Nil);
+    }
+
+    fn compile_block(&mut self, block: &Node<Block>) {
+        for statement in &block.v.statements {
+            self.compile_statement(statement);
+        }
+    }
+
+    fn compile_statement(&mut self, statement: &Node<Statement>) {
+        match &statement.v {
+            Statement::Expression(expression) => {
+                self.compile_expression(expression);
+                self.emit(Instruction::Pop);
+            }
+            Statement::Assignment(assignment) => self.compile_assignment(statement, assignment),
+            Statement::ForIn(for_in) => self.compile_for_in(for_in),
+            Statement::WhileLoop(while_loop) => self.compile_while_loop(while_loop),
+            Statement::Break(_) => {
+                self.emit(Instruction::Break);
+            }
+            Statement::Continue(_) => {
+                self.emit(Instruction::Continue);
+            }
+            Statement::Return(return_stmt) => {
+                let has_value = if let Some(retval) = &return_stmt.v.retval {
+                    self.compile_expression(retval);
+                    true
+                } else {
+                    false
+                };
+                let meta = self.meta(&return_stmt.meta);
+                self.emit(Instruction::Return { has_value, meta });
+            }
+            Statement::Raise(raise) => {
+                self.compile_expression(&raise.v.value);
+                let meta = self.meta(&raise.meta);
+                self.emit(Instruction::Raise { meta });
+            }
+            Statement::Try(try_stmt) => self.compile_try(try_stmt),
+            Statement::MethodDefinition(_)
+            | Statement::ClassDefinition(_)
+            | Statement::TraitDefinition(_)
+            | Statement::Include(_)
+            | Statement::Use(_)
+            | Statement::Import(_) => self.exec_fallback(statement),
+        }
+    }
+
+    fn compile_assignment(&mut self, statement: &Node<Statement>, assignment: &Node<Assignment>) {
+        let Assignment { target, op, value } = &assignment.v;
+        match &target.v {
+            LValue::Binding(binding) if binding.v.variables.len() == 1 => {
+                self.compile_expression(value);
+                let var = &binding.v.variables[0];
+                let update = self.update(op, &assignment.meta);
+                let target = self.store_target(&var.v.ident.v.name);
+                let meta = self.meta(&var.meta);
+                self.emit(Instruction::Store {
+                    target,
+                    update,
+                    meta,
+                });
+            }
+            LValue::Binding(binding) if op.v == Operator::Equal => {
+                self.compile_expression(value);
+                let targets = binding
+                    .v
+                    .variables
+                    .iter()
+                    .map(|var| self.store_target(&var.v.ident.v.name))
+                    .collect();
+                self.chunk.targets.push(targets);
+                let targets = self.chunk.targets.len() - 1;
+                let meta = self.meta(&assignment.meta);
+                self.emit(Instruction::Destructure { targets, meta });
+            }
+            LValue::Access(access) => {
+                let Expression::Variable(member) = &access.v.member.v else {
+                    return self.exec_fallback(statement);
+                };
+                self.compile_expression(value);
+                self.compile_expression(&access.v.target);
+                let name = self.name(&member.v.ident.v.name);
+                let update = self.update(op, &assignment.meta);
+                self.emit(Instruction::SetProperty { name, update });
+            }
+            LValue::Index(index) => {
+                self.compile_expression(value);
+                self.compile_expression(&index.v.target);
+                self.compile_expression(&index.v.index);
+                let update = self.update(op, &assignment.meta);
+                let meta = self.meta(&index.meta);
+                self.emit(Instruction::SetIndex { update, meta });
+            }
+            LValue::Binding(_) => self.exec_fallback(statement),
+        }
+    }
+
+    fn compile_for_in(&mut self, for_in: &Node<ForIn>) {
+        self.compile_expression(&for_in.v.iterable);
+        let iterate = Some(self.meta(&for_in.v.iterable.meta));
+        let enter_loop = self.emit(Instruction::EnterLoop {
+            next: 0,
+            exit: 0,
+            iterate,
+        });
+        let scope = self.begin_scope();
+        self.emit(Instruction::PushScope(scope));
+        let next = self.here();
+        let meta = self.meta(&for_in.meta);
+        let next_item = self.emit(Instruction::Next { exit: 0, meta });
+        if let [var] = &for_in.v.binding[..] {
+            let slot = self.declare(&var.v.ident.v.name);
+            self.emit(Instruction::Define { slot });
+        } else {
+            let depth = self.depth();
+            let targets = for_in
+                .v
+                .binding
+                .iter()
+                .map(|var| Target::Slot {
+                    depth,
+                    slot: self.declare(&var.v.ident.v.name),
+                })
+                .collect();
+            self.chunk.targets.push(targets);
+            let targets = self.chunk.targets.len() - 1;
+            self.emit(Instruction::BindItem { targets, meta });
+        }
+        self.compile_block(&for_in.v.body);
+        self.emit(Instruction::Jump(next));
+        let exit = self.here();
+        self.patch_jump(next_item);
+        self.emit(Instruction::PopScope);
+        self.end_scope();
+        self.emit(Instruction::ExitLoop);
+        self.chunk.code[enter_loop] = Instruction::EnterLoop {
+            next,
+            exit,
+            iterate,
+        };
+    }
+
+    fn compile_while_loop(&mut self, while_loop: &Node<WhileLoop>) {
+        let enter_loop = self.emit(Instruction::EnterLoop {
+            next: 0,
+            exit: 0,
+            iterate: None,
+        });
+        let scope = self.begin_scope();
+        self.emit(Instruction::PushScope(scope));
+        let next = self.here();
+        self.compile_expression(&while_loop.v.condition);
+        let exit_jump = self.emit(Instruction::JumpIfFalse(0));
+        self.compile_block(&while_loop.v.body);
+        self.emit(Instruction::Jump(next));
+        let exit = self.here();
+        self.patch_jump(exit_jump);
+        self.emit(Instruction::PopScope);
+        self.end_scope();
+        self.emit(Instruction::ExitLoop);
+        self.chunk.code[enter_loop] = Instruction::EnterLoop {
+            next,
+            exit,
+            iterate: None,
+        };
+    }
+
+    /// Lays out `try` as a rescue region nested in an ensure region. The ensure body is compiled
+    /// twice: once for falling out of the try normally, and once in the handler that runs it
+    /// before unwinding further.
+    fn compile_try(&mut self, try_stmt: &Node<Try>) {
+        let Try {
+            body,
+            rescues,
+            ensure,
+        } = &try_stmt.v;
+        let enter_ensure = ensure.as_ref().map(|_| {
+            self.emit(Instruction::EnterTry {
+                handler: 0,
+                catches_all: true,
+            })
+        });
+        if rescues.is_empty() {
+            self.compile_block(body);
+        } else {
+            let enter_rescue = self.emit(Instruction::EnterTry {
+                handler: 0,
+                catches_all: false,
+            });
+            self.compile_block(body);
+            self.emit(Instruction::ExitTry);
+            let mut done_jumps = vec![self.emit(Instruction::Jump(0))];
+            self.patch_handler(enter_rescue);
+            for rescue in rescues {
+                let skip_jump = rescue.v.class.as_ref().map(|class| {
+                    self.compile_expression(class);
+                    let meta = self.meta(&class.meta);
+                    self.emit(Instruction::Rescues { meta });
+                    self.emit(Instruction::JumpIfFalse(0))
+                });
+                match &rescue.v.binding {
+                    Some(binding) => {
+                        let target = self.store_target(&binding.v.ident.v.name);
+                        let meta = self.meta(&binding.meta);
+                        self.emit(Instruction::Store {
+                            target,
+                            update: None,
+                            meta,
+                        });
+                    }
+                    None => {
+                        self.emit(Instruction::Pop);
+                    }
+                }
+                self.emit(Instruction::DropCaught);
+                self.compile_block(&rescue.v.body);
+                done_jumps.push(self.emit(Instruction::Jump(0)));
+                if let Some(skip_jump) = skip_jump {
+                    self.patch_jump(skip_jump);
+                }
+            }
+            self.emit(Instruction::Reraise);
+            for done_jump in done_jumps {
+                self.patch_jump(done_jump);
+            }
+        }
+        if let (Some(enter_ensure), Some(ensure)) = (enter_ensure, ensure) {
+            self.emit(Instruction::ExitTry);
+            self.compile_block(ensure);
+            let end_jump = self.emit(Instruction::Jump(0));
+            self.patch_handler(enter_ensure);
+            self.compile_block(ensure);
+            self.emit(Instruction::Reraise);
+            self.patch_jump(end_jump);
+        }
+    }
+
+    /// Keeps the subject on the stack while cases try it in turn, and pops it before running
+    /// the body of the case that matches. Without one the match is `nil`.
+    /// Compiles a match. Each case runs in a scope of its own holding what its pattern binds,
+    /// so that the bindings neither assign to the variables outside nor outlive the case.
+    fn compile_match(&mut self, match_expr: &Node<Match>) {
+        let Match { subject, cases } = &match_expr.v;
+        self.compile_expression(subject);
+        let mut end_jumps = Vec::new();
+        for case in cases {
+            let pattern = &case.v.pattern;
+            let scope = self.begin_scope();
+            self.emit(Instruction::PushScope(scope));
+            let depth = self.depth();
+            let targets: Vec<_> = pattern
+                .v
+                .bound_names()
+                .into_iter()
+                .map(|name| Target::Slot {
+                    depth,
+                    slot: self.declare(name),
+                })
+                .collect();
+            self.emit_match_case(pattern, targets);
+            let no_match = self.emit(Instruction::JumpIfFalse(0));
+            let guard_failed = case.v.guard.as_ref().map(|guard| {
+                self.compile_expression(guard);
+                self.emit(Instruction::JumpIfFalse(0))
+            });
+            self.emit(Instruction::Pop);
+            self.compile_block_value(&case.v.body);
+            self.emit(Instruction::PopScope);
+            self.end_scope();
+            end_jumps.push(self.emit(Instruction::Jump(0)));
+            self.patch_jump(no_match);
+            if let Some(guard_failed) = guard_failed {
+                self.patch_jump(guard_failed);
+            }
+            self.emit(Instruction::PopScope);
+        }
+        self.emit(Instruction::Pop);
+        self.emit(Instruction::Nil);
+        for end_jump in end_jumps {
+            self.patch_jump(end_jump);
+        }
+    }
+
+    fn emit_match_case(&mut self, pattern: &Node<Pattern>, targets: Vec<Target>) {
+        self.chunk.targets.push(targets);
+        self.chunk.patterns.push(pattern.clone());
+        self.emit(Instruction::MatchCase {
+            pattern: self.chunk.patterns.len() - 1,
+            targets: self.chunk.targets.len() - 1,
+        });
+    }
+
+    fn patch_handler(&mut self, at: usize) {
+        let target = self.here();
+        let Instruction::EnterTry { handler, .. } = &mut self.chunk.code[at] else {
+            unreachable!("not a try: {:?}", self.chunk.code[at]);
+        };
+        *handler = target;
+    }
+
+    fn compile_expressions(&mut self, expressions: &[Node<Expression>]) -> usize {
+        for expression in expressions {
+            self.compile_expression(expression);
+        }
+        expressions.len()
+    }
+
+    /// Compiles the values of keyword arguments and returns the index of their names, if any.
+    fn compile_keywords(&mut self, keywords: &[(Node<Ident>, Node<Expression>)]) -> Option<usize> {
+        if keywords.is_empty() {
+            return None;
+        }
+        let names = keywords
+            .iter()
+            .map(|(name, value)| {
+                self.compile_expression(value);
+                name.v.name.clone()
+            })
+            .collect();
+        self.chunk.keyword_names.push(names);
+        Some(self.chunk.keyword_names.len() - 1)
+    }
+
+    fn compile_expression(&mut self, expression: &Node<Expression>) {
+        match &expression.v {
+            Expression::Literal(literal) => self.compile_literal(literal),
+            Expression::Variable(var) => {
+                let name = &var.v.ident.v.name;
+                if name == builtin::SELF {
+                    self.emit(Instruction::LoadSelf);
+                    return;
+                }
+                let target = self.load_target(name);
+                let meta = self.meta(&var.meta);
+                self.emit(Instruction::Load { target, meta });
+            }
+            Expression::Path(path) => {
+                self.chunk.paths.push(path.clone());
+                self.emit(Instruction::LoadPath(self.chunk.paths.len() - 1));
+            }
+            // only a call can use `super`, so evaluating it fails
+            Expression::Super(_) => self.eval_fallback(expression),
+            Expression::Closure(closure) => {
+                self.chunk.closures.push(ClosurePrototype {
+                    node: closure.clone(),
+                    compiled: OnceCell::new(),
+                });
+                self.emit(Instruction::MakeClosure(self.chunk.closures.len() - 1));
+            }
+            Expression::IfElse(if_else) => {
+                self.compile_expression(&if_else.v.condition);
+                let else_jump = self.emit(Instruction::JumpIfFalse(0));
+                self.compile_block_value(&if_else.v.then_body);
+                let end_jump = self.emit(Instruction::Jump(0));
+                self.patch_jump(else_jump);
+                match &if_else.v.else_body {
+                    Some(else_body) => self.compile_block_value(else_body),
+                    None => {
+                        self.emit(Instruction::Nil);
+                    }
+                }
+                self.patch_jump(end_jump);
+            }
+            Expression::Match(match_expr) => self.compile_match(match_expr),
+            Expression::Binary(binary) => {
+                self.compile_expression(&binary.v.lhs);
+                let short_circuit = match binary.v.op.v {
+                    Operator::LogicalOr => Some(Instruction::JumpIfTrueOrPop(0)),
+                    Operator::LogicalAnd => Some(Instruction::JumpIfFalseOrPop(0)),
+                    _ => None,
+                };
+                if let Some(short_circuit) = short_circuit {
+                    let jump = self.emit(short_circuit);
+                    self.compile_expression(&binary.v.rhs);
+                    self.patch_jump(jump);
+                    return;
+                }
+                self.compile_expression(&binary.v.rhs);
+                let method = builtin::op::method_for_binary_op(&binary.v.op.v).unwrap();
+                let name = self.name(method);
+                let meta = self.meta(&binary.meta);
+                self.emit(Instruction::CallMethod {
+                    name,
+                    argc: 1,
+                    keywords: None,
+                    meta,
+                });
+            }
+            Expression::Unary(unary) => {
+                self.compile_expression(&unary.v.rhs);
+                let method = builtin::op::method_for_unary_op(&unary.v.op.v).unwrap();
+                let name = self.name(method);
+                let meta = self.meta(&unary.meta);
+                self.emit(Instruction::CallMethod {
+                    name,
+                    argc: 0,
+                    keywords: None,
+                    meta,
+                });
+            }
+            Expression::Propagate(propagate) => {
+                self.compile_expression(&propagate.v.value);
+                let meta = self.meta(&propagate.meta);
+                self.emit(Instruction::Propagate { meta });
+            }
+            Expression::Index(index) => {
+                self.compile_expression(&index.v.target);
+                self.compile_expression(&index.v.index);
+                let name = self.name(builtin::op::__index__);
+                let meta = self.meta(&index.meta);
+                self.emit(Instruction::CallMethod {
+                    name,
+                    argc: 1,
+                    keywords: None,
+                    meta,
+                });
+            }
+            Expression::Access(access) => match &access.v.member.v {
+                Expression::Variable(var) => {
+                    self.compile_expression(&access.v.target);
+                    let name = self.name(&var.v.ident.v.name);
+                    let meta = self.meta(&access.meta);
+                    self.emit(Instruction::GetProperty { name, meta });
+                }
+                Expression::Call(call) => {
+                    let Expression::Variable(var) = &call.v.target.v else {
+                        return self.eval_fallback(expression);
+                    };
+                    self.compile_expression(&access.v.target);
+                    let argc = self.compile_expressions(&call.v.arguments);
+                    let keywords = self.compile_keywords(&call.v.keywords);
+                    let name = self.name(&var.v.ident.v.name);
+                    let meta = self.meta(&call.meta);
+                    self.emit(Instruction::CallMethod {
+                        name,
+                        argc,
+                        keywords,
+                        meta,
+                    });
+                }
+                Expression::Literal(literal) => {
+                    let Literal::Int(index) = &literal.v else {
+                        return self.eval_fallback(expression);
+                    };
+                    self.compile_expression(&access.v.target);
+                    let meta = self.meta(&access.meta);
+                    self.emit(Instruction::GetItem {
+                        index: index.v.value as usize,
+                        meta,
+                    });
+                }
+                _ => self.eval_fallback(expression),
+            },
+            Expression::Call(call) => {
+                let target = &call.v.target;
+                let meta = self.meta(&call.meta);
+                match &target.v {
+                    Expression::Variable(var) => {
+                        let target = self.load_target(&var.v.ident.v.name);
+                        let callee_meta = self.meta(&var.meta);
+                        self.emit(Instruction::ResolveNamed {
+                            target,
+                            meta,
+                            callee_meta,
+                        });
+                    }
+                    Expression::Path(path) => {
+                        self.chunk.paths.push(path.clone());
+                        let path = self.chunk.paths.len() - 1;
+                        self.emit(Instruction::ResolvePath { path, meta });
+                    }
+                    Expression::Super(super_ref) => {
+                        let method = super_ref
+                            .v
+                            .method
+                            .as_ref()
+                            .map(|ident| self.name(&ident.v.name));
+                        self.emit(Instruction::ResolveSuper { method, meta });
+                    }
+                    _ => {
+                        self.compile_expression(target);
+                        let meta = self.meta(&target.meta);
+                        self.emit(Instruction::ResolveValue { meta });
+                    }
+                }
+                let argc = self.compile_expressions(&call.v.arguments);
+                let keywords = self.compile_keywords(&call.v.keywords);
+                self.emit(Instruction::Call {
+                    argc,
+                    keywords,
+                    meta,
+                });
+            }
+        }
+    }
+
+    fn compile_literal(&mut self, literal: &Node<Literal>) {
+        match &literal.v {
+            Literal::Int(int) => {
+                let constant = self.number_constant(Number::Int(int.v.value));
+                self.emit(Instruction::Constant(constant));
+            }
+            Literal::BigInt(int) => {
+                let constant = self.number_constant(Number::integer(int.v.value.clone()));
+                self.emit(Instruction::Constant(constant));
+            }
+            Literal::Rational(rational) => {
+                let constant = self.number_constant(Number::Rational(rational.v.value.clone()));
+                self.emit(Instruction::Constant(constant));
+            }
+            Literal::Float(float) => {
+                let constant = self.number_constant(Number::Float(float.v.value));
+                self.emit(Instruction::Constant(constant));
+            }
+            Literal::StringLit(string) => {
+                let constant = self.string_constant(&string.v.value);
+                self.emit(Instruction::Constant(constant));
+            }
+            Literal::InterpolatedString(string) => {
+                let count = self.compile_expressions(&string.v.parts);
+                self.emit(Instruction::BuildString(count));
+            }
+            Literal::Boolean(boolean) => {
+                self.emit(if boolean.v.value {
+                    Instruction::True
+                } else {
+                    Instruction::False
+                });
+            }
+            Literal::Nil(_) => {
+                self.emit(Instruction::Nil);
+            }
+            Literal::Array(array) => {
+                let count = self.compile_expressions(&array.v.elements);
+                self.emit(Instruction::BuildArray(count));
+            }
+            Literal::Tuple(tuple) => {
+                let count = self.compile_expressions(&tuple.v.items);
+                self.emit(Instruction::BuildTuple(count));
+            }
+            Literal::Dictionary(dictionary) => {
+                for (key, value) in &dictionary.v.entries {
+                    self.compile_expression(key);
+                    self.compile_expression(value);
+                }
+                self.emit(Instruction::BuildDictionary(dictionary.v.entries.len()));
+            }
+        }
+    }
+}
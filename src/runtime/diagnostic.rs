@@ -0,0 +1,127 @@
+use crate::runtime::Error::{Located, NoSuchVariable, Traced};
+use crate::runtime::{builtin, Error, Runtime};
+use crate::types::NodeMeta;
+
+impl Error {
+    /// The error without the context it has picked up while unwinding.
+    pub fn root(&self) -> &Error {
+        match self {
+            Located { error, .. } | Traced { error, .. } => error.root(),
+            error => error,
+        }
+    }
+
+    /// The error's message, without the location that its `Display` ends with.
+    pub fn message(&self) -> String {
+        let error = self.root();
+        let mut message = error.to_string();
+        if let Some(node) = error.node()
+            && let Some(stripped) = message.strip_suffix(&format!(": {node}"))
+        {
+            message.truncate(stripped.len());
+        }
+        message
+    }
+
+    /// A suggestion for fixing the error, if there is one.
+    pub fn hint(&self) -> Option<String> {
+        match self.root() {
+            NoSuchVariable {
+                similar: Some(similar),
+                ..
+            } => Some(format!(
+                "a variable with a similar name exists: '{similar}'"
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl Runtime {
+    /// The error of a missing variable. Only an error that reaches the user gets a hint, so
+    /// there is no looking for a similar name while a `try` may still catch it.
+    pub(super) fn no_such_variable(&self, name: String, node: &NodeMeta) -> Error {
+        let similar = if self.handlers == 0 {
+            self.similar_variable_name(&name)
+        } else {
+            None
+        };
+        NoSuchVariable {
+            name,
+            node: node.clone(),
+            similar,
+        }
+    }
+
+    /// The visible name closest to `name`, if any is close enough to be a likely typo of it.
+    /// The distance must stay below the length of `name`, or every short name would be a typo
+    /// of every other.
+    fn similar_variable_name(&self, name: &str) -> Option<String> {
+        let length = name.chars().count();
+        let max_distance = (length / 3).max(1).min(length.saturating_sub(1));
+        self.visible_names()
+            .into_iter()
+            .filter(|candidate| candidate != name)
+            .map(|candidate| (edit_distance(name, &candidate), candidate))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min()
+            .map(|(_, candidate)| candidate)
+    }
+
+    /// The names that `resolve_variable` could find from the current frame.
+    fn visible_names(&self) -> Vec<String> {
+        let mut names = vec![builtin::SELF.to_string()];
+        let mut found_instance = false;
+        let mut found_class = false;
+        for frame in self.stack.iter().rev() {
+            names.extend(frame.variables.keys().cloned());
+            names.extend(
+                frame
+                    .slot_names
+                    .iter()
+                    .zip(&frame.slots)
+                    .filter(|(_, value)| value.is_some())
+                    .map(|(name, _)| name.clone()),
+            );
+            names.extend(frame.cells.keys().cloned());
+            names.extend(frame.captures.keys().cloned());
+            if !found_instance && let Some(instance) = &frame.instance {
+                found_instance = true;
+                names.extend(instance.borrow().property_names());
+            }
+            if !found_class && let Some(class) = &frame.class {
+                found_class = true;
+                names.extend(class.borrow().method_names());
+            }
+        }
+        names
+    }
+}
+
+/// The number of single-character insertions, deletions, substitutions and swaps of adjacent
+/// characters that turn one string into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // distances[i][j] is the distance between the first i chars of a and the first j of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = substitution
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
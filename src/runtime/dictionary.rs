@@ -0,0 +1,297 @@
+use std::cell::Ref;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use crate::runtime::number::Number;
+use crate::runtime::object::{receiver_primitive, Object, ObjectRef, Primitive};
+use crate::runtime::Error::TypeMismatch;
+use crate::runtime::{builtin, Result, Runtime};
+
+/// The entries of a dictionary, in the order they were added. A key can be any value: entries
+/// are indexed by the hash of their key and told apart within a hash with `==`. Both may run
+/// concorde code, so the runtime works out where a key goes and this only stores the result.
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    /// The entries in order, with `None` where one was removed. The gaps are closed once they
+    /// outnumber the entries, so that removing an entry does not move those after it.
+    entries: Vec<Option<Entry>>,
+    /// The positions in `entries` of the keys with each hash.
+    positions: HashMap<u64, Vec<usize>>,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    hash: u64,
+    key: ObjectRef,
+    value: ObjectRef,
+}
+
+impl Dictionary {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ObjectRef, &ObjectRef)> {
+        self.entries
+            .iter()
+            .flatten()
+            .map(|entry| (&entry.key, &entry.value))
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.positions.clear();
+        self.len = 0;
+    }
+
+    /// The positions and keys of the entries whose key has `hash`.
+    fn candidates(&self, hash: u64) -> Vec<(usize, ObjectRef)> {
+        self.positions
+            .get(&hash)
+            .map_or_else(Vec::new, |positions| {
+                positions
+                    .iter()
+                    .filter_map(|&position| Some((position, self.entry(position)?.key.clone())))
+                    .collect()
+            })
+    }
+
+    fn entry(&self, position: usize) -> Option<&Entry> {
+        self.entries.get(position)?.as_ref()
+    }
+
+    fn value(&self, position: usize) -> Option<ObjectRef> {
+        self.entry(position).map(|entry| entry.value.clone())
+    }
+
+    /// Sets the value of the entry at `position`, or adds an entry at the end when there is
+    /// none there.
+    fn insert(&mut self, hash: u64, position: Option<usize>, key: ObjectRef, value: ObjectRef) {
+        let existing = position.and_then(|position| self.entries.get_mut(position)?.as_mut());
+        match existing {
+            Some(entry) => entry.value = value,
+            None => {
+                self.positions
+                    .entry(hash)
+                    .or_default()
+                    .push(self.entries.len());
+                self.entries.push(Some(Entry { hash, key, value }));
+                self.len += 1;
+            }
+        }
+    }
+
+    /// Removes the entry at `position`, leaving a gap in its place.
+    fn remove(&mut self, hash: u64, position: usize) -> Option<ObjectRef> {
+        let entry = self.entries.get_mut(position)?.take()?;
+        if let Some(positions) = self.positions.get_mut(&hash) {
+            positions.retain(|&other| other != position);
+            if positions.is_empty() {
+                self.positions.remove(&hash);
+            }
+        }
+        self.len -= 1;
+        if self.entries.len() > 2 * self.len {
+            self.compact();
+        }
+        Some(entry.value)
+    }
+
+    /// Closes the gaps left by removed entries, which renumbers the rest.
+    fn compact(&mut self) {
+        self.entries.retain(Option::is_some);
+        self.positions.clear();
+        for (position, entry) in self.entries.iter().flatten().enumerate() {
+            self.positions.entry(entry.hash).or_default().push(position);
+        }
+    }
+}
+
+/// The entries of the `Dictionary`, or of the `Set`, a method is called on.
+pub(super) fn hash_table_receiver(this: &ObjectRef) -> Result<Ref<'_, Dictionary>> {
+    let expected = format!("{} or {}", builtin::class::Dictionary, builtin::class::Set);
+    receiver_primitive(this, &expected, Object::hash_table)
+}
+
+/// The `Int` that `__hash__` returns for a hashable Rust value.
+pub(super) fn hash_of(value: impl Hash) -> i64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    // keep within the integers a Float holds exactly
+    (hasher.finish() >> 11) as i64
+}
+
+/// The bucket a `__hash__` result files a key under. Equal numbers share one whatever their
+/// class: a number equal to a `Float` files as that `Float`, with zero and negative zero
+/// together, and any other as its exact value.
+fn hash_bits(hash: &Number) -> u64 {
+    let float = hash.as_f64();
+    let is_float = matches!(hash, Number::Float(_))
+        || hash.compare(&Number::Float(float)) == Some(Ordering::Equal);
+    if !is_float {
+        let mut hasher = DefaultHasher::new();
+        hash.to_rational().hash(&mut hasher);
+        return hasher.finish();
+    }
+    if float == 0.0 {
+        0
+    } else {
+        float.to_bits()
+    }
+}
+
+impl Runtime {
+    /// Creates a dictionary from its entries in order, a later entry replacing an earlier one
+    /// with an equal key.
+    pub fn build_dictionary(&mut self, entries: Vec<(ObjectRef, ObjectRef)>) -> Result<ObjectRef> {
+        let dictionary = self.create_object(self.builtins.Dictionary.clone());
+        dictionary
+            .borrow_mut()
+            .set_primitive(Primitive::Dictionary(Dictionary::default()));
+        for (key, value) in entries {
+            self.dictionary_insert(&dictionary, key, value)?;
+        }
+        Ok(dictionary)
+    }
+
+    pub(super) fn dictionary_get(
+        &mut self,
+        dictionary: &ObjectRef,
+        key: &ObjectRef,
+    ) -> Result<Option<ObjectRef>> {
+        let (_, position) = self.find_entry(dictionary, key)?;
+        let dictionary_ref = dictionary.borrow();
+        Ok(position.and_then(|position| dictionary_ref.hash_table().unwrap().value(position)))
+    }
+
+    pub(super) fn dictionary_insert(
+        &mut self,
+        dictionary: &ObjectRef,
+        key: ObjectRef,
+        value: ObjectRef,
+    ) -> Result<()> {
+        let (hash, position) = self.find_entry(dictionary, &key)?;
+        dictionary
+            .borrow_mut()
+            .hash_table_mut()
+            .unwrap()
+            .insert(hash, position, key, value);
+        Ok(())
+    }
+
+    /// Removes the entry for `key`, returning its value. Like the other `dictionary_` methods,
+    /// it works on a set too, whose elements are its keys.
+    pub(super) fn dictionary_remove(
+        &mut self,
+        dictionary: &ObjectRef,
+        key: &ObjectRef,
+    ) -> Result<Option<ObjectRef>> {
+        let (hash, position) = self.find_entry(dictionary, key)?;
+        let mut dictionary_mut = dictionary.borrow_mut();
+        let entries = dictionary_mut.hash_table_mut().unwrap();
+        Ok(position.and_then(|position| entries.remove(hash, position)))
+    }
+
+    pub(super) fn contains_key(&mut self, dictionary: &ObjectRef, key: &ObjectRef) -> Result<bool> {
+        let (_, position) = self.find_entry(dictionary, key)?;
+        Ok(position.is_some())
+    }
+
+    /// Whether two dictionaries, or two sets, have equal values for the same keys, in any order.
+    pub(super) fn dictionaries_equal(&mut self, a: &ObjectRef, b: &ObjectRef) -> Result<bool> {
+        let entries: Vec<_> = {
+            let (a_entries, b_entries) = (hash_table_receiver(a)?, hash_table_receiver(b)?);
+            if a_entries.len() != b_entries.len() {
+                return Ok(false);
+            }
+            a_entries
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()
+        };
+        for (key, value) in entries {
+            match self.dictionary_get(b, &key)? {
+                Some(other) if self.values_equal(&value, &other)? => {}
+                _ => return Ok(false),
+            }
+        }
+        Ok(true)
+    }
+
+    /// The hash of `key` and the position of the entry with an equal key, if there is one. No
+    /// borrow of the dictionary is held while `__hash__` and `__eq__` run.
+    fn find_entry(
+        &mut self,
+        dictionary: &ObjectRef,
+        key: &ObjectRef,
+    ) -> Result<(u64, Option<usize>)> {
+        let hash = self.hash_key(key)?;
+        let candidates = hash_table_receiver(dictionary)?.candidates(hash);
+        for (position, other) in candidates {
+            if self.values_equal(key, &other)? {
+                return Ok((hash, Some(position)));
+            }
+        }
+        Ok((hash, None))
+    }
+
+    /// Creates a set of the distinct elements among `elements`, in the order they first appear.
+    pub fn create_set(&mut self, elements: Vec<ObjectRef>) -> Result<ObjectRef> {
+        let set = self.create_object(self.builtins.Set.clone());
+        set.borrow_mut()
+            .set_primitive(Primitive::Set(Dictionary::default()));
+        for element in elements {
+            self.dictionary_insert(&set, element.clone(), element)?;
+        }
+        Ok(set)
+    }
+
+    /// The hash of a value, which is what its `__hash__` method returns.
+    pub(super) fn hash_key(&mut self, key: &ObjectRef) -> Result<u64> {
+        {
+            let key_ref = key.borrow();
+            if let Some(string) = key_ref.string() {
+                return Ok(hash_bits(&Number::Int(hash_of(string))));
+            }
+            if let Some(number) = key_ref.number() {
+                return Ok(hash_bits(&number));
+            }
+        }
+        let hash = self.call_instance_method(key.clone(), builtin::op::__hash__, None, None)?;
+        let hash_ref = hash.borrow();
+        hash_ref
+            .number()
+            .map(|hash| hash_bits(&hash))
+            .ok_or_else(|| TypeMismatch {
+                class: hash_ref.__class__().borrow().__name__().unwrap(),
+                expected: builtin::class::Number.into(),
+            })
+    }
+
+    /// Whether two values are equal by `==`, which is how dictionary keys are compared.
+    pub(super) fn values_equal(&mut self, a: &ObjectRef, b: &ObjectRef) -> Result<bool> {
+        if Rc::ptr_eq(a, b) {
+            return Ok(true);
+        }
+        {
+            let (a_ref, b_ref) = (a.borrow(), b.borrow());
+            if let (Some(a), Some(b)) = (a_ref.string(), b_ref.string()) {
+                return Ok(a == b);
+            }
+            if let (Some(a), Some(b)) = (a_ref.number(), b_ref.number()) {
+                return Ok(a.compare(&b) == Some(Ordering::Equal));
+            }
+        }
+        let equal =
+            self.call_instance_method(a.clone(), builtin::op::__eq__, Some(b.clone()), None)?;
+        Ok(self.is_truthy(&equal))
+    }
+}
@@ -0,0 +1,156 @@
+use crate::parse;
+use crate::runtime::object::{MethodBody, MethodReceiver, ObjectRef, Param};
+use crate::runtime::trace::MAIN_FRAME;
+use crate::runtime::value::{IntoValue, Value};
+use crate::runtime::Error::Host;
+use crate::runtime::{Error, Result, Runtime};
+use crate::types::{NodeMeta, TopError};
+
+/// The name host functions and methods give the list of their arguments.
+const HOST_ARGS: &str = "args";
+
+impl Error {
+    /// An error for a host function to fail with, which concorde code can rescue as a
+    /// `RuntimeError`.
+    pub fn host(message: impl Into<String>) -> Self {
+        Host {
+            message: message.into(),
+        }
+    }
+}
+
+impl Runtime {
+    /// Converts a Rust value to a concorde one.
+    pub fn value(&mut self, value: impl IntoValue) -> Value {
+        value.into_value(self)
+    }
+
+    pub fn global(&mut self, name: &str) -> Option<Value> {
+        self.resolve_variable(name).map(Value)
+    }
+
+    pub fn set_global(&mut self, name: impl Into<String>, value: Value) {
+        self.assign_global(name.into(), value.0);
+    }
+
+    /// The class called `name`. Like a `class` statement, this reopens the class if it already
+    /// exists and creates it otherwise.
+    pub fn define_class(&mut self, name: &str) -> Value {
+        let class = self
+            .resolve_variable(name)
+            .filter(|object| self.is_class(object))
+            .unwrap_or_else(|| self.create_simple_class(name));
+        Value(class)
+    }
+
+    /// Defines an instance method implemented in Rust. It takes any number of arguments and is
+    /// called with its receiver.
+    pub fn define_method(
+        &mut self,
+        class: &Value,
+        name: &str,
+        function: impl Fn(&mut Runtime, Value, Vec<Value>) -> Result<Value> + 'static,
+    ) -> Result<()> {
+        self.define_host_method(class, MethodReceiver::Instance, name, function)
+    }
+
+    /// Defines a class method implemented in Rust, which is called with the class as its
+    /// receiver.
+    pub fn define_class_method(
+        &mut self,
+        class: &Value,
+        name: &str,
+        function: impl Fn(&mut Runtime, Value, Vec<Value>) -> Result<Value> + 'static,
+    ) -> Result<()> {
+        self.define_host_method(class, MethodReceiver::Class, name, function)
+    }
+
+    /// Defines a function implemented in Rust that concorde code can call by name, like one
+    /// defined at the top level of a script.
+    pub fn define_function(
+        &mut self,
+        name: &str,
+        function: impl Fn(&mut Runtime, Vec<Value>) -> Result<Value> + 'static,
+    ) -> Result<()> {
+        let main = Value(self.builtins.Main.clone());
+        self.define_class_method(&main, name, move |runtime, _main, args| {
+            function(runtime, args)
+        })
+    }
+
+    fn define_host_method(
+        &mut self,
+        class: &Value,
+        receiver: MethodReceiver,
+        name: &str,
+        function: impl Fn(&mut Runtime, Value, Vec<Value>) -> Result<Value> + 'static,
+    ) -> Result<()> {
+        let body = MethodBody::system(move |runtime, this, _method_name, args| {
+            let args = args.into_iter().map(Value).collect();
+            function(runtime, Value(this), args).map(|value| value.0)
+        });
+        class.0.borrow_mut().define_method(
+            receiver,
+            name.into(),
+            vec![Param::Vararg(HOST_ARGS.into())],
+            body,
+        )
+    }
+
+    /// Calls what `name(...)` would call at the top level: a function, a class to construct, or
+    /// a variable holding something callable.
+    pub fn call_function(&mut self, name: &str, args: Vec<Value>) -> Result<Value> {
+        let node = NodeMeta::host(name);
+        self.host_call(|runtime| {
+            let (receiver, method) = runtime.resolve_named_callee(name, &node, &node)?;
+            runtime.call_method(receiver, method, args.into_iter().map(|arg| arg.0))
+        })
+    }
+
+    /// Calls the instance method `name` on `receiver`.
+    pub fn call_method_named(
+        &mut self,
+        receiver: &Value,
+        name: &str,
+        args: Vec<Value>,
+    ) -> Result<Value> {
+        let node = NodeMeta::host(name);
+        let receiver = receiver.0.clone();
+        self.host_call(|runtime| {
+            let args = args.into_iter().map(|arg| arg.0);
+            runtime.call_instance_method(receiver, name, args, Some(node))
+        })
+    }
+
+    /// Calls a closure, method object or class.
+    pub fn call_value(&mut self, callable: &Value, args: Vec<Value>) -> Result<Value> {
+        let node = NodeMeta::host(callable.class_name());
+        let callable = callable.0.clone();
+        self.host_call(|runtime| {
+            let (receiver, method) = runtime.resolve_value_callee(callable, &node)?;
+            runtime.call_method(receiver, method, args.into_iter().map(|arg| arg.0))
+        })
+    }
+
+    /// Runs source code and returns the value of its last statement.
+    pub fn eval_source(&mut self, source: &str, name: &str) -> Result<Value, TopError> {
+        let program = parse::parse_source(source, name)?;
+        self.host_call(|runtime| runtime.eval_program(program))
+            .map_err(|error| {
+                error
+                    .traced(MAIN_FRAME, None, MethodReceiver::Instance)
+                    .into()
+            })
+    }
+
+    /// Runs a call made by the host, dropping whatever frames an error left on the stack so that
+    /// the runtime can be used again.
+    fn host_call(&mut self, call: impl FnOnce(&mut Runtime) -> Result<ObjectRef>) -> Result<Value> {
+        let depth = self.stack.len();
+        let result = call(self);
+        if result.is_err() {
+            self.stack.truncate(depth);
+        }
+        result.map(Value)
+    }
+}
@@ -0,0 +1,255 @@
+use std::rc::Rc;
+
+use crate::runtime::object::ObjectRef;
+use crate::runtime::Error::{
+    Arithmetic, ArityMismatch, AssignmentRhsMustBeTuple, BadIterator, BadPath, ControlFlow,
+    CyclicInclude, IllegalAssignmentOperator, IllegalAssignmentTarget, ImportCycle, Index,
+    IndexOutOfBounds, InvalidArgument, InvalidMember, KeywordArgument, Located, ModuleLoad,
+    ModuleNotFound, NoSuchMethod, NoSuchProperty, NoSuchVariable, NotCallable, ObjectNotCallable,
+    PatternFields, Raised, ReturnFromInitializer, ReturnFromMethod, SuperclassMismatch,
+    SyntaxError, Traced, TypeMismatch, UndefinedProperty, Unhashable,
+};
+use crate::runtime::{builtin, Error, Result, Runtime};
+use crate::types::{Node, NodeMeta, Rescue, Try};
+
+impl Error {
+    /// Whether `rescue` can catch this error. Control flow passes through `try` untouched.
+    pub fn is_catchable(&self) -> bool {
+        !matches!(self, ControlFlow(_) | ReturnFromMethod { .. })
+    }
+
+    /// Where the error happened, if that is known.
+    pub fn node(&self) -> Option<&NodeMeta> {
+        match self {
+            ReturnFromMethod { node, .. }
+            | ReturnFromInitializer { node }
+            | NoSuchVariable { node, .. }
+            | NoSuchProperty { node, .. }
+            | UndefinedProperty { node, .. }
+            | NotCallable { node }
+            | InvalidMember { node }
+            | IllegalAssignmentTarget { node }
+            | IllegalAssignmentOperator { node }
+            | AssignmentRhsMustBeTuple { node }
+            | IndexOutOfBounds { node, .. }
+            | PatternFields { node, .. }
+            | BadPath { path: node, .. }
+            | SuperclassMismatch { node, .. }
+            | CyclicInclude { node, .. }
+            | BadIterator { node, .. }
+            | ModuleNotFound { node, .. }
+            | ImportCycle { node, .. }
+            | Raised { node, .. }
+            | Located { node, .. } => Some(node),
+            NoSuchMethod { node, .. } | ObjectNotCallable { node } | SyntaxError { node, .. } => {
+                node.get()
+            }
+            Traced { error, .. } => error.node(),
+            _ => None,
+        }
+    }
+
+    /// Gives an error that was raised without a location, e.g. by a system method, the location
+    /// of the call it came out of.
+    ///
+    /// For an error being traced, this is also the position of the frame it is unwinding through.
+    pub fn at(self, node: &NodeMeta) -> Self {
+        if let Traced {
+            error,
+            traceback,
+            call_site,
+        } = self
+        {
+            return Traced {
+                error: Box::new(error.at(node)),
+                traceback,
+                call_site: call_site.or_else(|| Some(node.clone())),
+            };
+        }
+        if !self.is_catchable() || self.node().is_some() {
+            return self;
+        }
+        Located {
+            error: Box::new(self),
+            node: node.clone(),
+        }
+    }
+}
+
+impl Runtime {
+    /// Builds the error that `raise value` unwinds with. An exception class is instantiated and
+    /// a string becomes the message of a `RuntimeError`.
+    pub(super) fn raise(&mut self, value: ObjectRef, node: &NodeMeta) -> Error {
+        let exception = if self.is_class(&value) {
+            let constructed = self
+                .resolve_value_callee(value, node)
+                .and_then(|(receiver, init)| self.call_method(receiver, init, None));
+            match constructed {
+                Ok(exception) => exception,
+                Err(error) => return error.at(node),
+            }
+        } else if value.borrow().string().is_some() {
+            let exception = self.create_object(self.builtins.RuntimeError.clone());
+            exception
+                .borrow_mut()
+                .set_property(builtin::property::message, value);
+            exception
+        } else {
+            value
+        };
+        let class = exception.borrow().__class__();
+        if !self.inherits_from(&class, &self.builtins.Exception) {
+            return TypeMismatch {
+                expected: builtin::class::Exception.into(),
+                class: class.borrow().__name__().unwrap(),
+            }
+            .at(node);
+        }
+        if exception
+            .borrow()
+            .get_property(builtin::property::line)
+            .is_none()
+        {
+            self.set_exception_location(&exception, node);
+        }
+        self.raised(exception, node)
+    }
+
+    fn raised(&mut self, exception: ObjectRef, node: &NodeMeta) -> Error {
+        let class = exception.borrow().__class__().borrow().__name__().unwrap();
+        let message = self
+            .call_instance_method(exception.clone(), builtin::method::to_s, None, None)
+            .ok()
+            .and_then(|message| message.borrow().string().cloned())
+            .unwrap_or_default();
+        Raised {
+            exception,
+            class,
+            message,
+            node: node.clone(),
+        }
+    }
+
+    fn set_exception_location(&mut self, exception: &ObjectRef, node: &NodeMeta) {
+        let (line, column) = node.line_col;
+        let line = self.create_int(line as i64);
+        let column = self.create_int(column as i64);
+        let mut exception_mut = exception.borrow_mut();
+        exception_mut.set_property(builtin::property::line, line);
+        exception_mut.set_property(builtin::property::column, column);
+    }
+
+    /// The exception object that `rescue` binds for an error. Raised exceptions are handed back
+    /// as they are, internal errors become an instance of the matching exception class.
+    pub(super) fn exception_from_error(&mut self, error: &Error) -> ObjectRef {
+        if let Raised { exception, .. } = error.root() {
+            return exception.clone();
+        }
+        let class = self.exception_class(error.root());
+        let exception = self.create_object(class);
+        let message = self.create_string(error.message());
+        exception
+            .borrow_mut()
+            .set_property(builtin::property::message, message);
+        if let Some(node) = error.node() {
+            self.set_exception_location(&exception, node);
+        }
+        exception
+    }
+
+    fn exception_class(&self, error: &Error) -> ObjectRef {
+        let builtins = &self.builtins;
+        match error {
+            NoSuchVariable { .. } | NoSuchProperty { .. } | UndefinedProperty { .. } => {
+                &builtins.NameError
+            }
+            NoSuchMethod { .. } | ObjectNotCallable { .. } | NotCallable { .. } => {
+                &builtins.NoMethodError
+            }
+            ArityMismatch { .. } | KeywordArgument { .. } | InvalidArgument { .. } => {
+                &builtins.ArgumentError
+            }
+            TypeMismatch { .. }
+            | Unhashable { .. }
+            | AssignmentRhsMustBeTuple { .. }
+            | BadPath { .. }
+            | SuperclassMismatch { .. }
+            | CyclicInclude { .. }
+            | BadIterator { .. }
+            | PatternFields { .. } => &builtins.TypeError,
+            Index { .. } | IndexOutOfBounds { .. } => &builtins.IndexError,
+            Arithmetic { .. } => &builtins.ArithmeticError,
+            ModuleNotFound { .. } | ImportCycle { .. } | ModuleLoad { .. } => &builtins.ImportError,
+            _ => &builtins.RuntimeError,
+        }
+        .clone()
+    }
+
+    /// Whether a `rescue` clause naming `class` catches `exception`.
+    pub(super) fn rescues(
+        &self,
+        class: &ObjectRef,
+        exception: &ObjectRef,
+        node: &NodeMeta,
+    ) -> Result<bool> {
+        if !self.is_class(class) {
+            return Err(TypeMismatch {
+                expected: builtin::class::Class.into(),
+                class: class.borrow().__class__().borrow().__name__().unwrap(),
+            }
+            .at(node));
+        }
+        let exception_class = exception.borrow().__class__();
+        Ok(self.inherits_from(&exception_class, class))
+    }
+
+    pub(super) fn inherits_from(&self, class: &ObjectRef, ancestor: &ObjectRef) -> bool {
+        let ancestors = class.borrow().ancestors();
+        ancestors
+            .iter()
+            .any(|current| Rc::ptr_eq(current, ancestor))
+    }
+
+    pub(super) fn exec_try(&mut self, try_stmt: Node<Try>) -> Result<()> {
+        let Try {
+            body,
+            rescues,
+            ensure,
+        } = try_stmt.v;
+        let depth = self.stack.len();
+        self.handlers += 1;
+        let body_result = self.eval_block(body);
+        self.handlers -= 1;
+        let result = match body_result {
+            Err(error) if error.is_catchable() && !rescues.is_empty() => {
+                self.stack.truncate(depth);
+                self.exec_rescue(error, rescues)
+            }
+            result => result.map(|_| ()),
+        };
+        if let Some(ensure) = ensure {
+            self.stack.truncate(depth);
+            self.eval_block(ensure)?;
+        }
+        result
+    }
+
+    /// Runs the first clause that catches `error`, or hands the error back if none does.
+    fn exec_rescue(&mut self, error: Error, rescues: Vec<Node<Rescue>>) -> Result<()> {
+        let exception = self.exception_from_error(&error);
+        for rescue in rescues {
+            if let Some(class) = rescue.v.class {
+                let class_meta = class.meta.clone();
+                let class = self.eval(class)?;
+                if !self.rescues(&class, &exception, &class_meta)? {
+                    continue;
+                }
+            }
+            if let Some(binding) = rescue.v.binding {
+                self.assign_variable(binding.v.ident.v.name, exception);
+            }
+            return self.eval_block(rescue.v.body).map(|_| ());
+        }
+        Err(error)
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::runtime::object::ObjectRef;
+use crate::runtime::Runtime;
+
+/// Collection is triggered once this many objects have been allocated since the last one.
+pub const GC_ALLOCATION_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct GcStats {
+    pub collections: usize,
+    pub last_collected: usize,
+    pub total_collected: usize,
+    pub live_objects: usize,
+}
+
+type ObjectId = *const ();
+
+fn object_id(object: &ObjectRef) -> ObjectId {
+    Rc::as_ptr(object).cast()
+}
+
+impl Runtime {
+    pub fn gc_stats(&self) -> GcStats {
+        self.gc_stats
+    }
+
+    pub(super) fn maybe_collect_garbage(&mut self) {
+        if self.all_objects.len() >= self.gc_threshold {
+            self.collect_garbage();
+        }
+    }
+
+    /// Frees objects that are only kept alive by reference cycles among themselves.
+    ///
+    /// This is trial deletion: any object with more strong references than the heap accounts
+    /// for is held from Rust (stack frames, builtins, values in flight) and is treated as a
+    /// root. Everything not reachable from a root is garbage, and clearing its references
+    /// lets `Rc` free it.
+    pub fn collect_garbage(&mut self) -> GcStats {
+        let objects: Vec<ObjectRef> = self
+            .all_objects
+            .iter()
+            .filter_map(|weak| weak.upgrade())
+            .collect();
+
+        let mut internal_references: HashMap<ObjectId, usize> = HashMap::new();
+        for object in &objects {
+            // an object that is borrowed right now is left out, so its references count as
+            // external; that can only keep more objects alive, never fewer
+            if let Ok(object_ref) = object.try_borrow() {
+                object_ref.for_each_reference(|child| {
+                    *internal_references.entry(object_id(child)).or_default() += 1;
+                });
+            }
+        }
+
+        let mut reachable: HashSet<ObjectId> = HashSet::new();
+        let mut pending: Vec<ObjectRef> = objects
+            .iter()
+            .filter(|object| {
+                let internal = internal_references.get(&object_id(object)).copied();
+                // one of the strong references is our own, from `objects`
+                Rc::strong_count(object) - 1 > internal.unwrap_or_default()
+            })
+            .cloned()
+            .collect();
+        while let Some(object) = pending.pop() {
+            if !reachable.insert(object_id(&object)) {
+                continue;
+            }
+            if let Ok(object_ref) = object.try_borrow() {
+                object_ref.for_each_reference(|child| {
+                    if !reachable.contains(&object_id(child)) {
+                        pending.push(child.clone());
+                    }
+                });
+            }
+        }
+
+        let mut collected = 0;
+        for object in &objects {
+            if reachable.contains(&object_id(object)) {
+                continue;
+            }
+            if let Ok(mut object_mut) = object.try_borrow_mut() {
+                object_mut.clear_references();
+                collected += 1;
+            }
+        }
+        drop(objects);
+
+        self.all_objects.retain(|weak| weak.strong_count() > 0);
+        self.cleanup_strings();
+        self.gc_threshold = self.all_objects.len() + GC_ALLOCATION_THRESHOLD;
+        self.gc_stats.collections += 1;
+        self.gc_stats.last_collected = collected;
+        self.gc_stats.total_collected += collected;
+        self.gc_stats.live_objects = self.all_objects.len();
+        self.gc_stats
+    }
+}
@@ -1,21 +1,26 @@
+use std::collections::{HashMap, HashSet};
 use std::ops::ControlFlow;
 
 use crate::parse;
 use crate::runtime::builtin;
+use crate::runtime::number::Number;
 use crate::runtime::object::{
-    MethodBody, MethodReceiver, MethodRef, ObjectRef, Param, DEFAULT_NAME,
+    MethodBody, MethodReceiver, MethodRef, ObjectRef, Param, Primitive, DEFAULT_NAME,
 };
+use crate::runtime::option::Outcome;
+use crate::runtime::trace::MAIN_FRAME;
 use crate::runtime::Error::{
-    ArityMismatch, AssignmentRhsMustBeTuple, BadIterator, BadPath, IllegalAssignmentOperator,
-    IllegalAssignmentTarget, IndexOutOfBounds, InvalidMember, NoSuchMethod, NoSuchProperty,
-    NoSuchVariable, NotCallable, ObjectNotCallable, ReturnFromInitializer, ReturnFromMethod,
-    TypeMismatch, UndefinedProperty,
+    AssignmentRhsMustBeTuple, BadIterator, BadPath, IllegalAssignmentOperator,
+    IllegalAssignmentTarget, IndexOutOfBounds, InvalidMember, KeywordArgument, NoSuchMethod,
+    NoSuchProperty, NotCallable, ObjectNotCallable, ReturnFromInitializer, ReturnFromMethod,
+    SyntaxError, TypeMismatch, UndefinedProperty,
 };
-use crate::runtime::{Error, Runtime};
+use crate::runtime::{Error, ExecMode, Runtime};
 use crate::runtime::{Result, StackFrame};
 use crate::types::{
-    Access, Assignment, Block, Call, Expression, ForIn, LValue, Literal, MethodDefinition, Node,
-    NodeMeta, Operator, Path, Program, Statement, TopError,
+    Access, Assignment, Block, Call, Closure, Expression, ForIn, Ident, LValue, Literal,
+    MethodDefinition, Node, NodeMeta, Operator, Path, Pattern, Program, Statement, TopError,
+    Variable,
 };
 
 macro handle_loop_control_flow($result:ident) {
@@ -39,46 +44,65 @@ macro handle_loop_control_flow($result:ident) {
 impl Runtime {
     pub fn exec_file(&mut self, path: impl AsRef<std::path::Path>) -> Result<(), TopError> {
         let program = parse::parse_file(path)?;
-        self.exec_program(program).map_err(From::from)
+        self.exec_main(program)
+    }
+
+    /// Runs source code that did not come from a file, calling it `name` in diagnostics.
+    pub fn exec_source(&mut self, source: &str, name: &str) -> Result<(), TopError> {
+        let program = parse::parse_source(source, name)?;
+        self.exec_main(program)
+    }
+
+    fn exec_main(&mut self, program: Node<Program>) -> Result<(), TopError> {
+        self.exec_program(program).map_err(|error| {
+            error
+                .traced(MAIN_FRAME, None, MethodReceiver::Instance)
+                .into()
+        })
     }
 
     pub fn exec_program(&mut self, program: Node<Program>) -> Result<()> {
+        self.report_warnings(&program);
+        if self.exec_mode == ExecMode::Bytecode {
+            return self.run_program(&program).map(|_| ());
+        }
         for statement in program.v.body.v.statements {
             self.exec(statement)?;
         }
         Ok(())
     }
 
+    pub fn eval_program(&mut self, program: Node<Program>) -> Result<ObjectRef> {
+        self.report_warnings(&program);
+        match self.exec_mode {
+            ExecMode::Bytecode => self.run_program(&program),
+            ExecMode::TreeWalk => self.eval_block(program.v.body),
+        }
+    }
+
+    fn report_warnings(&mut self, program: &Node<Program>) {
+        if let Some(handler) = &mut self.warning_handler {
+            program.v.warnings.iter().for_each(handler);
+        }
+    }
+
     pub fn exec(&mut self, statement: Node<Statement>) -> Result<()> {
         match statement.v {
             Statement::Expression(expression) => {
                 self.eval(expression)?;
             }
             Statement::MethodDefinition(method_def) => {
-                self.exec_method_def(self.current_class(), method_def)?;
+                self.exec_method_def(self.current_class(), method_def)
+                    .map_err(|error| error.at(&statement.meta))?;
             }
             Statement::Assignment(assignment) => return self.exec_assignment(assignment),
-            Statement::ClassDefinition(class_def) => {
-                let name = class_def.v.name.v.name;
-
-                let class = self
-                    .resolve_variable(&name)
-                    .filter(|object| self.is_class(object))
-                    .unwrap_or_else(|| self.create_simple_class(name));
-                let stack_id = self.push_stack_frame(StackFrame {
-                    class: Some(class),
-                    _context: "class definition",
-                    ..StackFrame::default()
-                });
-                class_def
-                    .v
-                    .body
-                    .v
-                    .statements
-                    .into_iter()
-                    .try_for_each(|statement| self.exec(statement))?;
-                self.pop_stack_frame(stack_id);
-            }
+            Statement::ClassDefinition(class_def) => self
+                .exec_class_def(class_def)
+                .map_err(|error| error.at(&statement.meta))?,
+            Statement::TraitDefinition(trait_def) => self
+                .exec_trait_def(trait_def)
+                .map_err(|error| error.at(&statement.meta))?,
+            Statement::Include(include) => self.exec_include(include)?,
             Statement::ForIn(for_in) => return self.exec_for_in(for_in),
             Statement::WhileLoop(while_loop) => {
                 let stack_id = self.push_stack_frame(StackFrame {
@@ -99,8 +123,15 @@ impl Runtime {
             }
             Statement::Break(_) => return Err(Error::ControlFlow(ControlFlow::Break(()))),
             Statement::Continue(_) => return Err(Error::ControlFlow(ControlFlow::Continue(()))),
+            Statement::Import(import) => self.exec_import(import)?,
+            Statement::Raise(raise) => {
+                let value = self.eval(raise.v.value)?;
+                return Err(self.raise(value, &raise.meta));
+            }
+            Statement::Try(try_stmt) => return self.exec_try(try_stmt),
             Statement::Use(use_stmt) => {
-                let class = self.resolve_class_from_path(use_stmt.v.path)?;
+                let path = use_stmt.v.path;
+                let class = self.resolve_class_from_path(&path.v.components, &path.meta)?;
                 self.stack.last_mut().unwrap().open_classes.push(class);
             }
             Statement::Return(return_stmt) => {
@@ -128,17 +159,61 @@ impl Runtime {
             .collect();
         let iterator_node = for_in.v.iterable.meta.clone();
         let iterable = self.eval(for_in.v.iterable)?;
+        let (iterator, next_method) = self.resolve_iterator(iterable, &iterator_node)?;
+
+        let stack_id = self.push_stack_frame(StackFrame {
+            _context: "for-in loop",
+            ..StackFrame::default()
+        });
+        let mut result = Ok(());
+        loop {
+            let item = match self.next_item(&iterator, &next_method, &node_meta) {
+                Ok(Some(item)) => item,
+                Ok(None) => break,
+                Err(error) => {
+                    result = Err(error);
+                    break;
+                }
+            };
+            if binding_names.len() == 1 {
+                self.bind_variable(binding_names[0].clone(), item);
+            } else {
+                let values = match self.unpack_item(&item, binding_names.len(), &node_meta) {
+                    Ok(values) => values,
+                    Err(error) => {
+                        result = Err(error);
+                        break;
+                    }
+                };
+                for (binding_name, value) in binding_names.iter().cloned().zip(values) {
+                    self.bind_variable(binding_name, value)
+                }
+            }
+            result = self.eval_block(for_in.v.body.clone()).map(|_| ());
+            handle_loop_control_flow!(result);
+        }
+        self.pop_stack_frame(stack_id);
+        result
+    }
+
+    pub(super) fn resolve_iterator(
+        &mut self,
+        iterable: ObjectRef,
+        node: &NodeMeta,
+    ) -> Result<(ObjectRef, MethodRef)> {
         let iterable_class = iterable.borrow().__class__();
         let Some(iter_method) = iterable_class
             .borrow()
             .resolve_own_method(builtin::method::iter)
         else {
             return Err(BadIterator {
-                node: iterator_node,
+                node: node.clone(),
                 reason: "iterable has no .iter() method",
             });
         };
-        let iterator = self.call_method(iterable, iter_method, None)?;
+        let iterator = self
+            .call_method(iterable, iter_method, None)
+            .map_err(|error| error.at(node))?;
         let Some(next_method) = iterator
             .borrow()
             .__class__()
@@ -146,71 +221,70 @@ impl Runtime {
             .resolve_own_method(builtin::method::next)
         else {
             return Err(BadIterator {
-                node: iterator_node,
+                node: node.clone(),
                 reason: "iterator has no .next() method",
             });
         };
+        Ok((iterator, next_method))
+    }
 
-        let stack_id = self.push_stack_frame(StackFrame {
-            _context: "for-in loop",
-            ..StackFrame::default()
-        });
-        let mut result = Ok(());
-        loop {
-            let next = match self.call_method(iterator.clone(), next_method.clone(), None) {
-                Ok(item) => item,
-                Err(error) => {
-                    result = Err(error);
-                    break;
-                }
-            };
-            let next_ref = next.borrow();
-            if next_ref.__class__() != self.builtins.Tuple {
-                result = Err(BadIterator {
-                    reason: "iterator item must be a tuple (ok, item)",
-                    node: node_meta.clone(),
-                });
-                break;
-            }
-            let [ok, item] = next_ref.array().unwrap().as_slice() else {
-                result = Err(BadIterator {
-                    reason: "iterator item must be a tuple (ok, item)",
-                    node: node_meta.clone(),
-                });
-                break;
-            };
-            if self.is_falsy(ok) {
-                break;
-            }
-            if binding_names.len() == 1 {
-                self.define_variable(binding_names[0].clone(), item.clone());
-            } else if item.borrow().__class__() == self.builtins.Tuple {
-                let item_ref = item.borrow();
-                let items = item_ref.array().expect("tuple without array");
-                if items.len() != binding_names.len() {
-                    result = Err(BadIterator {
-                        reason: "iterator binding arity mismatch",
-                        node: node_meta.clone(),
-                    });
-                    break;
-                }
-                for (binding_name, value) in
-                    binding_names.iter().cloned().zip(items.iter().cloned())
-                {
-                    self.define_variable(binding_name, value)
-                }
-            } else {
-                result = Err(BadIterator {
-                    reason: "iterator returned unbindable item",
-                    node: node_meta.clone(),
-                });
-                break;
+    /// Advances `iterator`, returning `None` once it reports that it is exhausted. `next()`
+    /// returns an `Option`, or an `(ok, item)` tuple as iterators did before there was one, and
+    /// an `Err` it returns is raised.
+    pub(super) fn next_item(
+        &mut self,
+        iterator: &ObjectRef,
+        next_method: &MethodRef,
+        node: &NodeMeta,
+    ) -> Result<Option<ObjectRef>> {
+        let next = self
+            .call_method(iterator.clone(), next_method.clone(), None)
+            .map_err(|error| error.at(node))?;
+        match self.outcome(&next) {
+            Some(Outcome::Value(item)) => return Ok(Some(item)),
+            Some(Outcome::Nothing) => return Ok(None),
+            Some(Outcome::Error(error)) => return Err(self.raise(error, node)),
+            None => {}
+        }
+        let next_ref = next.borrow();
+        let legacy_item = match next_ref.array().map(Vec::as_slice) {
+            Some([ok, item]) if next_ref.__class__() == self.builtins.Tuple => Some((ok, item)),
+            _ => None,
+        };
+        let Some((ok, item)) = legacy_item else {
+            return Err(TypeMismatch {
+                expected: builtin::class::Option.into(),
+                class: next_ref.__class__().borrow().__name__().unwrap(),
             }
-            result = self.eval_block(for_in.v.body.clone()).map(|_| ());
-            handle_loop_control_flow!(result);
+            .at(node));
+        };
+        if self.is_falsy(ok) {
+            return Ok(None);
         }
-        self.pop_stack_frame(stack_id);
-        result
+        Ok(Some(item.clone()))
+    }
+
+    pub(super) fn unpack_item(
+        &self,
+        item: &ObjectRef,
+        count: usize,
+        node: &NodeMeta,
+    ) -> Result<Vec<ObjectRef>> {
+        let item_ref = item.borrow();
+        if item_ref.__class__() != self.builtins.Tuple {
+            return Err(BadIterator {
+                reason: "iterator returned unbindable item",
+                node: node.clone(),
+            });
+        }
+        let items = item_ref.array().expect("tuple without array");
+        if items.len() != count {
+            return Err(BadIterator {
+                reason: "iterator binding arity mismatch",
+                node: node.clone(),
+            });
+        }
+        Ok(items.clone())
     }
 
     fn exec_assignment(&mut self, assignment: Node<Assignment>) -> Result<()> {
@@ -226,10 +300,9 @@ impl Runtime {
                     }
                     let var = binding.v.variables.first().unwrap();
                     let name = var.v.ident.v.name.clone();
-                    let lhs = self.resolve_variable(&name).ok_or(NoSuchVariable {
-                        name: name.clone(),
-                        node: var.meta.clone(),
-                    })?;
+                    let lhs = self
+                        .resolve_variable(&name)
+                        .ok_or_else(|| self.no_such_variable(name.clone(), &var.meta))?;
                     value = self.call_instance_method(
                         lhs,
                         method_name,
@@ -239,15 +312,9 @@ impl Runtime {
                 }
                 let value = value?;
                 if binding.v.variables.len() > 1 {
-                    let value_ref = value.borrow();
-                    if value_ref.__class__() != self.builtins.Tuple {
-                        return Err(AssignmentRhsMustBeTuple {
-                            node: assignment.meta,
-                        });
-                    }
-                    let values = value_ref.array().unwrap();
-                    for (var, value) in binding.v.variables.into_iter().zip(values.iter()) {
-                        self.assign_variable(var.v.ident.v.name, value.clone());
+                    let values = self.destructure_tuple(&value, &assignment.meta)?;
+                    for (var, value) in binding.v.variables.into_iter().zip(values) {
+                        self.assign_variable(var.v.ident.v.name, value);
                     }
                 } else {
                     let var = binding.v.variables.pop().unwrap();
@@ -306,7 +373,19 @@ impl Runtime {
         Ok(())
     }
 
-    fn push_stack_frame(&mut self, mut stack_frame: StackFrame) -> usize {
+    pub(super) fn destructure_tuple(
+        &self,
+        value: &ObjectRef,
+        node: &NodeMeta,
+    ) -> Result<Vec<ObjectRef>> {
+        let value_ref = value.borrow();
+        if value_ref.__class__() != self.builtins.Tuple {
+            return Err(AssignmentRhsMustBeTuple { node: node.clone() });
+        }
+        Ok(value_ref.array().unwrap().clone())
+    }
+
+    pub(super) fn push_stack_frame(&mut self, mut stack_frame: StackFrame) -> usize {
         let stack_id = self.stack_id;
         self.stack_id += 1;
         stack_frame.id = stack_id;
@@ -318,7 +397,7 @@ impl Runtime {
         stack_id
     }
 
-    fn pop_stack_frame(&mut self, stack_id: usize) {
+    pub(super) fn pop_stack_frame(&mut self, stack_id: usize) {
         let stack_frame = self.stack.pop().unwrap();
         // println!("-- {stack_frame}");
         // for (i, frame) in self.stack.iter().enumerate() {
@@ -329,21 +408,17 @@ impl Runtime {
         }
     }
 
-    fn exec_method_def(
+    pub(super) fn exec_method_def(
         &mut self,
         class: ObjectRef,
         method_def: Node<MethodDefinition>,
     ) -> Result<()> {
         let method_name = method_def.v.name.v.name.clone();
-        let params = method_def
-            .v
-            .parameters
-            .iter()
-            .map(|param| Param::Positional(param.v.name.v.name.clone()))
-            .collect();
+        let params = method_def.v.parameters.iter().map(Param::from).collect();
         let body = MethodBody::User(method_def.v.body);
-        let receiver = if method_def.v.is_class_method || self.current_class() == self.builtins.Main
-        {
+        let class_receiver =
+            method_def.v.is_class_method || class == self.builtins.Main || self.is_module(&class);
+        let receiver = if class_receiver {
             MethodReceiver::Class
         } else {
             MethodReceiver::Instance
@@ -359,6 +434,7 @@ impl Runtime {
             Expression::Call(call) => self.eval_call_expr(call),
             Expression::Literal(literal) => self.eval_literal(literal),
             Expression::Access(access) => self.eval_access(access),
+            Expression::Match(match_expr) => self.eval_match(match_expr),
             Expression::IfElse(if_else) => {
                 let condition = self.eval(*if_else.v.condition)?;
                 if self.is_falsy(&condition) {
@@ -409,136 +485,292 @@ impl Runtime {
                 let method_name = builtin::op::method_for_unary_op(&unary.v.op.v).unwrap();
                 self.call_instance_method(rhs, method_name, None, Some(unary.meta))
             }
-            Expression::Path(mut path) => {
-                let final_component = path.v.components.pop().unwrap();
-                let name = final_component.v.ident.v.name;
-                let node = path.meta.clone();
-                let class = self.resolve_class_from_path(path)?;
-                if let Some(object) = class.borrow().get_property(&name) {
-                    return Ok(object);
-                }
-                if let Some(method) = class.borrow().resolve_own_method(&name) {
-                    return Ok(self.create_method_object(method));
-                }
-                Err(NoSuchProperty { name, node })
-            }
-            Expression::Closure(closure) => {
-                let object = self.create_object(self.builtins.Closure.clone());
-                let binding_variables = closure
-                    .v
-                    .binding
-                    .iter()
-                    .cloned()
-                    .map(|var| self.create_string(var.v.ident.v.name))
-                    .collect();
-                let params = closure
-                    .v
-                    .binding
-                    .into_iter()
-                    .map(|var| Param::Positional(var.v.ident.v.name))
-                    .collect();
-                let binding = self.create_tuple(binding_variables);
-                let closed_vars = Self::find_closed_vars_in_block(&closure.v.body.v);
-                for closed_var in closed_vars {
-                    let Some(var) = self.resolve_variable(&closed_var) else {
-                        continue;
-                    };
-                    object.borrow_mut().set_property(closed_var, var);
-                }
-                object
-                    .borrow_mut()
-                    .set_property(builtin::property::__binding__, binding);
-                object.borrow_mut().define_method(
-                    MethodReceiver::Instance,
-                    builtin::op::__call__.into(),
-                    params,
-                    MethodBody::User(closure.v.body),
-                )?;
-                Ok(object)
+            Expression::Propagate(propagate) => {
+                let value = self.eval(*propagate.v.value)?;
+                self.propagate(value, &propagate.meta)
             }
+            Expression::Path(path) => self.eval_path(&path),
+            Expression::Closure(closure) => self.create_closure(&closure),
             Expression::Variable(var) => {
                 let name = &var.v.ident.v.name;
-                self.resolve_variable(name).ok_or_else(|| NoSuchVariable {
-                    name: name.clone(),
-                    node: var.meta,
-                })
+                self.resolve_variable(name)
+                    .ok_or_else(|| self.no_such_variable(name.clone(), &var.meta))
             }
+            Expression::Super(super_ref) => Err(SyntaxError {
+                reason: "super can only be called",
+                node: super_ref.meta.into(),
+            }),
         }
     }
 
-    fn find_closed_vars_in_block(block: &Block) -> Vec<String> {
-        block
-            .statements
+    pub(super) fn eval_path(&mut self, path: &Node<Path>) -> Result<ObjectRef> {
+        let (final_component, class_path) = path.v.components.split_last().unwrap();
+        let name = &final_component.v.ident.v.name;
+        let class = self.resolve_class_from_path(class_path, &path.meta)?;
+        if let Some(object) = class.borrow().get_property(name) {
+            return Ok(object);
+        }
+        if let Some(method) = class.borrow().resolve_own_method(name) {
+            return Ok(self.create_method_object(method));
+        }
+        Err(NoSuchProperty {
+            name: name.clone(),
+            node: path.meta.clone(),
+        })
+    }
+
+    pub(super) fn create_closure(&mut self, closure: &Node<Closure>) -> Result<ObjectRef> {
+        let object = self.create_object(self.builtins.Closure.clone());
+        let binding_variables = closure
+            .v
+            .binding
+            .iter()
+            .map(|var| self.create_string(var.v.ident.v.name.clone()))
+            .collect();
+        let params = closure
+            .v
+            .binding
             .iter()
-            .flat_map(|stmt| Self::find_closed_vars_in_stmt(&stmt.v))
-            .collect()
+            .map(|var| Param::Positional(var.v.ident.v.name.clone()))
+            .collect();
+        let binding = self.create_tuple(binding_variables);
+        let mut captures = HashMap::new();
+        for name in Self::find_free_variables(&closure.v) {
+            let cell = match self.capture_variable(&name) {
+                Some(cell) => cell,
+                None => {
+                    // not a variable (yet), but it may name a property or method of `self`
+                    if let Some(value) = self.resolve_variable(&name) {
+                        object.borrow_mut().set_property(name.clone(), value);
+                    }
+                    self.capture_pending_variable(&name)
+                }
+            };
+            captures.insert(name, cell);
+        }
+        let mut object_mut = object.borrow_mut();
+        object_mut.set_primitive(Primitive::Closure(captures));
+        object_mut.set_property(builtin::property::__binding__, binding);
+        if let Some(module) = self.current_module() {
+            object_mut.set_property(builtin::property::__module__, module);
+        }
+        object_mut.define_method(
+            MethodReceiver::Instance,
+            builtin::op::__call__.into(),
+            params,
+            MethodBody::User(closure.v.body.clone()),
+        )?;
+        drop(object_mut);
+        Ok(object)
+    }
+
+    /// Finds the names a closure body refers to that are not its own parameters.
+    fn find_free_variables(closure: &Closure) -> HashSet<String> {
+        let mut names = HashSet::new();
+        Self::find_names_in_block(&closure.body.v, &mut names);
+        for var in &closure.binding {
+            names.remove(&var.v.ident.v.name);
+        }
+        names.remove(builtin::SELF);
+        names
     }
 
-    fn find_closed_vars_in_stmt(stmt: &Statement) -> Vec<String> {
+    fn find_names_in_block(block: &Block, names: &mut HashSet<String>) {
+        for stmt in &block.statements {
+            Self::find_names_in_stmt(&stmt.v, names);
+        }
+    }
+
+    fn find_names_in_stmt(stmt: &Statement, names: &mut HashSet<String>) {
         match stmt {
-            Statement::ForIn(for_in) => Self::find_closed_vars_in_expr(&for_in.v.iterable.v)
-                .into_iter()
-                .chain(Self::find_closed_vars_in_block(&for_in.v.body.v))
-                .collect(),
+            Statement::ForIn(for_in) => {
+                Self::find_names_in_expr(&for_in.v.iterable.v, names);
+                Self::find_names_in_block(&for_in.v.body.v, names);
+            }
             Statement::WhileLoop(while_loop) => {
-                Self::find_closed_vars_in_expr(&while_loop.v.condition.v)
-                    .into_iter()
-                    .chain(Self::find_closed_vars_in_block(&while_loop.v.body.v))
-                    .collect()
-            }
-            Statement::Expression(expression) => Self::find_closed_vars_in_expr(&expression.v),
-            Statement::Return(return_stmt) => return_stmt
-                .v
-                .retval
-                .as_ref()
-                .map(|expr| Self::find_closed_vars_in_expr(&expr.v))
-                .unwrap_or(Vec::new()),
+                Self::find_names_in_expr(&while_loop.v.condition.v, names);
+                Self::find_names_in_block(&while_loop.v.body.v, names);
+            }
+            Statement::Expression(expression) => Self::find_names_in_expr(&expression.v, names),
+            Statement::Return(return_stmt) => {
+                if let Some(retval) = &return_stmt.v.retval {
+                    Self::find_names_in_expr(&retval.v, names);
+                }
+            }
+            Statement::Raise(raise) => Self::find_names_in_expr(&raise.v.value.v, names),
+            Statement::Try(try_stmt) => {
+                Self::find_names_in_block(&try_stmt.v.body.v, names);
+                for rescue in &try_stmt.v.rescues {
+                    if let Some(binding) = &rescue.v.binding {
+                        names.insert(binding.v.ident.v.name.clone());
+                    }
+                    if let Some(class) = &rescue.v.class {
+                        Self::find_names_in_expr(&class.v, names);
+                    }
+                    Self::find_names_in_block(&rescue.v.body.v, names);
+                }
+                if let Some(ensure) = &try_stmt.v.ensure {
+                    Self::find_names_in_block(&ensure.v, names);
+                }
+            }
             Statement::Assignment(assignment) => {
-                Self::find_closed_vars_in_expr(&assignment.v.value.v)
+                match &assignment.v.target.v {
+                    LValue::Access(access) => Self::find_names_in_expr(&access.v.target.v, names),
+                    LValue::Index(index) => {
+                        Self::find_names_in_expr(&index.v.target.v, names);
+                        Self::find_names_in_expr(&index.v.index.v, names);
+                    }
+                    LValue::Binding(binding) => names.extend(
+                        binding
+                            .v
+                            .variables
+                            .iter()
+                            .map(|var| var.v.ident.v.name.clone()),
+                    ),
+                }
+                Self::find_names_in_expr(&assignment.v.value.v, names);
             }
             Statement::MethodDefinition(_)
             | Statement::ClassDefinition(_)
+            | Statement::TraitDefinition(_)
+            | Statement::Include(_)
             | Statement::Use(_)
+            | Statement::Import(_)
             | Statement::Break(_)
-            | Statement::Continue(_) => Vec::new(),
+            | Statement::Continue(_) => {}
         }
     }
 
-    fn find_closed_vars_in_expr(expr: &Expression) -> Vec<String> {
+    fn find_names_in_expr(expr: &Expression, names: &mut HashSet<String>) {
         match expr {
-            Expression::Index(index) => [&index.v.target, &index.v.index]
-                .iter()
-                .flat_map(|expr| Self::find_closed_vars_in_expr(&expr.v))
-                .collect(),
-            Expression::Access(access) => Self::find_closed_vars_in_expr(&access.v.target.v),
-            Expression::Call(call) => Self::find_closed_vars_in_expr(&call.v.target.v),
+            Expression::Index(index) => {
+                Self::find_names_in_expr(&index.v.target.v, names);
+                Self::find_names_in_expr(&index.v.index.v, names);
+            }
+            Expression::Access(access) => {
+                Self::find_names_in_expr(&access.v.target.v, names);
+                // the member names a property or method, but a call's arguments are code
+                if let Expression::Call(call) = &access.v.member.v {
+                    for argument in call.v.argument_expressions() {
+                        Self::find_names_in_expr(&argument.v, names);
+                    }
+                }
+            }
+            Expression::Call(call) => {
+                Self::find_names_in_expr(&call.v.target.v, names);
+                for argument in call.v.argument_expressions() {
+                    Self::find_names_in_expr(&argument.v, names);
+                }
+            }
             Expression::Variable(variable) => {
-                vec![variable.v.ident.v.name.clone()] // leaf
+                names.insert(variable.v.ident.v.name.clone());
+            }
+            Expression::Match(match_expr) => {
+                Self::find_names_in_expr(&match_expr.v.subject.v, names);
+                for case in &match_expr.v.cases {
+                    Self::find_names_in_pattern(&case.v.pattern.v, names);
+                    if let Some(guard) = &case.v.guard {
+                        Self::find_names_in_expr(&guard.v, names);
+                    }
+                    Self::find_names_in_block(&case.v.body.v, names);
+                }
             }
             Expression::IfElse(if_else) => {
-                let mut vars = Self::find_closed_vars_in_expr(&if_else.v.condition.v);
-                vars.extend(Self::find_closed_vars_in_block(&if_else.v.then_body.v));
+                Self::find_names_in_expr(&if_else.v.condition.v, names);
+                Self::find_names_in_block(&if_else.v.then_body.v, names);
                 if let Some(block) = &if_else.v.else_body {
-                    vars.extend(Self::find_closed_vars_in_block(&block.v));
+                    Self::find_names_in_block(&block.v, names);
+                }
+            }
+            Expression::Binary(binary) => {
+                Self::find_names_in_expr(&binary.v.lhs.v, names);
+                Self::find_names_in_expr(&binary.v.rhs.v, names);
+            }
+            Expression::Unary(unary) => Self::find_names_in_expr(&unary.v.rhs.v, names),
+            Expression::Propagate(propagate) => {
+                Self::find_names_in_expr(&propagate.v.value.v, names)
+            }
+            Expression::Closure(closure) => names.extend(Self::find_free_variables(&closure.v)),
+            Expression::Literal(literal) => match &literal.v {
+                Literal::Array(array) => {
+                    for element in &array.v.elements {
+                        Self::find_names_in_expr(&element.v, names);
+                    }
+                }
+                Literal::Tuple(tuple) => {
+                    for item in &tuple.v.items {
+                        Self::find_names_in_expr(&item.v, names);
+                    }
+                }
+                Literal::InterpolatedString(string) => {
+                    for part in &string.v.parts {
+                        Self::find_names_in_expr(&part.v, names);
+                    }
+                }
+                Literal::Dictionary(dictionary) => {
+                    for (key, value) in &dictionary.v.entries {
+                        Self::find_names_in_expr(&key.v, names);
+                        Self::find_names_in_expr(&value.v, names);
+                    }
+                }
+                Literal::StringLit(_)
+                | Literal::Int(_)
+                | Literal::BigInt(_)
+                | Literal::Rational(_)
+                | Literal::Float(_)
+                | Literal::Boolean(_)
+                | Literal::Nil(_) => {}
+            },
+            Expression::Path(_) | Expression::Super(_) => {}
+        }
+    }
+
+    /// The names a pattern binds, and those of the values and classes it refers to.
+    fn find_names_in_pattern(pattern: &Pattern, names: &mut HashSet<String>) {
+        names.extend(pattern.bound_names().into_iter().map(String::from));
+        match pattern {
+            Pattern::ClassPattern(class) => {
+                Self::find_names_in_expr(&class.v.class.v, names);
+                for field in &class.v.fields {
+                    Self::find_names_in_pattern(&field.v, names);
+                }
+            }
+            Pattern::TuplePattern(tuple) => {
+                for item in &tuple.v.items {
+                    Self::find_names_in_pattern(&item.v, names);
+                }
+            }
+            Pattern::ArrayPattern(array) => {
+                for element in array.v.before.iter().chain(&array.v.after) {
+                    Self::find_names_in_pattern(&element.v, names);
+                }
+            }
+            Pattern::DictionaryPattern(dictionary) => {
+                for (_, value) in &dictionary.v.entries {
+                    Self::find_names_in_pattern(&value.v, names);
+                }
+            }
+            Pattern::AlternativePattern(alternatives) => {
+                for alternative in &alternatives.v.alternatives {
+                    Self::find_names_in_pattern(&alternative.v, names);
                 }
-                vars
             }
-            Expression::Binary(binary) => [&binary.v.lhs.v, &binary.v.rhs.v]
-                .iter()
-                .flat_map(|expr| Self::find_closed_vars_in_expr(expr))
-                .collect(),
-            Expression::Unary(unary) => Self::find_closed_vars_in_expr(&unary.v.rhs.v),
-            Expression::Closure(closure) => Self::find_closed_vars_in_block(&closure.v.body.v),
-            Expression::Literal(_) => Vec::new(),
-            Expression::Path(_) => Vec::new(),
+            Pattern::ValuePattern(value) => {
+                names.insert(value.v.variable.v.ident.v.name.clone());
+            }
+            Pattern::Literal(_) | Pattern::Variable(_) | Pattern::Wildcard(_) => {}
         }
     }
 
+    /// Calls a closure or a method object.
     pub(crate) fn call_callable(
         &mut self,
         callable: ObjectRef,
         arguments: Vec<ObjectRef>,
     ) -> Result<ObjectRef> {
+        if let Some((receiver, method)) = self.method_object_target(&callable) {
+            return self.call_method(receiver, method, arguments);
+        }
         let method;
         {
             let callable_ref = callable.borrow();
@@ -551,105 +783,175 @@ impl Runtime {
 
     fn eval_call_expr(&mut self, call: Node<Call>) -> Result<ObjectRef> {
         let target = call.v.target;
-        let receiver: ObjectRef;
-        let method: MethodRef;
-        match &target.v {
+        let (receiver, method) = match &target.v {
             Expression::Variable(var) => {
-                let method_name = &var.v.ident.v.name;
-                if let Some(variable) = self.resolve_variable(method_name) {
-                    println!(">> {method_name} {:?}", variable.borrow());
-                    if self.is_class(&variable) {
-                        receiver = self.create_object(variable.clone());
-                        method = variable.borrow().get_init_method();
-                    } else {
-                        method = Self::resolve_callable_method(&variable, var.meta.clone())?;
-                        receiver = variable;
-                    }
-                } else if let Some((current_receiver, instance_method)) =
-                    self.current_instance().and_then(|receiver| {
-                        receiver
-                            .borrow()
-                            .__class__()
-                            .borrow()
-                            .resolve_own_method(method_name)
-                            .map(|method| (receiver.clone(), method))
-                    })
-                {
-                    receiver = current_receiver;
-                    method = instance_method;
-                } else {
-                    let mut search_classes = self
-                        .stack
-                        .iter()
-                        .flat_map(|frame| frame.open_classes.iter())
-                        .rev();
-                    let (found_class, found_method) = search_classes
-                        .find_map(|class| {
-                            class
-                                .borrow()
-                                .resolve_own_method(&method_name)
-                                .map(|method| (class, method))
-                        })
-                        .ok_or(NoSuchMethod {
-                            node: call.meta.into(),
-                            search: method_name.clone(),
-                        })?;
-                    receiver = found_class.clone();
-                    method = found_method;
-                }
+                self.resolve_named_callee(&var.v.ident.v.name, &var.meta, &call.meta)?
             }
-            Expression::Path(path) => {
-                let mut path = path.clone();
-                let method_component = path.v.components.pop().unwrap();
-                let method_name = method_component.v.ident.v.name;
-                let class_from_path = self.resolve_class_from_path(path)?;
-                if let Some(class_prop) = class_from_path.borrow().get_property(&method_name)
-                    && self.is_class(&class_prop)
-                {
-                    receiver = self.create_object(class_prop.clone());
-                    method = class_prop.borrow().get_init_method();
-                } else {
-                    receiver = class_from_path.clone();
-                    method =
-                        receiver
-                            .borrow()
-                            .resolve_own_method(&method_name)
-                            .ok_or(NoSuchMethod {
-                                node: call.meta.into(),
-                                search: format!(
-                                    "{}::{method_name}",
-                                    receiver.borrow().__name__().unwrap_or(DEFAULT_NAME.into())
-                                ),
-                            })?;
-                };
+            Expression::Path(path) => self.resolve_path_callee(path, &call.meta)?,
+            Expression::Super(super_ref) => {
+                let method_name = super_ref
+                    .v
+                    .method
+                    .as_ref()
+                    .map(|ident| ident.v.name.as_str());
+                self.resolve_super_callee(method_name, &call.meta)?
             }
             _ => {
                 let callable = self.eval(*target.clone())?;
-                method = Self::resolve_callable_method(&callable, target.meta)?;
-                receiver = callable;
+                self.resolve_callable(callable, &target.meta)?
             }
         };
         let arguments = self.eval_expr_list(call.v.arguments)?;
-        self.call_method(receiver, method, arguments)
+        let keywords = self.eval_keywords(call.v.keywords)?;
+        self.call_method_with_keywords(receiver, method, arguments, keywords)
+            .map_err(|error| error.at(&call.meta))
+    }
+
+    /// Finds what a call like `name(...)` refers to: a variable holding a class or callable,
+    /// a method on the current instance, or a method on one of the open classes.
+    pub(super) fn resolve_named_callee(
+        &mut self,
+        name: &str,
+        var_node: &NodeMeta,
+        call_node: &NodeMeta,
+    ) -> Result<(ObjectRef, MethodRef)> {
+        if let Some(variable) = self.resolve_variable(name) {
+            return self.resolve_value_callee(variable, var_node);
+        }
+        if let Some(current_receiver) = self.current_instance() {
+            let instance_method = current_receiver
+                .borrow()
+                .__class__()
+                .borrow()
+                .resolve_own_method(name);
+            if let Some(instance_method) = instance_method {
+                return Ok((current_receiver, instance_method));
+            }
+            // a class method, such as a module's, can call its siblings without a path
+            if self.is_class(&current_receiver) {
+                let class_method = current_receiver.borrow().resolve_own_method(name);
+                if let Some(class_method) = class_method {
+                    return Ok((current_receiver, class_method));
+                }
+            }
+        }
+        let mut search_classes = self
+            .stack
+            .iter()
+            .flat_map(|frame| frame.open_classes.iter())
+            .rev();
+        let (found_class, found_method) = search_classes
+            .find_map(|class| {
+                class
+                    .borrow()
+                    .resolve_own_method(name)
+                    .map(|method| (class, method))
+            })
+            .ok_or_else(|| NoSuchMethod {
+                node: call_node.clone().into(),
+                search: name.to_string(),
+            })?;
+        Ok((found_class.clone(), found_method))
+    }
+
+    /// Calling a class constructs an instance of it, anything else must have a `__call__` method.
+    pub(super) fn resolve_value_callee(
+        &mut self,
+        value: ObjectRef,
+        node: &NodeMeta,
+    ) -> Result<(ObjectRef, MethodRef)> {
+        if self.is_class(&value) {
+            let receiver = self.create_object(value.clone());
+            let method = value.borrow().get_init_method();
+            return Ok((receiver, method));
+        }
+        self.resolve_callable(value, node)
+    }
+
+    pub(super) fn resolve_path_callee(
+        &mut self,
+        path: &Node<Path>,
+        call_node: &NodeMeta,
+    ) -> Result<(ObjectRef, MethodRef)> {
+        let (method_component, class_path) = path.v.components.split_last().unwrap();
+        let method_name = &method_component.v.ident.v.name;
+        let class_from_path = self.resolve_class_from_path(class_path, &path.meta)?;
+        if let Some(class_prop) = class_from_path.borrow().get_property(method_name)
+            && self.is_class(&class_prop)
+        {
+            let receiver = self.create_object(class_prop.clone());
+            let method = class_prop.borrow().get_init_method();
+            return Ok((receiver, method));
+        }
+        let method = class_from_path
+            .borrow()
+            .resolve_own_method(method_name)
+            .ok_or_else(|| NoSuchMethod {
+                node: call_node.clone().into(),
+                search: format!(
+                    "{}::{method_name}",
+                    class_from_path
+                        .borrow()
+                        .__name__()
+                        .unwrap_or(DEFAULT_NAME.into())
+                ),
+            })?;
+        Ok((class_from_path, method))
     }
 
-    fn resolve_callable_method(object: &ObjectRef, meta: NodeMeta) -> Result<MethodRef, Error> {
+    /// What calling `object` runs: the method a `Method` object stands for, the body of a
+    /// closure, or else the `__call__` method of the object's class. The first two are called
+    /// directly rather than through the system `__call__` that forwards to them, so that keyword
+    /// arguments reach them.
+    pub(super) fn resolve_callable(
+        &self,
+        object: ObjectRef,
+        meta: &NodeMeta,
+    ) -> Result<(ObjectRef, MethodRef)> {
+        if let Some(callee) = self.method_object_target(&object) {
+            return Ok(callee);
+        }
+        let method = {
+            let object_ref = object.borrow();
+            let class = object_ref.__class__();
+            let class_ref = class.borrow();
+            object_ref
+                .resolve_own_method(builtin::op::__call__)
+                .or_else(|| class_ref.resolve_own_method(builtin::op::__call__))
+        };
+        let method = method.ok_or_else(|| ObjectNotCallable {
+            node: meta.clone().into(),
+        })?;
+        Ok((object, method))
+    }
+
+    /// The receiver and method that a `Method` object stands for.
+    pub(super) fn method_object_target(
+        &self,
+        object: &ObjectRef,
+    ) -> Option<(ObjectRef, MethodRef)> {
         let object_ref = object.borrow();
-        let class = object_ref.__class__();
-        let class_ref = class.borrow();
-        println!(">>> {:?} {:?}", object_ref, class_ref);
-        class_ref
-            .resolve_own_method(builtin::op::__call__)
-            .ok_or(ObjectNotCallable { node: meta.into() })
+        if object_ref.__class__() != self.builtins.Method {
+            return None;
+        }
+        let name = object_ref.get_property(builtin::property::__name__)?;
+        let receiver = object_ref.get_property(builtin::property::__receiver__)?;
+        let method = receiver
+            .borrow()
+            .resolve_own_method(name.borrow().string()?)?;
+        Some((receiver, method))
     }
 
-    fn resolve_class_from_path(&mut self, path: Node<Path>) -> Result<ObjectRef> {
-        let (start_class, components) = path.v.components.split_first().unwrap();
+    fn resolve_class_from_path(
+        &mut self,
+        components: &[Node<Variable>],
+        node: &NodeMeta,
+    ) -> Result<ObjectRef> {
+        let (start_class, components) = components.split_first().unwrap();
         let receiver_name = &start_class.v.ident.v.name;
-        let mut receiver = self.resolve_variable(receiver_name).ok_or(NoSuchVariable {
-            name: receiver_name.clone(),
-            node: start_class.meta.clone(),
-        })?;
+        let mut receiver = self
+            .resolve_variable(receiver_name)
+            .ok_or_else(|| self.no_such_variable(receiver_name.clone(), &start_class.meta))?;
         for component in components {
             let member = &component.v.ident.v.name;
             let child_receiver =
@@ -659,11 +961,11 @@ impl Runtime {
                     .ok_or(UndefinedProperty {
                         target: receiver.borrow().__debug__(),
                         member: member.clone(),
-                        node: path.meta.clone(),
+                        node: node.clone(),
                     })?;
             if !self.is_class(&child_receiver) {
                 return Err(BadPath {
-                    path: path.meta,
+                    path: node.clone(),
                     non_class: member.clone(),
                 });
             }
@@ -672,11 +974,11 @@ impl Runtime {
         Ok(receiver)
     }
 
-    fn is_truthy(&self, condition: &ObjectRef) -> bool {
+    pub(super) fn is_truthy(&self, condition: &ObjectRef) -> bool {
         !self.is_falsy(condition)
     }
 
-    fn is_falsy(&self, condition: &ObjectRef) -> bool {
+    pub(super) fn is_falsy(&self, condition: &ObjectRef) -> bool {
         [&self.builtins.bool_false, &self.builtins.nil].contains(&condition)
     }
 
@@ -694,80 +996,113 @@ impl Runtime {
             }
             Expression::Call(call) => {
                 let arguments = self.eval_expr_list(call.v.arguments)?;
+                let keywords = self.eval_keywords(call.v.keywords)?;
                 let Expression::Variable(var) = call.v.target.v else {
                     return Err(NotCallable {
                         node: call.v.target.meta,
                     });
                 };
                 let method_name = &var.v.ident.v.name;
-                self.call_instance_method(target, method_name, arguments, Some(call.meta))
+                let method =
+                    self.resolve_instance_method(&target, method_name, || Some(call.meta.clone()))?;
+                self.call_method_with_keywords(target, method, arguments, keywords)
+                    .map_err(|error| error.at(&call.meta))
             }
             Expression::Literal(literal) => {
-                let Literal::Number(index) = literal.v else {
+                let Literal::Int(index) = literal.v else {
                     return Err(InvalidMember { node: literal.meta });
                 };
-                if target.borrow().__class__() != self.builtins.Tuple {
-                    return Err(TypeMismatch {
-                        class: target.borrow().__class__().borrow().__name__().unwrap(),
-                        expected: builtin::class::Tuple.to_string(),
-                    });
-                }
-                let index = index.v.value as usize;
-                target
-                    .borrow()
-                    .array()
-                    .unwrap()
-                    .get(index)
-                    .ok_or(IndexOutOfBounds {
-                        node: access.meta,
-                        index,
-                    })
-                    .cloned()
+                self.tuple_item(&target, index.v.value as usize, &access.meta)
             }
             _ => unimplemented!(),
         }
     }
 
+    pub(super) fn tuple_item(
+        &self,
+        target: &ObjectRef,
+        index: usize,
+        node: &NodeMeta,
+    ) -> Result<ObjectRef> {
+        if target.borrow().__class__() != self.builtins.Tuple {
+            return Err(TypeMismatch {
+                class: target.borrow().__class__().borrow().__name__().unwrap(),
+                expected: builtin::class::Tuple.to_string(),
+            });
+        }
+        target
+            .borrow()
+            .array()
+            .unwrap()
+            .get(index)
+            .ok_or_else(|| IndexOutOfBounds {
+                node: node.clone(),
+                index,
+            })
+            .cloned()
+    }
+
     pub fn call_method(
         &mut self,
         receiver: ObjectRef,
         method: MethodRef,
         arguments: impl IntoIterator<Item = ObjectRef>,
     ) -> Result<ObjectRef> {
-        let class = method.class.upgrade().expect("method's class was dropped");
+        self.call_method_with_keywords(receiver, method, arguments, Vec::new())
+    }
+
+    pub fn call_method_with_keywords(
+        &mut self,
+        receiver: ObjectRef,
+        method: MethodRef,
+        arguments: impl IntoIterator<Item = ObjectRef>,
+        keywords: Vec<(String, ObjectRef)>,
+    ) -> Result<ObjectRef> {
+        let traced_receiver = receiver.clone();
+        self.call_method_untraced(receiver, method.clone(), arguments, keywords)
+            .map_err(|error| self.trace_call(error, &traced_receiver, &method))
+    }
+
+    fn call_method_untraced(
+        &mut self,
+        receiver: ObjectRef,
+        method: MethodRef,
+        arguments: impl IntoIterator<Item = ObjectRef>,
+        keywords: Vec<(String, ObjectRef)>,
+    ) -> Result<ObjectRef> {
         let method_name = method.name.clone();
         let arguments: Vec<ObjectRef> = arguments.into_iter().collect();
         match &method.body {
             MethodBody::User(body) => {
-                if arguments.len() != method.params.len() {
-                    return Err(ArityMismatch {
-                        expected: method.params.len(),
-                        actual: arguments.len(),
-                        class_name: class.borrow().__name__().unwrap(),
-                        method_name,
-                    });
-                }
+                let bound = self.bind_arguments(&method, arguments, keywords)?;
                 let is_init = method_name == builtin::method::init;
-                let variables = method
-                    .params
-                    .iter()
-                    .zip(arguments)
-                    .map(|(param, arg)| {
-                        let Param::Positional(name) = param else {
-                            todo!();
-                        };
-                        (name.clone(), arg)
-                    })
-                    .collect();
-                let stack_id = self.push_stack_frame(StackFrame {
-                    instance: Some(receiver.clone()),
-                    _context: "method call",
-                    _method: Some(method.clone()),
-                    variables,
-                    ..StackFrame::default()
-                });
-                let result = self.eval_block(body.clone());
-                self.pop_stack_frame(stack_id);
+                let result = match self.exec_mode {
+                    ExecMode::Bytecode => self.run_method(receiver.clone(), &method, body, bound),
+                    ExecMode::TreeWalk => {
+                        let variables = method
+                            .params
+                            .iter()
+                            .zip(&bound)
+                            .filter_map(|(param, value)| {
+                                Some((param.name().to_string(), value.clone()?))
+                            })
+                            .collect();
+                        let captures = receiver.borrow().captures().cloned().unwrap_or_default();
+                        let stack_id = self.push_stack_frame(StackFrame {
+                            instance: Some(receiver.clone()),
+                            _context: "method call",
+                            _method: Some(method.clone()),
+                            variables,
+                            captures,
+                            ..StackFrame::default()
+                        });
+                        let result = self
+                            .bind_defaults(&method, &bound)
+                            .and_then(|()| self.eval_block(body.clone()));
+                        self.pop_stack_frame(stack_id);
+                        result
+                    }
+                };
                 if is_init {
                     match result {
                         Err(ReturnFromMethod { retval: None, .. }) | Ok(_) => Ok(receiver),
@@ -783,7 +1118,17 @@ impl Runtime {
                     }
                 }
             }
-            MethodBody::System(function) => function(self, receiver, method_name, arguments),
+            MethodBody::System(function) => {
+                if let Some((keyword, _)) = keywords.into_iter().next() {
+                    return Err(KeywordArgument {
+                        class_name: method.class_name(),
+                        method_name,
+                        keyword,
+                        reason: "unknown",
+                    });
+                }
+                function(self, receiver, method_name, arguments)
+            }
         }
     }
 
@@ -794,29 +1139,43 @@ impl Runtime {
         arguments: impl IntoIterator<Item = ObjectRef>,
         node: Option<NodeMeta>,
     ) -> Result<ObjectRef> {
+        let method = self.resolve_instance_method(&receiver, method_name, || node.clone())?;
+        let result = self.call_method(receiver, method, arguments);
+        match node {
+            Some(node) => result.map_err(|error| error.at(&node)),
+            None => result,
+        }
+    }
+
+    pub(super) fn resolve_instance_method(
+        &self,
+        receiver: &ObjectRef,
+        method_name: &str,
+        node: impl FnOnce() -> Option<NodeMeta>,
+    ) -> Result<MethodRef> {
         let class = receiver.borrow().__class__();
-        let make_no_such_method_error = || NoSuchMethod {
+        let method = class
+            .borrow()
+            .resolve_own_method(method_name)
+            .filter(|method| method.receiver == MethodReceiver::Instance);
+        method.ok_or_else(|| NoSuchMethod {
             search: format!(
                 "{class_name}.{method_name}",
                 class_name = class.borrow().__name__().unwrap()
             ),
-            node: node.clone().into(),
-        };
-        let method = class
-            .borrow()
-            .resolve_own_method(method_name)
-            .ok_or_else(make_no_such_method_error)?;
-        if method.receiver != MethodReceiver::Instance {
-            return Err(make_no_such_method_error());
-        }
-        self.call_method(receiver, method, arguments)
+            node: node().into(),
+        })
     }
 
-    fn is_class(&self, object: &ObjectRef) -> bool {
+    pub(super) fn is_class(&self, object: &ObjectRef) -> bool {
         object.borrow().__class__() == self.builtins.Class
     }
 
-    fn eval_block(&mut self, block: Node<Block>) -> Result<ObjectRef> {
+    pub(super) fn is_trait(&self, object: &ObjectRef) -> bool {
+        object.borrow().__class__() == self.builtins.Trait
+    }
+
+    pub(super) fn eval_block(&mut self, block: Node<Block>) -> Result<ObjectRef> {
         let mut retval = self.nil();
         let statement_count = block.v.statements.len();
         for (i, statement) in block.v.statements.into_iter().enumerate() {
@@ -837,10 +1196,29 @@ impl Runtime {
         exprs.into_iter().map(|node| self.eval(node)).try_collect()
     }
 
-    fn eval_literal(&mut self, literal: Node<Literal>) -> Result<ObjectRef> {
+    fn eval_keywords(
+        &mut self,
+        keywords: Vec<(Node<Ident>, Node<Expression>)>,
+    ) -> Result<Vec<(String, ObjectRef)>> {
+        keywords
+            .into_iter()
+            .map(|(name, value)| Ok((name.v.name, self.eval(value)?)))
+            .try_collect()
+    }
+
+    pub(super) fn eval_literal(&mut self, literal: Node<Literal>) -> Result<ObjectRef> {
         match literal.v {
             Literal::StringLit(string) => Ok(self.create_string(string.v.value)),
-            Literal::Number(number) => Ok(self.create_number(number.v.value)),
+            Literal::InterpolatedString(string) => {
+                let parts = self.eval_expr_list(string.v.parts)?;
+                self.join_strings(parts)
+            }
+            Literal::Int(int) => Ok(self.create_int(int.v.value)),
+            Literal::BigInt(int) => Ok(self.create_number(Number::integer(int.v.value.clone()))),
+            Literal::Rational(rational) => {
+                Ok(self.create_number(Number::Rational(rational.v.value.clone())))
+            }
+            Literal::Float(float) => Ok(self.create_float(float.v.value)),
             Literal::Boolean(boolean) => Ok(self.create_bool(boolean.v.value)),
             Literal::Array(array) => {
                 let elements = self.eval_expr_list(array.v.elements)?;
@@ -856,9 +1234,9 @@ impl Runtime {
                     .v
                     .entries
                     .into_iter()
-                    .map(|(key, value)| Ok((key.v.name, self.eval(value)?)))
+                    .map(|(key, value)| Ok((self.eval(key)?, self.eval(value)?)))
                     .try_collect()?;
-                Ok(self.create_dictionary(entries))
+                self.build_dictionary(entries)
             }
         }
     }
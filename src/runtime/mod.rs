@@ -1,18 +1,48 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
 use std::rc::{Rc, Weak};
 
 use object::Primitive;
 
+use crate::parse;
+pub use crate::runtime::arguments::Arity;
 use crate::runtime::bootstrap::Builtins;
-use crate::runtime::object::{MethodRef, Object, ObjectRef, WeakObjectRef};
-use crate::types::{MaybeNodeMeta, NodeMeta};
+use crate::runtime::gc::{GcStats, GC_ALLOCATION_THRESHOLD};
+use crate::runtime::module::ModuleState;
+pub use crate::runtime::native::{NativeClass, NativeClassBuilder};
+use crate::runtime::number::Number;
+use crate::runtime::object::{MethodRef, Object, ObjectRef, VariableCell, WeakObjectRef};
+pub use crate::runtime::trace::Traceback;
+pub use crate::runtime::value::{FromValue, IntoValue, Value};
+pub use crate::runtime::vm::ExecMode;
+use crate::types::{MaybeNodeMeta, NodeMeta, TopError};
 
+mod arguments;
+mod array;
 mod bootstrap;
 pub mod builtin;
+mod class;
+mod compile;
+mod diagnostic;
+mod dictionary;
+mod embed;
+mod exception;
+mod gc;
 mod interpret;
+mod module;
+mod native;
+mod number;
 mod object;
+mod option;
+mod pattern;
+mod prelude;
+mod string;
+mod trace;
+mod value;
+mod vm;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -28,7 +58,12 @@ pub enum Error {
     #[error("duplicate definition of method '{class}::{name}'")]
     DuplicateMethodDefinition { class: String, name: String },
     #[error("no such variable '{name}': {node}")]
-    NoSuchVariable { name: String, node: NodeMeta },
+    NoSuchVariable {
+        name: String,
+        node: NodeMeta,
+        /// A visible name that the missing one may be a typo of.
+        similar: Option<String>,
+    },
     #[error("no such property '{name}': {node}")]
     NoSuchProperty { name: String, node: NodeMeta },
     #[error("no such method '{search}': {node}")]
@@ -39,9 +74,16 @@ pub enum Error {
     ArityMismatch {
         class_name: String,
         method_name: String,
-        expected: usize,
+        expected: Arity,
         actual: usize,
     },
+    #[error("{reason} keyword argument '{keyword}' for '{class_name}::{method_name}()'")]
+    KeywordArgument {
+        class_name: String,
+        method_name: String,
+        keyword: String,
+        reason: &'static str,
+    },
     #[error("object {target} has no property '{member}': {node}")]
     UndefinedProperty {
         target: String,
@@ -60,12 +102,29 @@ pub enum Error {
     AssignmentRhsMustBeTuple { node: NodeMeta },
     #[error("index error: {error}")]
     Index { error: &'static str },
+    #[error("invalid argument: {reason}")]
+    InvalidArgument { reason: &'static str },
+    #[error("arithmetic error: {reason}")]
+    Arithmetic { reason: &'static str },
     #[error("illegal constructor call: {class}")]
     IllegalConstructorCall { class: String },
     #[error("type error: expected {expected}, got {class}")]
     TypeMismatch { expected: String, class: String },
-    #[error("index out of bounds: {index} in {node}")]
+    #[error("unhashable type: {class}")]
+    Unhashable { class: String },
+    #[error("pattern matches {patterns} fields of class '{class}', which has {fields}: {node}")]
+    PatternFields {
+        class: String,
+        fields: usize,
+        patterns: usize,
+        node: NodeMeta,
+    },
+    #[error("index out of bounds: {index}: {node}")]
     IndexOutOfBounds { index: usize, node: NodeMeta },
+    #[error("superclass mismatch for class '{class}': {node}")]
+    SuperclassMismatch { class: String, node: NodeMeta },
+    #[error("'{name}' would include itself: {node}")]
+    CyclicInclude { name: String, node: NodeMeta },
     #[error("bad path contains non-class '{non_class}': {path}")]
     BadPath { non_class: String, path: NodeMeta },
     #[error("bad iterator, {reason}: {node}")]
@@ -73,14 +132,41 @@ pub enum Error {
         reason: &'static str,
         node: NodeMeta,
     },
+    #[error("no module '{module}' in the search path: {node}")]
+    ModuleNotFound { module: String, node: NodeMeta },
+    #[error("import cycle {cycle}: {node}")]
+    ImportCycle { cycle: String, node: NodeMeta },
+    #[error("could not load module '{module}': {error}")]
+    ModuleLoad {
+        module: String,
+        error: Box<TopError>,
+    },
     #[error("syntax error: {reason}: {node}")]
     SyntaxError {
         reason: &'static str,
         node: MaybeNodeMeta,
     },
+    #[error("{class}: {message}: {node}")]
+    Raised {
+        exception: ObjectRef,
+        class: String,
+        message: String,
+        node: NodeMeta,
+    },
+    #[error("{message}")]
+    Host { message: String },
+    #[error("{error}: {node}")]
+    Located { error: Box<Error>, node: NodeMeta },
+    #[error("{error}")]
+    Traced {
+        error: Box<Error>,
+        traceback: Traceback,
+        /// The call that the error came out of, in the frame it has not yet been traced through.
+        call_site: Option<NodeMeta>,
+    },
 }
 
-type Result<T, E = Error> = std::result::Result<T, E>;
+pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 #[derive(Default, Debug)]
 pub struct StackFrame {
@@ -91,6 +177,114 @@ pub struct StackFrame {
     _context: &'static str,
     open_classes: Vec<ObjectRef>,
     variables: HashMap<String, ObjectRef>,
+    slot_names: Rc<[String]>,
+    slots: Vec<Option<ObjectRef>>,
+    /// Variables of this frame that closures have captured.
+    cells: HashMap<String, VariableCell>,
+    /// Variables captured by the closure this frame is running.
+    captures: HashMap<String, VariableCell>,
+    /// Whether this frame runs the top level of a module, whose globals are its own.
+    is_module: bool,
+}
+
+impl StackFrame {
+    fn get_variable(&self, name: &str) -> Option<ObjectRef> {
+        if let Some(value) = self.variables.get(name) {
+            return Some(value.clone());
+        }
+        let slot_value = self
+            .slot_names
+            .iter()
+            .zip(&self.slots)
+            .find_map(|(slot_name, value)| (slot_name == name).then(|| value.clone()).flatten());
+        if slot_value.is_some() {
+            return slot_value;
+        }
+        self.cells
+            .get(name)
+            .or_else(|| self.captures.get(name))
+            .and_then(|cell| cell.borrow().clone())
+    }
+
+    /// Overwrites `name` if it is defined in this frame, otherwise hands the value back.
+    /// With `fill_pending`, a cell captured before its variable was assigned counts as defined.
+    fn set_existing_variable(
+        &mut self,
+        name: &str,
+        value: ObjectRef,
+        fill_pending: bool,
+    ) -> Result<(), ObjectRef> {
+        if let Some(variable) = self.variables.get_mut(name) {
+            *variable = value;
+            return Ok(());
+        }
+        let slot = self
+            .slot_names
+            .iter()
+            .zip(&mut self.slots)
+            .find_map(|(slot_name, slot)| (slot_name == name && slot.is_some()).then_some(slot));
+        if let Some(slot) = slot {
+            *slot = Some(value);
+            return Ok(());
+        }
+        let own_cell = self
+            .cells
+            .get(name)
+            .filter(|cell| fill_pending || cell.borrow().is_some());
+        let cell = own_cell.or_else(|| {
+            self.captures
+                .get(name)
+                .filter(|cell| cell.borrow().is_some())
+        });
+        match cell {
+            Some(cell) => {
+                *cell.borrow_mut() = Some(value);
+                Ok(())
+            }
+            None => Err(value),
+        }
+    }
+
+    /// Defines `name` in this frame, filling in a cell if a closure captured it beforehand.
+    fn define_variable(&mut self, name: String, value: ObjectRef) {
+        match self.cells.get(name.as_str()) {
+            Some(cell) => *cell.borrow_mut() = Some(value),
+            None => {
+                self.variables.insert(name, value);
+            }
+        }
+    }
+
+    /// Binds `name` to a fresh variable, so closures that captured an earlier binding keep it.
+    fn bind_variable(&mut self, name: String, value: ObjectRef) {
+        self.cells.remove(name.as_str());
+        self.variables.insert(name, value);
+    }
+
+    fn bind_slot(&mut self, slot: usize, value: ObjectRef) {
+        if !self.cells.is_empty() {
+            self.cells.remove(self.slot_names[slot].as_str());
+        }
+        self.slots[slot] = Some(value);
+    }
+
+    /// Moves `name` into a shared cell if it is defined in this frame and returns the cell.
+    fn capture_variable(&mut self, name: &str) -> Option<VariableCell> {
+        if let Some(cell) = self.cells.get(name).or_else(|| self.captures.get(name)) {
+            return Some(cell.clone());
+        }
+        let value = match self.variables.remove(name) {
+            Some(value) => value,
+            None => self
+                .slot_names
+                .iter()
+                .zip(&mut self.slots)
+                .find_map(|(slot_name, slot)| (slot_name == name).then(|| slot.take()).flatten())?,
+        };
+        let cell = Rc::new(RefCell::new(Some(value)));
+        self.cells.insert(name.to_string(), cell.clone());
+        Some(cell)
+    }
 }
 
 impl Display for StackFrame {
@@ -114,6 +308,9 @@ impl Display for StackFrame {
     }
 }
 
+/// What the application embedding the runtime does with the warnings about a program.
+type WarningHandler = Box<dyn FnMut(&parse::Warning)>;
+
 #[derive(Default)]
 pub struct Runtime {
     all_objects: Vec<WeakObjectRef>,
@@ -122,17 +319,119 @@ pub struct Runtime {
     stack_id: usize,
     strings: HashMap<String, WeakObjectRef>,
     string_count_marker: usize,
+    exec_mode: ExecMode,
+    gc_threshold: usize,
+    gc_stats: GcStats,
+    module_paths: Vec<PathBuf>,
+    modules: HashMap<PathBuf, ModuleState>,
+    importing: Vec<String>,
+    /// The globals of the builtins and the prelude, the only ones outside a module it can see.
+    prelude_globals: HashSet<String>,
+    warning_handler: Option<WarningHandler>,
+    /// How many `try` blocks are running, whose rescue clauses may catch an error.
+    handlers: usize,
 }
 
 pub const STRING_ALLOCATION_THRESHOLD: usize = 64;
 
 impl Runtime {
+    /// A runtime with the builtin classes and the standard prelude.
     pub fn new() -> Self {
-        let mut runtime = Self::default();
+        let mut runtime = Self::without_prelude();
+        runtime.run_std_prelude();
+        runtime
+    }
+
+    /// A runtime with only the builtin classes. Another prelude can be run on it like any other
+    /// program.
+    pub fn without_prelude() -> Self {
+        let mut runtime = Self {
+            gc_threshold: GC_ALLOCATION_THRESHOLD,
+            ..Self::default()
+        };
         runtime.bootstrap();
+        runtime.record_prelude_globals();
         runtime
     }
 
+    /// Runs a prelude from a file. Like the builtins, what it defines is visible to modules.
+    pub fn exec_prelude_file(&mut self, path: impl AsRef<Path>) -> Result<(), TopError> {
+        self.exec_file(path)?;
+        self.record_prelude_globals();
+        Ok(())
+    }
+
+    fn record_prelude_globals(&mut self) {
+        let root_frame = &self.stack[0];
+        let names = root_frame.variables.keys().chain(root_frame.cells.keys());
+        self.prelude_globals = names.cloned().collect();
+        self.register_prelude_classes();
+    }
+
+    /// Records the `Range` class the prelude defines, which slices are told apart by identity
+    /// so that a class of the same name defined elsewhere is not mistaken for it.
+    fn register_prelude_classes(&mut self) {
+        let range = self.stack[0]
+            .get_variable(builtin::class::Range)
+            .filter(|value| self.is_class(value));
+        if let Some(range) = range {
+            self.builtins.Range = range;
+        }
+    }
+
+    /// Where the variables visible to the running code begin, and the module it belongs to, if
+    /// that is a module's code. A closure belongs to the module it was created in, so main code
+    /// called back from a module's code only sees the main program's frames.
+    fn module_boundary(&self) -> Option<(usize, Option<ObjectRef>)> {
+        let mut owners = (0..self.stack.len())
+            .rev()
+            .filter_map(|index| Some((index, self.frame_module(index)?)));
+        let (mut boundary, module) = owners.next()?;
+        if module.is_some() {
+            return Some((boundary, module));
+        }
+        for (index, module) in owners {
+            if module.is_some() {
+                return Some((boundary, None));
+            }
+            boundary = index;
+        }
+        None
+    }
+
+    /// Whether a variable of the frame at `index` is visible from the top of the stack. Below
+    /// the running code only the top level of its module, or the main program's globals, and the
+    /// globals of the prelude are, and not the variables of the code that called it.
+    fn is_visible(
+        &self,
+        index: usize,
+        name: &str,
+        boundary: &Option<(usize, Option<ObjectRef>)>,
+    ) -> bool {
+        let Some((boundary, module)) = boundary else {
+            return true;
+        };
+        let frame = &self.stack[index];
+        index >= *boundary
+            || (index == 0 && (module.is_none() || self.prelude_globals.contains(name)))
+            || (frame.is_module && module.is_some() && frame.instance == *module)
+    }
+
+    pub fn set_exec_mode(&mut self, exec_mode: ExecMode) {
+        self.exec_mode = exec_mode;
+    }
+
+    /// Has `handler` report the warnings about each program, including imported modules, before
+    /// it runs. Without a handler they are dropped.
+    pub fn set_warning_handler(&mut self, handler: impl FnMut(&parse::Warning) + 'static) {
+        self.warning_handler = Some(Box::new(handler));
+    }
+
+    /// Drops every stack frame above the global one, e.g. after an error aborted execution.
+    pub fn unwind_to_global(&mut self) {
+        self.stack.truncate(1);
+    }
+
     fn find_closest_in_stack<T>(&self, finder: impl Fn(&StackFrame) -> Option<&T>) -> Option<&T> {
         self.stack.iter().rev().find_map(finder)
     }
@@ -192,14 +491,29 @@ impl Runtime {
         .clone()
     }
 
-    pub fn create_number(&mut self, value: f64) -> ObjectRef {
-        let number_obj = self.create_object(self.builtins.Number.clone());
+    /// Creates a number of the class that `value` is of.
+    pub fn create_number(&mut self, value: Number) -> ObjectRef {
+        let class = match value {
+            Number::Int(_) => &self.builtins.Int,
+            Number::BigInt(_) => &self.builtins.BigInt,
+            Number::Rational(_) => &self.builtins.Rational,
+            Number::Float(_) => &self.builtins.Float,
+        };
+        let number_obj = self.create_object(class.clone());
         number_obj
             .borrow_mut()
             .set_primitive(Primitive::Number(value));
         number_obj
     }
 
+    pub fn create_int(&mut self, value: i64) -> ObjectRef {
+        self.create_number(Number::Int(value))
+    }
+
+    pub fn create_float(&mut self, value: f64) -> ObjectRef {
+        self.create_number(Number::Float(value))
+    }
+
     pub fn create_array(&mut self, elements: Vec<ObjectRef>) -> ObjectRef {
         let array_obj = self.create_object(self.builtins.Array.clone());
         array_obj
@@ -216,15 +530,18 @@ impl Runtime {
         tuple_obj
     }
 
+    /// Creates a dictionary keyed by strings. See [`Runtime::build_dictionary`] for other keys.
     pub fn create_dictionary(&mut self, entries: Vec<(String, ObjectRef)>) -> ObjectRef {
-        let dict_obj = self.create_object(self.builtins.Dictionary.clone());
-        dict_obj
-            .borrow_mut()
-            .set_primitive(Primitive::Dictionary(entries.into_iter().collect()));
-        dict_obj
+        let entries = entries
+            .into_iter()
+            .map(|(key, value)| (self.create_string(key), value))
+            .collect();
+        self.build_dictionary(entries)
+            .expect("strings are hashed and compared without running code")
     }
 
     pub fn create_object(&mut self, class: ObjectRef) -> ObjectRef {
+        self.maybe_collect_garbage();
         let object = Object::new_of_class(class.clone());
         object
             .borrow_mut()
@@ -234,14 +551,29 @@ impl Runtime {
     }
 
     pub fn create_class(&mut self, name: String, superclass: Option<ObjectRef>) -> ObjectRef {
-        let class = self.create_object(self.builtins.Class.clone());
+        let class = self.create_global_definition(self.builtins.Class.clone(), name);
         class.borrow_mut().superclass = superclass;
-        let name_obj = self.create_string(name.clone());
         class
+    }
+
+    pub fn create_trait(&mut self, name: String) -> ObjectRef {
+        self.create_global_definition(self.builtins.Trait.clone(), name)
+    }
+
+    /// Creates a class or trait called `name` and defines it as a global.
+    fn create_global_definition(&mut self, class: ObjectRef, name: String) -> ObjectRef {
+        let definition = self.create_object(class);
+        if let Some(module) = self.current_module() {
+            definition
+                .borrow_mut()
+                .set_property(builtin::property::__module__, module);
+        }
+        let name_obj = self.create_string(name.clone());
+        definition
             .borrow_mut()
             .set_property(builtin::property::__name__, name_obj);
-        self.assign_global(name, class.clone());
-        class
+        self.assign_global(name, definition.clone());
+        definition
     }
 
     pub fn create_simple_class(&mut self, name: impl Into<String>) -> ObjectRef {
@@ -261,8 +593,13 @@ impl Runtime {
         method_obj
     }
 
+    /// Defines a global variable, which inside a module is one of the module's own.
     pub fn assign_global(&mut self, name: String, object: ObjectRef) {
-        self.stack[0].variables.insert(name, object);
+        let frame = match self.stack.iter().rposition(|frame| frame.is_module) {
+            Some(index) => &mut self.stack[index],
+            None => &mut self.stack[0],
+        };
+        frame.define_variable(name, object);
     }
 
     pub fn resolve_variable(&mut self, name: &str) -> Option<ObjectRef> {
@@ -272,9 +609,13 @@ impl Runtime {
         let mut found_instance = false;
         let mut found_class = false;
         let mut found_method = None;
-        for frame in self.stack.iter().rev() {
-            if let Some(value) = frame.variables.get(name) {
-                return Some(value.clone());
+        let boundary = self.module_boundary();
+        for (index, frame) in self.stack.iter().enumerate().rev() {
+            if !self.is_visible(index, name, &boundary) {
+                continue;
+            }
+            if let Some(value) = frame.get_variable(name) {
+                return Some(value);
             }
             if !found_instance && let Some(instance) = &frame.instance {
                 found_instance = true;
@@ -294,28 +635,75 @@ impl Runtime {
         if let Some(method) = found_method {
             return Some(self.create_method_object(method));
         }
-        None
+        self.resolve_module_variable(name)
     }
 
     pub fn assign_variable(&mut self, name: String, object: ObjectRef) {
+        if let Err(object) = self.assign_existing_variable(&name, object) {
+            self.define_variable(name, object);
+        }
+    }
+
+    fn assign_existing_variable(
+        &mut self,
+        name: &str,
+        mut object: ObjectRef,
+    ) -> Result<(), ObjectRef> {
+        // a variable captured before it was assigned is only defined by the method it is in
+        let mut fill_pending = true;
         for frame in self.stack.iter_mut().rev() {
-            if frame.variables.contains_key(&name) {
-                frame.variables.insert(name.clone(), object.clone());
-                return;
+            match frame.set_existing_variable(name, object, fill_pending) {
+                Ok(()) => return Ok(()),
+                Err(unassigned) => object = unassigned,
+            }
+            fill_pending &= frame._method.is_none();
+            if frame.is_module {
+                // a module's top level does not assign to the variables of whoever imported it
+                break;
             }
         }
-        self.define_variable(name, object);
+        Err(object)
     }
 
     fn define_variable(&mut self, name: String, object: ObjectRef) {
         self.stack
             .last_mut()
             .expect("no scope")
-            .variables
-            .insert(name, object);
+            .define_variable(name, object);
+    }
+
+    fn bind_variable(&mut self, name: String, object: ObjectRef) {
+        self.stack
+            .last_mut()
+            .expect("no scope")
+            .bind_variable(name, object);
+    }
+
+    /// Returns the cell holding the variable `name` for a closure to capture, if it exists.
+    fn capture_variable(&mut self, name: &str) -> Option<VariableCell> {
+        let boundary = self.module_boundary();
+        let visible: Vec<_> = (0..self.stack.len())
+            .rev()
+            .filter(|&index| self.is_visible(index, name, &boundary))
+            .collect();
+        visible
+            .into_iter()
+            .find_map(|index| self.stack[index].capture_variable(name))
+    }
+
+    /// Captures a variable that does not exist yet: the cell stays empty until the variable is
+    /// first assigned in the current frame.
+    fn capture_pending_variable(&mut self, name: &str) -> VariableCell {
+        let cell = VariableCell::default();
+        self.stack
+            .last_mut()
+            .expect("no scope")
+            .cells
+            .insert(name.to_string(), cell.clone());
+        cell
     }
 
-    fn nil(&self) -> ObjectRef {
+    pub fn nil(&self) -> ObjectRef {
         self.builtins.nil.clone()
     }
 }
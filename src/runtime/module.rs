@@ -0,0 +1,221 @@
+use std::path::PathBuf;
+
+use crate::parse;
+use crate::runtime::object::{MethodReceiver, ObjectRef};
+use crate::runtime::Error::{ImportCycle, ModuleLoad, ModuleNotFound};
+use crate::runtime::{builtin, Result, Runtime, StackFrame};
+use crate::types::{Import, Node};
+
+pub const MODULE_EXTENSION: &str = "concorde";
+
+#[derive(Debug)]
+pub enum ModuleState {
+    Loading,
+    Loaded(ObjectRef),
+}
+
+impl Runtime {
+    /// Adds a directory that `import` searches for modules, after the ones added before it.
+    pub fn add_module_path(&mut self, path: impl Into<PathBuf>) {
+        self.module_paths.push(path.into());
+    }
+
+    /// Runs `import a::b::c`: loads `a/b/c.concorde` once and binds `a` in the current scope, so
+    /// that the module is reachable as the path `a::b::c`.
+    pub(super) fn exec_import(&mut self, import: Node<Import>) -> Result<()> {
+        let names: Vec<String> = import
+            .v
+            .path
+            .v
+            .components
+            .iter()
+            .map(|component| component.v.ident.v.name.clone())
+            .collect();
+        let module_name = names.join("::");
+        let Some(file) = self.find_module_file(&names) else {
+            return Err(ModuleNotFound {
+                module: module_name,
+                node: import.meta,
+            });
+        };
+        let module = match self.modules.get(&file) {
+            Some(ModuleState::Loaded(module)) => module.clone(),
+            Some(ModuleState::Loading) => {
+                let start = self
+                    .importing
+                    .iter()
+                    .position(|name| *name == module_name)
+                    .unwrap_or_default();
+                let mut cycle = self.importing[start..].to_vec();
+                cycle.push(module_name);
+                return Err(ImportCycle {
+                    cycle: cycle.join(" -> "),
+                    node: import.meta,
+                });
+            }
+            None => self
+                .load_module(module_name, names.last().unwrap(), file)
+                .map_err(|error| error.at(&import.meta))?,
+        };
+        self.bind_module(&names, module);
+        Ok(())
+    }
+
+    fn find_module_file(&self, names: &[String]) -> Option<PathBuf> {
+        let relative_path: PathBuf = names.iter().collect();
+        let relative_path = relative_path.with_extension(MODULE_EXTENSION);
+        self.module_paths
+            .iter()
+            .map(|directory| directory.join(&relative_path))
+            .find(|path| path.is_file())
+            .map(|path| path.canonicalize().unwrap_or(path))
+    }
+
+    fn load_module(&mut self, module_name: String, name: &str, file: PathBuf) -> Result<ObjectRef> {
+        self.modules.insert(file.clone(), ModuleState::Loading);
+        self.importing.push(module_name.clone());
+        let result = self.exec_module(module_name, name, &file);
+        self.importing.pop();
+        match &result {
+            Ok(module) => self
+                .modules
+                .insert(file, ModuleState::Loaded(module.clone())),
+            Err(_) => self.modules.remove(&file),
+        };
+        result
+    }
+
+    /// Runs a module file in a frame of its own. Its variables and classes become properties of
+    /// the module's namespace class, and its methods become methods of that class.
+    fn exec_module(
+        &mut self,
+        module_name: String,
+        name: &str,
+        file: &PathBuf,
+    ) -> Result<ObjectRef> {
+        let program = parse::parse_file(file).map_err(|error| ModuleLoad {
+            module: module_name.clone(),
+            error: Box::new(error),
+        })?;
+        let file_obj = self.create_string(file.display().to_string());
+        let module = self.create_namespace(name, file_obj);
+
+        let depth = self.stack.len();
+        let stack_id = self.push_stack_frame(StackFrame {
+            instance: Some(module.clone()),
+            class: Some(module.clone()),
+            _context: "module",
+            open_classes: vec![module.clone()],
+            is_module: true,
+            ..StackFrame::default()
+        });
+        let result = self.exec_program(program).map_err(|error| {
+            error.traced(
+                format!("<module {module_name}>"),
+                None,
+                MethodReceiver::Instance,
+            )
+        });
+        self.stack.truncate(depth + 1);
+        let frame = &self.stack[depth];
+        let exports: Vec<(String, ObjectRef)> = frame
+            .variables
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .chain(frame.cells.iter().filter_map(|(name, cell)| {
+                let value = cell.borrow().clone()?;
+                Some((name.clone(), value))
+            }))
+            .collect();
+        self.pop_stack_frame(stack_id);
+        result?;
+        for (name, value) in exports {
+            module.borrow_mut().set_property(name, value);
+        }
+        Ok(module)
+    }
+
+    /// Binds the first component of a module path, creating namespace classes for the packages
+    /// between it and the module.
+    fn bind_module(&mut self, names: &[String], module: ObjectRef) {
+        let (module_name, package_names) = names.split_last().unwrap();
+        let Some((root_name, package_names)) = package_names.split_first() else {
+            self.define_variable(module_name.clone(), module);
+            return;
+        };
+        let root = self
+            .resolve_variable(root_name)
+            .filter(|object| self.is_module(object))
+            .unwrap_or_else(|| self.create_namespace(root_name, self.nil()));
+        let mut package = root.clone();
+        for name in package_names {
+            let existing = package
+                .borrow()
+                .get_property(name)
+                .filter(|object| self.is_module(object));
+            let child = existing.unwrap_or_else(|| {
+                let child = self.create_namespace(name, self.nil());
+                package.borrow_mut().set_property(name, child.clone());
+                child
+            });
+            package = child;
+        }
+        package.borrow_mut().set_property(module_name, module);
+        self.define_variable(root_name.clone(), root);
+    }
+
+    /// Creates the class standing for a module, or for a package when `file` is nil.
+    fn create_namespace(&mut self, name: &str, file: ObjectRef) -> ObjectRef {
+        let namespace = self.create_object(self.builtins.Class.clone());
+        namespace.borrow_mut().superclass = Some(self.builtins.Object.clone());
+        let name_obj = self.create_string(name);
+        let mut namespace_mut = namespace.borrow_mut();
+        namespace_mut.set_property(builtin::property::__name__, name_obj);
+        namespace_mut.set_property(builtin::property::__file__, file);
+        drop(namespace_mut);
+        namespace
+    }
+
+    /// The module whose code is running, if any.
+    pub(super) fn current_module(&self) -> Option<ObjectRef> {
+        (0..self.stack.len())
+            .rev()
+            .find_map(|index| self.frame_module(index))?
+    }
+
+    /// The module whose code the frame at `index` runs: its top level, a method of the module or
+    /// of a class it defines, or a closure created by such code. `Some(None)` stands for the code
+    /// of the main program, and `None` for a frame that only scopes the code of the one below it.
+    pub(super) fn frame_module(&self, index: usize) -> Option<Option<ObjectRef>> {
+        let frame = &self.stack[index];
+        if index == 0 {
+            return Some(None);
+        }
+        if frame.is_module {
+            return Some(frame.instance.clone());
+        }
+        let class = frame._method.as_ref()?.class.upgrade()?;
+        if self.is_module(&class) {
+            return Some(Some(class));
+        }
+        let class_ref = class.borrow();
+        Some(class_ref.get_property(builtin::property::__module__))
+    }
+
+    /// Looks `name` up among the globals of the module whose code is running, so that methods
+    /// and closures of a module can see the module's variables after it has loaded.
+    pub(super) fn resolve_module_variable(&self, name: &str) -> Option<ObjectRef> {
+        let module = self.current_module()?;
+        let module_ref = module.borrow();
+        module_ref.get_property(name)
+    }
+
+    /// Modules and the packages containing them are classes with a `__file__` property.
+    pub(super) fn is_module(&self, object: &ObjectRef) -> bool {
+        self.is_class(object)
+            && object
+                .borrow()
+                .get_property(builtin::property::__file__)
+                .is_some()
+    }
+}
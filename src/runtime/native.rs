@@ -0,0 +1,118 @@
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+use crate::runtime::object::{MethodBody, MethodReceiver, NativePayload, Param};
+use crate::runtime::value::{IntoValue, Value};
+use crate::runtime::Error::TypeMismatch;
+use crate::runtime::{builtin, Error, Result, Runtime};
+
+/// A Rust type exposed to concorde as a class, each instance of which carries a value of the
+/// type.
+///
+/// The value is built by [`NativeClass::init`] when concorde code calls `Name(...)`, and the
+/// methods defined in [`NativeClass::define`] are handed it by mutable reference. Objects kept
+/// inside the value are invisible to the garbage collector, which treats them as roots.
+pub trait NativeClass: Sized + 'static {
+    /// The name the class is defined under.
+    const NAME: &'static str;
+
+    /// Builds the payload of a new instance from the arguments of `Name(...)`. Properties can be
+    /// set on the instance, which is `this`.
+    fn init(runtime: &mut Runtime, this: &Value, args: Vec<Value>) -> Result<Self>;
+
+    /// Defines the methods and properties of the class.
+    fn define(class: &mut NativeClassBuilder<Self>) -> Result<()>;
+}
+
+/// Defines the members of a native class, see [`NativeClass::define`].
+pub struct NativeClassBuilder<'a, T> {
+    runtime: &'a mut Runtime,
+    class: Value,
+    payload: PhantomData<T>,
+}
+
+impl<T: NativeClass> NativeClassBuilder<'_, T> {
+    pub fn runtime(&mut self) -> &mut Runtime {
+        self.runtime
+    }
+
+    pub fn class(&self) -> &Value {
+        &self.class
+    }
+
+    /// Defines an instance method that works on the payload of its receiver.
+    pub fn method(
+        &mut self,
+        name: &str,
+        function: impl Fn(&mut Runtime, &mut T, Vec<Value>) -> Result<Value> + 'static,
+    ) -> Result<&mut Self> {
+        self.runtime
+            .define_method(&self.class, name, move |runtime, this, args| {
+                this.with_native(|native| function(runtime, native, args))?
+            })?;
+        Ok(self)
+    }
+
+    pub fn class_method(
+        &mut self,
+        name: &str,
+        function: impl Fn(&mut Runtime, Vec<Value>) -> Result<Value> + 'static,
+    ) -> Result<&mut Self> {
+        self.runtime
+            .define_class_method(&self.class, name, move |runtime, _class, args| {
+                function(runtime, args)
+            })?;
+        Ok(self)
+    }
+
+    /// Sets a property of the class, which concorde code reads as `Name::property`.
+    pub fn property(&mut self, name: &str, value: impl IntoValue) -> &mut Self {
+        let value = value.into_value(self.runtime);
+        self.class.set_property(name, value);
+        self
+    }
+}
+
+impl Runtime {
+    /// Defines the class for `T`, or adds its members to a class of the same name.
+    pub fn define_native_class<T: NativeClass>(&mut self) -> Result<Value> {
+        let class = self.define_class(T::NAME);
+        class.0.borrow_mut().define_method(
+            MethodReceiver::Instance,
+            builtin::method::init.into(),
+            vec![Param::Vararg("args".into())],
+            MethodBody::system(|runtime, this, _method_name, args| {
+                let instance = Value(this);
+                let args = args.into_iter().map(Value).collect();
+                let native = T::init(runtime, &instance, args)?;
+                let payload: NativePayload = Rc::new(RefCell::new(native));
+                instance.0.borrow_mut().set_native(payload);
+                Ok(instance.0)
+            }),
+        )?;
+        T::define(&mut NativeClassBuilder {
+            runtime: self,
+            class: class.clone(),
+            payload: PhantomData,
+        })?;
+        Ok(class)
+    }
+}
+
+impl Value {
+    /// Runs `f` on the payload of an instance of the native class `T`. Fails if the value is not
+    /// one, or if a method of the instance is running.
+    pub fn with_native<T: NativeClass, R>(&self, f: impl FnOnce(&mut T) -> R) -> Result<R> {
+        let not_native = || TypeMismatch {
+            expected: T::NAME.into(),
+            class: self.class_name(),
+        };
+        let payload = self.0.borrow().native().ok_or_else(not_native)?;
+        let mut payload_mut = payload
+            .try_borrow_mut()
+            .map_err(|_| Error::host(format!("{} is already in use by another call", T::NAME)))?;
+        let native = payload_mut.downcast_mut::<T>().ok_or_else(not_native)?;
+        Ok(f(native))
+    }
+}
@@ -0,0 +1,394 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, ToPrimitive, Zero};
+
+use crate::runtime::object::ObjectRef;
+use crate::runtime::Error::{Arithmetic, TypeMismatch};
+use crate::runtime::{builtin, Error, Result};
+
+use Number::{Float, Int, Rational};
+
+/// The value of a number. An `Int` that overflows becomes a `BigInt`, and a `BigInt` that fits
+/// in an `Int` again becomes one, so the two never hold the same value.
+///
+/// When two kinds of number meet in an operator the result is of the later kind in
+/// `Int`, `BigInt`, `Rational`, `Float`: exact until a `Float` is involved.
+#[derive(Debug, Clone)]
+pub enum Number {
+    Int(i64),
+    BigInt(BigInt),
+    Rational(BigRational),
+    Float(f64),
+}
+
+const DIVISION_BY_ZERO: Error = Arithmetic {
+    reason: "division by zero",
+};
+
+const NEGATIVE_SHIFT: Error = Arithmetic {
+    reason: "negative shift count",
+};
+
+const RESULT_TOO_LARGE: Error = Arithmetic {
+    reason: "result too large",
+};
+
+/// The most bits an integer that `<<` or `pow` gives may have, about five million digits, so
+/// that one operator cannot run the interpreter out of memory.
+const MAX_INTEGER_BITS: u64 = 1 << 24;
+
+/// 2^63, the first float past the largest `i64`.
+const INT_LIMIT: f64 = 9_223_372_036_854_775_808.0;
+
+impl Number {
+    /// An `Int`, or a `BigInt` if it does not fit in one.
+    pub fn integer(value: BigInt) -> Number {
+        match i64::try_from(&value) {
+            Ok(value) => Int(value),
+            Err(_) => Number::BigInt(value),
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            Int(value) => *value as f64,
+            Number::BigInt(value) => value.to_f64().unwrap_or(f64::NAN),
+            Rational(value) => value.to_f64().unwrap_or(f64::NAN),
+            Float(value) => *value,
+        }
+    }
+
+    /// The integer this is, if it is an `Int` or a `BigInt`.
+    pub fn to_bigint(&self) -> Option<BigInt> {
+        match self {
+            Int(value) => Some(BigInt::from(*value)),
+            Number::BigInt(value) => Some(value.clone()),
+            Rational(_) | Float(_) => None,
+        }
+    }
+
+    /// The exact fraction this is, which a `Float` that is `NaN` or infinite does not have.
+    pub fn to_rational(&self) -> Option<BigRational> {
+        match self {
+            Rational(value) => Some(value.clone()),
+            Float(value) => BigRational::from_float(*value),
+            integer => integer.to_bigint().map(BigRational::from_integer),
+        }
+    }
+
+    /// Where this comes among `Int`, `BigInt`, `Rational` and `Float`.
+    fn rank(&self) -> u8 {
+        match self {
+            Int(_) => 0,
+            Number::BigInt(_) => 1,
+            Rational(_) => 2,
+            Float(_) => 3,
+        }
+    }
+
+    /// How two numbers order by value, exactly whatever their kinds. `NaN` is unordered.
+    pub fn compare(&self, other: &Number) -> Option<Ordering> {
+        match (self, other) {
+            (Int(a), Int(b)) => Some(a.cmp(b)),
+            (Float(a), Float(b)) => a.partial_cmp(b),
+            (Int(a), Float(b)) => compare_int_float(*a, *b),
+            (Float(a), Int(b)) => compare_int_float(*b, *a).map(Ordering::reverse),
+            (a, b) => match (a.to_rational(), b.to_rational()) {
+                (Some(a), Some(b)) => Some(a.cmp(&b)),
+                // a float that is NaN or infinite
+                _ => a.as_f64().partial_cmp(&b.as_f64()),
+            },
+        }
+    }
+
+    /// Works out an arithmetic operator in the kind of the later of the two numbers. `int_op`
+    /// gives `None` when it overflows, and then the operator is worked out with `big_op`.
+    pub fn arithmetic(
+        &self,
+        other: &Number,
+        int_op: fn(i64, i64) -> Option<i64>,
+        big_op: fn(BigInt, BigInt) -> BigInt,
+        rational_op: fn(BigRational, BigRational) -> BigRational,
+        float_op: fn(f64, f64) -> f64,
+    ) -> Number {
+        if let (Int(a), Int(b)) = (self, other)
+            && let Some(result) = int_op(*a, *b)
+        {
+            return Int(result);
+        }
+        match self.rank().max(other.rank()) {
+            0 | 1 => Number::integer(big_op(
+                self.to_bigint().unwrap(),
+                other.to_bigint().unwrap(),
+            )),
+            2 => Rational(rational_op(
+                self.to_rational().unwrap(),
+                other.to_rational().unwrap(),
+            )),
+            _ => Float(float_op(self.as_f64(), other.as_f64())),
+        }
+    }
+
+    /// `self / divisor`, a `Rational` when either is one and otherwise a `Float`.
+    pub fn div(&self, divisor: &Number) -> Result<Number> {
+        if self.rank().max(divisor.rank()) != 2 {
+            return Ok(Float(self.as_f64() / divisor.as_f64()));
+        }
+        let divisor = divisor.to_rational().unwrap();
+        if divisor.is_zero() {
+            return Err(DIVISION_BY_ZERO);
+        }
+        Ok(Rational(self.to_rational().unwrap() / divisor))
+    }
+
+    /// `self // divisor`, rounded down to a whole number, which is an integer unless either
+    /// is a `Float`.
+    pub fn floor_div(&self, divisor: &Number) -> Result<Number> {
+        self.check_divisor(divisor)?;
+        let quotient = self.arithmetic(
+            divisor,
+            floor_div,
+            |a, b| a.div_floor(&b),
+            |a, b| (a / b).floor(),
+            |a, b| (a / b).floor(),
+        );
+        Ok(match quotient {
+            Rational(quotient) => Number::integer(quotient.to_integer()),
+            quotient => quotient,
+        })
+    }
+
+    /// `self % divisor`, which takes the sign of the divisor so that
+    /// `a == (a // b) * b + a % b`.
+    pub fn floor_mod(&self, divisor: &Number) -> Result<Number> {
+        self.check_divisor(divisor)?;
+        Ok(self.arithmetic(
+            divisor,
+            |a, b| Some(floor_remainder(a.wrapping_rem(b), b)),
+            |a, b| a.mod_floor(&b),
+            |a, b| &a - &b * (&a / &b).floor(),
+            |a, b| a - b * (a / b).floor(),
+        ))
+    }
+
+    /// Dividing an exact number by zero has no answer, where a `Float` gives infinity or `NaN`.
+    fn check_divisor(&self, divisor: &Number) -> Result<()> {
+        let exact = self.rank().max(divisor.rank()) < 3;
+        if exact
+            && divisor
+                .to_rational()
+                .is_some_and(|divisor| divisor.is_zero())
+        {
+            return Err(DIVISION_BY_ZERO);
+        }
+        Ok(())
+    }
+
+    /// `self` raised to `power`. A whole power of an exact number is exact, and anything else
+    /// is worked out as a `Float`.
+    pub fn pow(&self, power: &Number) -> Result<Number> {
+        let Int(power) = *power else {
+            return Ok(Float(self.as_f64().powf(power.as_f64())));
+        };
+        let too_large = |_| Arithmetic {
+            reason: "exponent too large",
+        };
+        match self {
+            Int(base) if power >= 0 => {
+                let power = u32::try_from(power).map_err(too_large)?;
+                if let Some(result) = base.checked_pow(power) {
+                    return Ok(Int(result));
+                }
+                let base = BigInt::from(*base);
+                check_pow_size(&base, power)?;
+                Ok(Number::integer(base.pow(power)))
+            }
+            Number::BigInt(base) if power >= 0 => {
+                let power = u32::try_from(power).map_err(too_large)?;
+                check_pow_size(base, power)?;
+                Ok(Number::integer(base.pow(power)))
+            }
+            Rational(base) => {
+                let power = i32::try_from(power).map_err(too_large)?;
+                if power < 0 && base.is_zero() {
+                    return Err(DIVISION_BY_ZERO);
+                }
+                check_pow_size(base.numer(), power.unsigned_abs())?;
+                check_pow_size(base.denom(), power.unsigned_abs())?;
+                Ok(Rational(base.pow(power)))
+            }
+            base => Ok(Float(base.as_f64().powf(power as f64))),
+        }
+    }
+
+    pub fn neg(&self) -> Number {
+        match self {
+            Int(value) => match value.checked_neg() {
+                Some(result) => Int(result),
+                None => Number::integer(-BigInt::from(*value)),
+            },
+            Number::BigInt(value) => Number::integer(-value),
+            Rational(value) => Rational(-value),
+            Float(value) => Float(-value),
+        }
+    }
+
+    /// The integer that rounding this gives, with `float_round` or `rational_round` by its
+    /// kind. An integer is itself.
+    pub fn to_integer(
+        &self,
+        float_round: fn(f64) -> f64,
+        rational_round: fn(&BigRational) -> BigRational,
+    ) -> Result<Number> {
+        match self {
+            Int(_) | Number::BigInt(_) => Ok(self.clone()),
+            Rational(value) => Ok(Number::integer(rational_round(value).to_integer())),
+            Float(value) => BigInt::from_f64(float_round(*value))
+                .map(Number::integer)
+                .ok_or(Arithmetic {
+                    reason: "NaN or infinity has no integer value",
+                }),
+        }
+    }
+
+    /// Works out a bitwise operator on two integers.
+    pub fn bitwise(
+        &self,
+        other: &Number,
+        int_op: fn(i64, i64) -> i64,
+        big_op: fn(&BigInt, &BigInt) -> BigInt,
+    ) -> Number {
+        match (self, other) {
+            (Int(a), Int(b)) => Int(int_op(*a, *b)),
+            (a, b) => Number::integer(big_op(&a.to_bigint().unwrap(), &b.to_bigint().unwrap())),
+        }
+    }
+
+    /// `self << shift` for an integer, which becomes a `BigInt` rather than drop bits off the
+    /// top.
+    pub fn shift_left(&self, shift: i64) -> Result<Number> {
+        let shift = usize::try_from(shift).map_err(|_| NEGATIVE_SHIFT)?;
+        if let Int(value) = *self
+            && shift < 64
+            && let Ok(shifted) = i64::try_from(i128::from(value) << shift)
+        {
+            return Ok(Int(shifted));
+        }
+        let value = self.to_bigint().unwrap();
+        if !value.is_zero() && value.bits().saturating_add(shift as u64) > MAX_INTEGER_BITS {
+            return Err(RESULT_TOO_LARGE);
+        }
+        Ok(Number::integer(value << shift))
+    }
+
+    /// `self >> shift` for an integer, which keeps the sign.
+    pub fn shift_right(&self, shift: i64) -> Result<Number> {
+        let shift = usize::try_from(shift).map_err(|_| NEGATIVE_SHIFT)?;
+        Ok(match self {
+            Int(value) => Int(value >> shift.min(63)),
+            integer => Number::integer(integer.to_bigint().unwrap() >> shift),
+        })
+    }
+
+    pub fn invert(&self) -> Number {
+        match self {
+            Int(value) => Int(!value),
+            integer => Number::integer(!integer.to_bigint().unwrap()),
+        }
+    }
+}
+
+/// Fails if `base` to the `power` would have more than `MAX_INTEGER_BITS` bits, which it has
+/// about `power` times as many of as `base` does.
+fn check_pow_size(base: &BigInt, power: u32) -> Result<()> {
+    // 0, 1 and -1 stay as small whatever the power
+    let bits = base
+        .bits()
+        .saturating_sub(1)
+        .saturating_mul(u64::from(power));
+    if bits > MAX_INTEGER_BITS {
+        return Err(RESULT_TOO_LARGE);
+    }
+    Ok(())
+}
+
+/// Integers print as their digits, a `Rational` in its lowest terms and a `Float` always with a
+/// point or an exponent, so that they are told apart.
+impl Display for Number {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Int(value) => write!(f, "{value}"),
+            Number::BigInt(value) => write!(f, "{value}"),
+            Rational(value) => write!(f, "{}/{}", value.numer(), value.denom()),
+            Float(value) => write!(f, "{value:?}"),
+        }
+    }
+}
+
+fn compare_int_float(int: i64, float: f64) -> Option<Ordering> {
+    if float.is_nan() {
+        return None;
+    }
+    if float >= INT_LIMIT {
+        return Some(Ordering::Less);
+    }
+    if float < -INT_LIMIT {
+        return Some(Ordering::Greater);
+    }
+    // the whole part is in range now, and the fraction settles a tie
+    let order = int.cmp(&(float.trunc() as i64));
+    Some(order.then_with(|| 0.0.partial_cmp(&float.fract()).unwrap()))
+}
+
+fn floor_div(a: i64, b: i64) -> Option<i64> {
+    let quotient = a.checked_div(b)?;
+    Some(if a % b != 0 && (a < 0) != (b < 0) {
+        quotient - 1
+    } else {
+        quotient
+    })
+}
+
+/// Moves a remainder with the sign of the dividend over to the sign of the divisor.
+fn floor_remainder(remainder: i64, divisor: i64) -> i64 {
+    if remainder != 0 && (remainder < 0) != (divisor < 0) {
+        remainder + divisor
+    } else {
+        remainder
+    }
+}
+
+/// The value of the right-hand side of an arithmetic or comparison operator on a `Number`.
+pub(super) fn number_operand(other: &ObjectRef) -> Result<Number> {
+    let other_ref = other.borrow();
+    other_ref.number().ok_or_else(|| TypeMismatch {
+        class: other_ref.__class__().borrow().__name__().unwrap(),
+        expected: builtin::class::Number.into(),
+    })
+}
+
+/// An argument that must be an `Int`, such as an index or a shift count.
+pub(super) fn int_operand(other: &ObjectRef) -> Result<i64> {
+    let other_ref = other.borrow();
+    other_ref.int().ok_or_else(|| TypeMismatch {
+        class: other_ref.__class__().borrow().__name__().unwrap(),
+        expected: builtin::class::Int.into(),
+    })
+}
+
+/// An argument that must be an integer, an `Int` or a `BigInt`, such as the right-hand side of
+/// a bitwise operator.
+pub(super) fn integer_operand(other: &ObjectRef) -> Result<Number> {
+    let other_ref = other.borrow();
+    let integer = other_ref
+        .number()
+        .filter(|number| number.to_bigint().is_some());
+    integer.ok_or_else(|| TypeMismatch {
+        class: other_ref.__class__().borrow().__name__().unwrap(),
+        expected: builtin::class::Int.into(),
+    })
+}
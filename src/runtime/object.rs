@@ -1,23 +1,36 @@
-use std::cell::RefCell;
+use std::any::Any;
+use std::cell::{OnceCell, Ref, RefCell, RefMut};
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::rc::{Rc, Weak};
 
 use crate::runtime::builtin;
-use crate::runtime::Error::DuplicateMethodDefinition;
+use crate::runtime::compile::Chunk;
+use crate::runtime::dictionary::Dictionary;
+use crate::runtime::number::Number;
+use crate::runtime::Error::{DuplicateMethodDefinition, TypeMismatch};
 use crate::runtime::{Result, Runtime};
-use crate::types::{Block, Node};
+use crate::types::{Block, Expression, Node, Parameter, ParameterKind};
 
 pub type WeakObjectRef = Weak<RefCell<Object>>;
 pub type ObjectRef = Rc<RefCell<Object>>;
 pub type MethodRef = Rc<Method>;
-
-pub type SystemMethod = fn(
-    runtime: &mut Runtime,
-    this: ObjectRef,
-    method_name: String,
-    arguments: Vec<ObjectRef>,
-) -> Result<ObjectRef>;
+/// A variable shared between the scope that defines it and the closures that capture it.
+/// It holds `None` while the variable has been captured but not yet assigned.
+pub type VariableCell = Rc<RefCell<Option<ObjectRef>>>;
+/// The Rust value carried by an instance of a native class. It lives in a cell of its own so
+/// that a method can hold it while the runtime works with the object.
+pub type NativePayload = Rc<RefCell<dyn Any>>;
+
+/// A method implemented in Rust, by the runtime itself or by the application embedding it.
+pub type SystemMethod = Rc<
+    dyn Fn(
+        &mut Runtime,
+        ObjectRef, // this
+        String,    // method name
+        Vec<ObjectRef>,
+    ) -> Result<ObjectRef>,
+>;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum MethodReceiver {
@@ -25,25 +38,79 @@ pub enum MethodReceiver {
     Class,
 }
 
-#[derive(Debug)]
 pub enum MethodBody {
     User(Node<Block>),
     System(SystemMethod),
 }
 
+impl MethodBody {
+    pub fn system(
+        function: impl Fn(&mut Runtime, ObjectRef, String, Vec<ObjectRef>) -> Result<ObjectRef>
+            + 'static,
+    ) -> Self {
+        MethodBody::System(Rc::new(function))
+    }
+}
+
+impl Debug for MethodBody {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MethodBody::User(body) => f.debug_tuple("User").field(body).finish(),
+            MethodBody::System(_) => f.write_str("System(..)"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Param {
     Positional(String),
+    /// A positional parameter whose default is evaluated when its argument is left out.
+    Optional(String, Node<Expression>),
     Vararg(String),
+    /// A parameter passed by name, which must be given unless it has a default.
+    Keyword(String, Option<Node<Expression>>),
+}
+
+impl Param {
+    pub fn name(&self) -> &str {
+        match self {
+            Param::Positional(name)
+            | Param::Optional(name, _)
+            | Param::Vararg(name)
+            | Param::Keyword(name, _) => name,
+        }
+    }
+
+    pub fn default(&self) -> Option<&Node<Expression>> {
+        match self {
+            Param::Optional(_, default) | Param::Keyword(_, Some(default)) => Some(default),
+            _ => None,
+        }
+    }
+}
+
+impl From<&Node<Parameter>> for Param {
+    fn from(param: &Node<Parameter>) -> Self {
+        let name = param.v.name.v.name.clone();
+        match (param.v.kind, param.v.default.clone()) {
+            (ParameterKind::Positional, None) => Param::Positional(name),
+            (ParameterKind::Positional, Some(default)) => Param::Optional(name, default),
+            (ParameterKind::Rest, _) => Param::Vararg(name),
+            (ParameterKind::Keyword, default) => Param::Keyword(name, default),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum Primitive {
     String(String),
-    Number(f64),
+    Number(Number),
     Boolean(bool),
     Array(Vec<ObjectRef>),
-    Dictionary(HashMap<String, ObjectRef>),
+    Dictionary(Dictionary),
+    /// The elements of a set, kept as the keys of entries that map each to itself.
+    Set(Dictionary),
+    Closure(HashMap<String, VariableCell>),
 }
 
 #[derive(Debug)]
@@ -53,16 +120,36 @@ pub struct Method {
     pub params: Vec<Param>,
     pub body: MethodBody,
     pub receiver: MethodReceiver,
+    pub compiled: OnceCell<Rc<Chunk>>,
+}
+
+impl Method {
+    /// The name of the class the method is defined on. A closure's `__call__` is defined on the
+    /// closure itself, which goes by the name of its class.
+    pub fn class_name(&self) -> String {
+        let class = self.class.upgrade().expect("method's class was dropped");
+        let class_ref = class.borrow();
+        class_ref
+            .__name__()
+            .or_else(|| class_ref.__class__().borrow().__name__())
+            .unwrap_or(DEFAULT_NAME.into())
+    }
 }
 
 pub struct Object {
     pub(super) class: Option<ObjectRef>,
     pub(super) superclass: Option<ObjectRef>,
+    /// The traits included into this class or trait, in the order they were included.
+    traits: Vec<ObjectRef>,
+    /// The fields of a class declared as `class Name(fields)`, including those it inherits,
+    /// which its generated initializer takes.
+    pub(super) fields: Vec<Node<Parameter>>,
     pub(super) _name: String,
     weak_self: WeakObjectRef,
     properties: HashMap<String, ObjectRef>,
     methods: HashMap<String, MethodRef>,
     primitive: Option<Primitive>,
+    native: Option<NativePayload>,
 }
 
 impl PartialEq for Object {
@@ -97,6 +184,8 @@ impl Object {
                 // fine to clone by-ref here
                 class: object.class.clone(),
                 superclass: object.superclass.clone(),
+                traits: object.traits.clone(),
+                fields: object.fields.clone(),
                 // new self-ref
                 weak_self: weak_self.clone(),
                 // gotta be careful about this one
@@ -105,6 +194,8 @@ impl Object {
                 methods: object.methods.clone(),
                 // easy primitive clone
                 primitive: object.primitive.clone(),
+                // payloads are opaque, so the clone shares its original's
+                native: object.native.clone(),
             })
         })
     }
@@ -115,10 +206,13 @@ impl Object {
                 _name: Default::default(),
                 class: None,
                 superclass: None,
+                traits: Vec::new(),
+                fields: Vec::new(),
                 primitive: None,
                 weak_self: weak_self.clone(),
                 properties: HashMap::new(),
                 methods: HashMap::new(),
+                native: None,
             })
         })
     }
@@ -129,10 +223,13 @@ impl Object {
                 _name: Default::default(),
                 class: Some(class),
                 superclass: None,
+                traits: Vec::new(),
+                fields: Vec::new(),
                 primitive: None,
                 weak_self: weak_self.clone(),
                 properties: HashMap::new(),
                 methods: HashMap::new(),
+                native: None,
             })
         })
     }
@@ -170,16 +267,22 @@ impl Object {
                     receiver: MethodReceiver::Instance,
                     class: self.weak_self(),
                     name: builtin::method::init.into(),
-                    body: MethodBody::System(|runtime, class, _, _| {
-                        Ok(runtime.create_object(class))
-                    }),
+                    body: MethodBody::system(|_runtime, this, _, _| Ok(this)),
                     params: Vec::new(),
+                    compiled: OnceCell::new(),
                 })
             })
     }
 
-    pub fn number(&self) -> Option<f64> {
-        let Some(Primitive::Number(value)) = self.primitive.clone() else {
+    pub fn number(&self) -> Option<Number> {
+        let Some(Primitive::Number(value)) = &self.primitive else {
+            return None;
+        };
+        Some(value.clone())
+    }
+
+    pub fn int(&self) -> Option<i64> {
+        let Some(Primitive::Number(Number::Int(value))) = self.primitive else {
             return None;
         };
         Some(value)
@@ -206,13 +309,28 @@ impl Object {
         Some(value)
     }
 
-    pub fn dictionary(&self) -> Option<&HashMap<String, ObjectRef>> {
+    pub fn dictionary(&self) -> Option<&Dictionary> {
         let Some(Primitive::Dictionary(value)) = &self.primitive else {
             return None;
         };
         Some(value)
     }
 
+    /// The entries of a dictionary, or those a set keeps its elements in.
+    pub fn hash_table(&self) -> Option<&Dictionary> {
+        match &self.primitive {
+            Some(Primitive::Dictionary(value) | Primitive::Set(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn captures(&self) -> Option<&HashMap<String, VariableCell>> {
+        let Some(Primitive::Closure(value)) = &self.primitive else {
+            return None;
+        };
+        Some(value)
+    }
+
     pub fn array_mut(&mut self) -> Option<&mut Vec<ObjectRef>> {
         let Some(Primitive::Array(value)) = &mut self.primitive else {
             return None;
@@ -220,13 +338,20 @@ impl Object {
         Some(value)
     }
 
-    pub fn dictionary_mut(&mut self) -> Option<&mut HashMap<String, ObjectRef>> {
+    pub fn dictionary_mut(&mut self) -> Option<&mut Dictionary> {
         let Some(Primitive::Dictionary(value)) = &mut self.primitive else {
             return None;
         };
         Some(value)
     }
 
+    pub fn hash_table_mut(&mut self) -> Option<&mut Dictionary> {
+        match &mut self.primitive {
+            Some(Primitive::Dictionary(value) | Primitive::Set(value)) => Some(value),
+            _ => None,
+        }
+    }
+
     pub fn set_property(&mut self, name: impl Into<String>, value: ObjectRef) {
         let name = name.into();
         if name == builtin::property::__name__ {
@@ -239,6 +364,22 @@ impl Object {
         self.properties.get(name).cloned()
     }
 
+    pub fn property_names(&self) -> impl Iterator<Item = String> + '_ {
+        self.properties.keys().cloned()
+    }
+
+    /// The names of this class's methods, including inherited and included ones.
+    pub fn method_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.methods.keys().cloned().collect();
+        for included in &self.traits {
+            names.extend(included.borrow().method_names());
+        }
+        if let Some(superclass) = &self.superclass {
+            names.extend(superclass.borrow().method_names());
+        }
+        names
+    }
+
     pub fn weak_self(&self) -> WeakObjectRef {
         self.weak_self.clone()
     }
@@ -247,6 +388,14 @@ impl Object {
         self.primitive = Some(primitive);
     }
 
+    pub fn native(&self) -> Option<NativePayload> {
+        self.native.clone()
+    }
+
+    pub fn set_native(&mut self, payload: NativePayload) {
+        self.native = Some(payload);
+    }
+
     pub fn define_method(
         &mut self,
         receiver: MethodReceiver,
@@ -256,13 +405,7 @@ impl Object {
     ) -> Result<()> {
         if self.methods.contains_key(&method_name) {
             return Err(DuplicateMethodDefinition {
-                class: self
-                    .weak_self
-                    .upgrade()
-                    .expect("help i dont exist")
-                    .try_borrow()
-                    .map(|c| c.__name__().unwrap())
-                    .unwrap_or("Class".to_string()),
+                class: self.__name__().unwrap_or_else(|| "Class".to_string()),
                 name: method_name.clone(),
             });
         }
@@ -272,15 +415,96 @@ impl Object {
             receiver,
             params,
             body,
+            compiled: OnceCell::new(),
         };
         self.methods.insert(method_name, MethodRef::new(method));
         Ok(())
     }
 
+    /// Visits every strong reference this object holds to other objects, once per reference.
+    pub fn for_each_reference(&self, mut visit: impl FnMut(&ObjectRef)) {
+        self.class.iter().for_each(&mut visit);
+        self.superclass.iter().for_each(&mut visit);
+        self.traits.iter().for_each(&mut visit);
+        self.properties.values().for_each(&mut visit);
+        match &self.primitive {
+            Some(Primitive::Array(elements)) => elements.iter().for_each(&mut visit),
+            Some(Primitive::Dictionary(entries) | Primitive::Set(entries)) => {
+                entries.iter().for_each(|(key, value)| {
+                    visit(key);
+                    visit(value);
+                })
+            }
+            // a cell shared with a live frame or another closure is not ours alone to count
+            Some(Primitive::Closure(captures)) => captures
+                .values()
+                .filter(|cell| Rc::strong_count(cell) == 1)
+                .for_each(|cell| cell.borrow().iter().for_each(&mut visit)),
+            _ => {}
+        }
+    }
+
+    /// Drops every reference this object holds, breaking any cycle it is part of.
+    pub fn clear_references(&mut self) {
+        self.class = None;
+        self.superclass = None;
+        self.traits.clear();
+        self.properties.clear();
+        self.methods.clear();
+        self.primitive = None;
+        self.native = None;
+    }
+
+    /// Includes a trait, whose methods take precedence over those of traits included before it
+    /// but not over the class's own. Including a trait again changes nothing.
+    pub fn include(&mut self, included: ObjectRef) {
+        if !self.traits.iter().any(|other| Rc::ptr_eq(other, &included)) {
+            self.traits.push(included);
+        }
+    }
+
+    /// The classes and traits that methods are looked up in, in order: this one, the traits it
+    /// includes with the latest first, each followed by the traits it includes in turn, and then
+    /// the ancestors of its superclass. Each is listed where it is first reached.
+    pub fn ancestors(&self) -> Vec<ObjectRef> {
+        let mut ancestors = Vec::new();
+        self.collect_ancestors(&mut ancestors);
+        ancestors
+    }
+
+    fn collect_ancestors(&self, ancestors: &mut Vec<ObjectRef>) {
+        let this = self.weak_self.upgrade().expect("help i dont exist");
+        if ancestors.iter().any(|ancestor| Rc::ptr_eq(ancestor, &this)) {
+            return;
+        }
+        ancestors.push(this);
+        for included in self.traits.iter().rev() {
+            included.borrow().collect_ancestors(ancestors);
+        }
+        if let Some(superclass) = &self.superclass {
+            superclass.borrow().collect_ancestors(ancestors);
+        }
+    }
+
+    /// A method defined on this very class or trait, not on one of its ancestors.
+    pub fn own_method(&self, name: &str) -> Option<MethodRef> {
+        self.methods.get(name).cloned()
+    }
+
+    /// Looks a method up through the ancestors, finding the same one as going through
+    /// [`Object::ancestors`] in order would.
     pub fn resolve_own_method(&self, name: &str) -> Option<MethodRef> {
         if let Some(method) = self.methods.get(name) {
             return Some(method.clone());
         };
+        let included = self
+            .traits
+            .iter()
+            .rev()
+            .find_map(|included| included.borrow().resolve_own_method(name));
+        if included.is_some() {
+            return included;
+        }
         if let Some(superclass) = self.superclass.as_ref() {
             return superclass.borrow().resolve_own_method(name);
         }
@@ -288,6 +512,31 @@ impl Object {
     }
 }
 
+/// What a builtin method of `class` works on in its receiver, such as the text of a `String`.
+/// The method can also be called on the class itself, which has none, and that fails.
+pub(super) fn receiver_primitive<'a, T: ?Sized>(
+    this: &'a ObjectRef,
+    class: &str,
+    primitive: impl FnOnce(&Object) -> Option<&T>,
+) -> Result<Ref<'a, T>> {
+    Ref::filter_map(this.borrow(), primitive).map_err(|this_ref| TypeMismatch {
+        class: this_ref.__class__().borrow().__name__().unwrap(),
+        expected: class.into(),
+    })
+}
+
+/// Like `receiver_primitive`, for a method that changes it.
+pub(super) fn receiver_primitive_mut<'a, T: ?Sized>(
+    this: &'a ObjectRef,
+    class: &str,
+    primitive: impl FnOnce(&mut Object) -> Option<&mut T>,
+) -> Result<RefMut<'a, T>> {
+    RefMut::filter_map(this.borrow_mut(), primitive).map_err(|this_mut| TypeMismatch {
+        class: this_mut.__class__().borrow().__name__().unwrap(),
+        expected: class.into(),
+    })
+}
+
 impl Debug for Object {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Object")
@@ -0,0 +1,50 @@
+use crate::runtime::object::ObjectRef;
+use crate::runtime::Error::{ReturnFromMethod, TypeMismatch};
+use crate::runtime::{builtin, Result, Runtime};
+use crate::types::NodeMeta;
+
+/// What an `Option` or a `Result` holds.
+pub(super) enum Outcome {
+    /// The value of `Some(value)` or `Ok(value)`.
+    Value(ObjectRef),
+    /// `None`.
+    Nothing,
+    /// The error of `Err(error)`.
+    Error(ObjectRef),
+}
+
+impl Runtime {
+    /// What `value` holds if it is an `Option` or a `Result`. The builtin classes are told apart
+    /// by identity, so that a class of the same name defined elsewhere is not mistaken for one.
+    pub(super) fn outcome(&self, value: &ObjectRef) -> Option<Outcome> {
+        let value_ref = value.borrow();
+        let class = value_ref.__class__();
+        let property = |name| value_ref.get_property(name);
+        if class == self.builtins.Some || class == self.builtins.Ok {
+            property(builtin::property::value).map(Outcome::Value)
+        } else if class == self.builtins.NoneClass {
+            Some(Outcome::Nothing)
+        } else if class == self.builtins.Err {
+            property(builtin::property::error).map(Outcome::Error)
+        } else {
+            None
+        }
+    }
+
+    /// The value of `value?`: what a `Some` or an `Ok` holds, or else a return of the `None` or
+    /// `Err` itself from the method it is in.
+    pub(super) fn propagate(&mut self, value: ObjectRef, node: &NodeMeta) -> Result<ObjectRef> {
+        match self.outcome(&value) {
+            Some(Outcome::Value(inner)) => Ok(inner),
+            Some(Outcome::Nothing | Outcome::Error(_)) => Err(ReturnFromMethod {
+                retval: Some(value),
+                node: node.clone(),
+            }),
+            None => Err(TypeMismatch {
+                expected: format!("{} or {}", builtin::class::Option, builtin::class::Result),
+                class: value.borrow().__class__().borrow().__name__().unwrap(),
+            }
+            .at(node)),
+        }
+    }
+}
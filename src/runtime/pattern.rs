@@ -0,0 +1,196 @@
+use crate::runtime::class::class_fields;
+use crate::runtime::object::ObjectRef;
+use crate::runtime::Error::{PatternFields, TypeMismatch};
+use crate::runtime::{builtin, Result, Runtime, StackFrame};
+use crate::types::{ArrayPattern, Match, MatchCase, Node, Pattern};
+
+/// The items of `value` if it is an instance of `class`, which is `Array` or `Tuple`.
+fn items_of(value: &ObjectRef, class: &ObjectRef) -> Option<Vec<ObjectRef>> {
+    let value_ref = value.borrow();
+    if value_ref.__class__() != *class {
+        return None;
+    }
+    value_ref.array().cloned()
+}
+
+impl Runtime {
+    pub(super) fn eval_match(&mut self, match_expr: Node<Match>) -> Result<ObjectRef> {
+        let Match { subject, cases } = match_expr.v;
+        let subject = self.eval(*subject)?;
+        for case in cases {
+            let mut bindings = Vec::new();
+            if !self.match_pattern(&case.v.pattern, &subject, &mut bindings)? {
+                continue;
+            }
+            let stack_id = self.push_stack_frame(StackFrame {
+                _context: "match case",
+                ..StackFrame::default()
+            });
+            for (name, value) in bindings {
+                self.bind_variable(name, value);
+            }
+            let result = self.eval_case(case.v);
+            self.pop_stack_frame(stack_id);
+            if let Some(value) = result? {
+                return Ok(value);
+            }
+        }
+        Ok(self.nil())
+    }
+
+    /// Runs the body of a case whose pattern matched, in the scope holding what the pattern
+    /// binds, unless its guard fails.
+    fn eval_case(&mut self, case: MatchCase) -> Result<Option<ObjectRef>> {
+        if let Some(guard) = case.guard {
+            let guard = self.eval(guard)?;
+            if self.is_falsy(&guard) {
+                return Ok(None);
+            }
+        }
+        self.eval_block(case.body).map(Some)
+    }
+
+    /// Whether `value` matches `pattern`, adding what the pattern binds to `bindings` if it
+    /// does. Literals and values are compared with `==` and class patterns check `is_a?` before matching
+    /// fields, so this may run concorde code.
+    pub(super) fn match_pattern(
+        &mut self,
+        pattern: &Node<Pattern>,
+        value: &ObjectRef,
+        bindings: &mut Vec<(String, ObjectRef)>,
+    ) -> Result<bool> {
+        match &pattern.v {
+            Pattern::Wildcard(_) => Ok(true),
+            Pattern::Variable(var) => {
+                bindings.push((var.v.ident.v.name.clone(), value.clone()));
+                Ok(true)
+            }
+            Pattern::Literal(literal) => {
+                let literal = self.eval_literal(literal.clone())?;
+                self.values_equal(&literal, value)
+            }
+            Pattern::ValuePattern(pattern) => {
+                let variable = &pattern.v.variable;
+                let name = &variable.v.ident.v.name;
+                let expected = self
+                    .resolve_variable(name)
+                    .ok_or_else(|| self.no_such_variable(name.clone(), &variable.meta))?;
+                self.values_equal(&expected, value)
+            }
+            Pattern::TuplePattern(tuple) => {
+                let Some(items) = items_of(value, &self.builtins.Tuple) else {
+                    return Ok(false);
+                };
+                if items.len() != tuple.v.items.len() {
+                    return Ok(false);
+                }
+                self.match_all(&tuple.v.items, &items, bindings)
+            }
+            Pattern::ArrayPattern(array) => {
+                let Some(elements) = items_of(value, &self.builtins.Array) else {
+                    return Ok(false);
+                };
+                self.match_array(&array.v, elements, bindings)
+            }
+            Pattern::DictionaryPattern(dictionary) => {
+                if value.borrow().dictionary().is_none() {
+                    return Ok(false);
+                }
+                for (key, pattern) in &dictionary.v.entries {
+                    let key = self.eval_literal(key.clone())?;
+                    let Some(entry) = self.dictionary_get(value, &key)? else {
+                        return Ok(false);
+                    };
+                    if !self.match_pattern(pattern, &entry, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Pattern::ClassPattern(class_pattern) => {
+                let class = self.eval(class_pattern.v.class.clone())?;
+                if !self.is_class(&class) {
+                    return Err(TypeMismatch {
+                        expected: builtin::class::Class.into(),
+                        class: class.borrow().__class__().borrow().__name__().unwrap(),
+                    }
+                    .at(&class_pattern.v.class.meta));
+                }
+                let fields = class_fields(&class);
+                let patterns = &class_pattern.v.fields;
+                if patterns.len() > fields.len() {
+                    return Err(PatternFields {
+                        class: class.borrow().__name__().unwrap(),
+                        fields: fields.len(),
+                        patterns: patterns.len(),
+                        node: pattern.meta.clone(),
+                    });
+                }
+                let value_class = value.borrow().__class__();
+                if !self.inherits_from(&value_class, &class) {
+                    return Ok(false);
+                }
+                for (field, pattern) in fields.iter().zip(patterns) {
+                    let field_value = value.borrow().get_property(&field.v.name.v.name);
+                    let Some(field_value) = field_value else {
+                        return Ok(false);
+                    };
+                    if !self.match_pattern(pattern, &field_value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Pattern::AlternativePattern(alternatives) => {
+                for alternative in &alternatives.v.alternatives {
+                    let bound = bindings.len();
+                    if self.match_pattern(alternative, value, bindings)? {
+                        return Ok(true);
+                    }
+                    bindings.truncate(bound);
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    fn match_array(
+        &mut self,
+        array: &ArrayPattern,
+        elements: Vec<ObjectRef>,
+        bindings: &mut Vec<(String, ObjectRef)>,
+    ) -> Result<bool> {
+        let ArrayPattern {
+            before,
+            rest,
+            after,
+        } = array;
+        let Some(rest) = rest else {
+            return Ok(
+                elements.len() == before.len() && self.match_all(before, &elements, bindings)?
+            );
+        };
+        if elements.len() < before.len() + after.len() {
+            return Ok(false);
+        }
+        let rest_end = elements.len() - after.len();
+        let rest_elements = self.create_array(elements[before.len()..rest_end].to_vec());
+        Ok(self.match_all(before, &elements[..before.len()], bindings)?
+            && self.match_pattern(rest, &rest_elements, bindings)?
+            && self.match_all(after, &elements[rest_end..], bindings)?)
+    }
+
+    fn match_all(
+        &mut self,
+        patterns: &[Node<Pattern>],
+        values: &[ObjectRef],
+        bindings: &mut Vec<(String, ObjectRef)>,
+    ) -> Result<bool> {
+        for (pattern, value) in patterns.iter().zip(values) {
+            if !self.match_pattern(pattern, value, bindings)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,24 @@
+use crate::parse;
+use crate::runtime::Runtime;
+use crate::types::{Node, Program};
+
+/// The standard prelude, built into the binary so that it does not have to be found on disk.
+pub const STD_PRELUDE_SOURCE: &str = include_str!("../../examples/std.concorde");
+/// What diagnostics call the file of the standard prelude.
+pub const STD_PRELUDE_NAME: &str = "<std>";
+
+thread_local! {
+    /// The standard prelude is parsed once, and every runtime started afterwards runs the same
+    /// AST.
+    static STD_PRELUDE: Node<Program> = parse::parse_source(STD_PRELUDE_SOURCE, STD_PRELUDE_NAME)
+        .expect("the standard prelude does not parse");
+}
+
+impl Runtime {
+    pub(super) fn run_std_prelude(&mut self) {
+        STD_PRELUDE
+            .with(|program| self.run_program(program))
+            .expect("the standard prelude failed");
+        self.record_prelude_globals();
+    }
+}
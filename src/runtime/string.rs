@@ -0,0 +1,65 @@
+use std::cell::Ref;
+
+use crate::runtime::object::{receiver_primitive, Object, ObjectRef};
+use crate::runtime::Error::{InvalidArgument, TypeMismatch};
+use crate::runtime::{builtin, Result, Runtime};
+
+/// The right-hand side of a comparison or other operator on a `String`.
+pub(super) fn string_operand(other: &ObjectRef) -> Result<String> {
+    let other_ref = other.borrow();
+    other_ref.string().cloned().ok_or_else(|| TypeMismatch {
+        class: other_ref.__class__().borrow().__name__().unwrap(),
+        expected: builtin::class::String.into(),
+    })
+}
+
+/// The text of the `String` a method is called on.
+pub(super) fn string_receiver(this: &ObjectRef) -> Result<Ref<'_, String>> {
+    receiver_primitive(this, builtin::class::String, Object::string)
+}
+
+impl Runtime {
+    /// The string of an interpolated string literal, made of the `to_s` of each of its parts.
+    pub(super) fn join_strings(&mut self, parts: Vec<ObjectRef>) -> Result<ObjectRef> {
+        let mut result = String::new();
+        for part in parts {
+            result.push_str(&self.string_of(part)?);
+        }
+        Ok(self.create_string(result))
+    }
+
+    /// Fills each `{}` in `template` with the next of `args`, as its `to_s` gives it. `{{` and
+    /// `}}` stand for literal braces.
+    pub(super) fn format_string(&mut self, template: &str, args: Vec<ObjectRef>) -> Result<String> {
+        let mut args = args.into_iter();
+        let mut result = String::with_capacity(template.len());
+        let mut chars = template.chars().peekable();
+        while let Some(char) = chars.next() {
+            match (char, chars.peek()) {
+                ('{', Some('{')) | ('}', Some('}')) => {
+                    chars.next();
+                    result.push(char);
+                }
+                ('{', Some('}')) => {
+                    chars.next();
+                    let arg = args.next().ok_or(InvalidArgument {
+                        reason: "too few arguments for the format string",
+                    })?;
+                    result.push_str(&self.string_of(arg)?);
+                }
+                ('{' | '}', _) => {
+                    return Err(InvalidArgument {
+                        reason: "unmatched brace in the format string",
+                    })
+                }
+                _ => result.push(char),
+            }
+        }
+        if args.next().is_some() {
+            return Err(InvalidArgument {
+                reason: "too many arguments for the format string",
+            });
+        }
+        Ok(result)
+    }
+}
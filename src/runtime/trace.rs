@@ -0,0 +1,123 @@
+use std::fmt::{Display, Formatter};
+
+use crate::runtime::object::{Method, MethodBody, MethodReceiver, ObjectRef};
+use crate::runtime::Error::Traced;
+use crate::runtime::{builtin, Error, Runtime};
+use crate::types::NodeMeta;
+
+pub const MAIN_FRAME: &str = "<main>";
+
+/// A frame that an error unwound out of.
+#[derive(Debug, Clone)]
+pub struct TraceFrame {
+    pub method: String,
+    /// The class of the receiver, or the receiver itself for class methods. Top-level frames
+    /// have none.
+    pub class: Option<String>,
+    pub receiver: MethodReceiver,
+    /// Where the frame was when the error left it: the failing node in the innermost frame and
+    /// the call in the others, if known.
+    pub node: Option<NodeMeta>,
+}
+
+/// The call stack of an error, innermost frame first.
+#[derive(Debug, Clone, Default)]
+pub struct Traceback {
+    pub frames: Vec<TraceFrame>,
+}
+
+impl Error {
+    /// Records that the error unwound out of a frame. Control flow is not traced.
+    pub(super) fn traced(
+        self,
+        method: impl Into<String>,
+        class: Option<String>,
+        receiver: MethodReceiver,
+    ) -> Self {
+        if !self.is_catchable() {
+            return self;
+        }
+        let (error, mut traceback, node) = match self {
+            Traced {
+                error,
+                traceback,
+                call_site,
+            } => (error, traceback, call_site),
+            error => {
+                let node = error.node().cloned();
+                (Box::new(error), Traceback::default(), node)
+            }
+        };
+        traceback.frames.push(TraceFrame {
+            method: method.into(),
+            class,
+            receiver,
+            node,
+        });
+        Traced {
+            error,
+            traceback,
+            call_site: None,
+        }
+    }
+
+    pub fn traceback(&self) -> Option<&Traceback> {
+        match self {
+            Traced { traceback, .. } => Some(traceback),
+            _ => None,
+        }
+    }
+}
+
+impl Runtime {
+    /// Records that `error` unwound out of a call of `method` on `receiver`. The system
+    /// `__call__` of closures and `Method` objects is left out, as the method it forwards to has
+    /// a frame of its own.
+    pub(super) fn trace_call(&self, error: Error, receiver: &ObjectRef, method: &Method) -> Error {
+        if matches!(method.body, MethodBody::System(_)) && method.name == builtin::op::__call__ {
+            return error;
+        }
+        let class = match method.receiver {
+            MethodReceiver::Class => receiver.borrow().__name__(),
+            MethodReceiver::Instance => receiver.borrow().__class__().borrow().__name__(),
+        };
+        error.traced(method.name.clone(), class, method.receiver)
+    }
+}
+
+impl Display for TraceFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(node) = &self.node {
+            let (line, column) = node.line_col;
+            write!(
+                f,
+                "File \"{}\", line {line}, column {column}, ",
+                node.file.name
+            )?;
+        }
+        write!(f, "in ")?;
+        match (&self.class, self.receiver) {
+            (Some(class), MethodReceiver::Instance) => write!(f, "{class}#")?,
+            (Some(class), MethodReceiver::Class) => write!(f, "{class}::")?,
+            (None, _) => {}
+        }
+        write!(f, "{}", self.method)
+    }
+}
+
+impl Display for Traceback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Traceback (most recent call last):")?;
+        for frame in self.frames.iter().rev() {
+            writeln!(f, "  {frame}")?;
+            let line = frame
+                .node
+                .as_ref()
+                .and_then(|node| node.file.line(node.line_col.0));
+            if let Some(line) = line {
+                writeln!(f, "    {}", line.trim())?;
+            }
+        }
+        Ok(())
+    }
+}
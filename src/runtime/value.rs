@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+
+use crate::runtime::number::Number;
+use crate::runtime::object::ObjectRef;
+use crate::runtime::Error::TypeMismatch;
+use crate::runtime::{builtin, Error, Result, Runtime};
+
+/// A concorde object held by the application embedding the runtime.
+///
+/// Values keep the object alive: the garbage collector treats anything held from Rust as a root.
+#[derive(Clone, PartialEq)]
+pub struct Value(pub(crate) ObjectRef);
+
+impl Value {
+    pub fn class_name(&self) -> String {
+        self.0.borrow().__class__().borrow().__name__().unwrap()
+    }
+
+    pub fn property(&self, name: &str) -> Option<Value> {
+        self.0.borrow().get_property(name).map(Value)
+    }
+
+    pub fn set_property(&self, name: impl Into<String>, value: Value) {
+        self.0.borrow_mut().set_property(name, value.0);
+    }
+
+    /// Converts the value to a Rust type, e.g. `value.get::<f64>(&runtime)`.
+    pub fn get<T: FromValue>(&self, runtime: &Runtime) -> Result<T> {
+        T::from_value(self, runtime)
+    }
+
+    fn type_mismatch(&self, expected: &str) -> Error {
+        TypeMismatch {
+            expected: expected.into(),
+            class: self.class_name(),
+        }
+    }
+}
+
+impl Debug for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0.borrow().__debug__())
+    }
+}
+
+/// A Rust value that can be handed to concorde code.
+pub trait IntoValue {
+    fn into_value(self, runtime: &mut Runtime) -> Value;
+}
+
+/// A Rust type that a concorde value can be converted to.
+pub trait FromValue: Sized {
+    fn from_value(value: &Value, runtime: &Runtime) -> Result<Self>;
+}
+
+impl IntoValue for Value {
+    fn into_value(self, _runtime: &mut Runtime) -> Value {
+        self
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: &Value, _runtime: &Runtime) -> Result<Self> {
+        Ok(value.clone())
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self, runtime: &mut Runtime) -> Value {
+        Value(runtime.nil())
+    }
+}
+
+/// Accepts any value and discards it, for calls made only for their effect.
+impl FromValue for () {
+    fn from_value(_value: &Value, _runtime: &Runtime) -> Result<Self> {
+        Ok(())
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self, runtime: &mut Runtime) -> Value {
+        Value(runtime.create_bool(self))
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value, _runtime: &Runtime) -> Result<Self> {
+        let bool = value.0.borrow().bool();
+        bool.ok_or_else(|| value.type_mismatch(builtin::class::Bool))
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self, runtime: &mut Runtime) -> Value {
+        Value(runtime.create_float(self))
+    }
+}
+
+/// An `Int` converts too, to the nearest float.
+impl FromValue for f64 {
+    fn from_value(value: &Value, _runtime: &Runtime) -> Result<Self> {
+        let number = value.0.borrow().number();
+        number
+            .as_ref()
+            .map(Number::as_f64)
+            .ok_or_else(|| value.type_mismatch(builtin::class::Number))
+    }
+}
+
+impl IntoValue for i64 {
+    fn into_value(self, runtime: &mut Runtime) -> Value {
+        Value(runtime.create_int(self))
+    }
+}
+
+/// Only an `Int` converts to an integer, not a `Float` even when it is whole.
+impl FromValue for i64 {
+    fn from_value(value: &Value, _runtime: &Runtime) -> Result<Self> {
+        let int = value.0.borrow().int();
+        int.ok_or_else(|| value.type_mismatch(builtin::class::Int))
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self, runtime: &mut Runtime) -> Value {
+        Value(runtime.create_string(self))
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self, runtime: &mut Runtime) -> Value {
+        Value(runtime.create_string(self))
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &Value, _runtime: &Runtime) -> Result<Self> {
+        let string = value.0.borrow().string().cloned();
+        string.ok_or_else(|| value.type_mismatch(builtin::class::String))
+    }
+}
+
+/// `None` is `nil`.
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self, runtime: &mut Runtime) -> Value {
+        match self {
+            Some(value) => value.into_value(runtime),
+            None => Value(runtime.nil()),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &Value, runtime: &Runtime) -> Result<Self> {
+        if value.0 == runtime.nil() {
+            return Ok(None);
+        }
+        T::from_value(value, runtime).map(Some)
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self, runtime: &mut Runtime) -> Value {
+        let elements = self
+            .into_iter()
+            .map(|element| element.into_value(runtime).0)
+            .collect();
+        Value(runtime.create_array(elements))
+    }
+}
+
+/// Both arrays and tuples convert to a `Vec`.
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: &Value, runtime: &Runtime) -> Result<Self> {
+        let elements = value.0.borrow().array().cloned();
+        elements
+            .ok_or_else(|| value.type_mismatch(builtin::class::Array))?
+            .into_iter()
+            .map(|element| T::from_value(&Value(element), runtime))
+            .collect()
+    }
+}
+
+impl<T: IntoValue> IntoValue for HashMap<String, T> {
+    fn into_value(self, runtime: &mut Runtime) -> Value {
+        let entries = self
+            .into_iter()
+            .map(|(key, value)| (key, value.into_value(runtime).0))
+            .collect();
+        Value(runtime.create_dictionary(entries))
+    }
+}
+
+/// Only a dictionary whose keys are all strings converts to a `HashMap`.
+impl<T: FromValue> FromValue for HashMap<String, T> {
+    fn from_value(value: &Value, runtime: &Runtime) -> Result<Self> {
+        let entries: Vec<_> = value
+            .0
+            .borrow()
+            .dictionary()
+            .ok_or_else(|| value.type_mismatch(builtin::class::Dictionary))?
+            .iter()
+            .map(|(key, value)| (Value(key.clone()), Value(value.clone())))
+            .collect();
+        entries
+            .into_iter()
+            .map(|(key, value)| {
+                Ok((
+                    String::from_value(&key, runtime)?,
+                    T::from_value(&value, runtime)?,
+                ))
+            })
+            .collect()
+    }
+}
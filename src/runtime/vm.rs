@@ -0,0 +1,641 @@
+use std::ops::ControlFlow;
+use std::rc::Rc;
+
+use crate::runtime::compile::{Chunk, Compiler, Constant, Instruction, Target, Update};
+use crate::runtime::object::{Method, MethodRef, ObjectRef};
+use crate::runtime::Error::{NoSuchProperty, ReturnFromMethod, UndefinedProperty};
+use crate::runtime::{builtin, Error, Result, Runtime, StackFrame};
+use crate::types::{Block, Node, Program};
+
+/// The execution engine used for user code.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum ExecMode {
+    /// Compile bodies to bytecode on first use and run them on the stack VM.
+    #[default]
+    Bytecode,
+    /// Walk the AST directly.
+    TreeWalk,
+}
+
+struct LoopState {
+    next: usize,
+    exit: usize,
+    values: usize,
+    callees: usize,
+    frames: usize,
+    iterator: Option<(ObjectRef, MethodRef)>,
+}
+
+struct TryState {
+    handler: usize,
+    catches_all: bool,
+    values: usize,
+    callees: usize,
+    frames: usize,
+    loops: usize,
+    caught: usize,
+}
+
+impl TryState {
+    fn catches(&self, error: &Error) -> bool {
+        self.catches_all || error.is_catchable()
+    }
+}
+
+impl Runtime {
+    pub(super) fn run_program(&mut self, program: &Node<Program>) -> Result<ObjectRef> {
+        let chunk = Compiler::compile_program(&program.v.body);
+        self.run_chunk(&chunk)
+    }
+
+    /// Runs a user method body on the VM, compiling it the first time it is called.
+    pub(super) fn run_method(
+        &mut self,
+        receiver: ObjectRef,
+        method: &MethodRef,
+        body: &Node<Block>,
+        bound: Vec<Option<ObjectRef>>,
+    ) -> Result<ObjectRef> {
+        let chunk = self.compiled_chunk(method, body);
+        let captures = receiver.borrow().captures().cloned().unwrap_or_default();
+        let mut slots = bound.clone();
+        slots.resize(chunk.scopes[0].len(), None);
+        let stack_id = self.push_stack_frame(StackFrame {
+            instance: Some(receiver),
+            _context: "method call",
+            _method: Some(method.clone()),
+            slot_names: chunk.scopes[0].clone(),
+            slots,
+            captures,
+            ..StackFrame::default()
+        });
+        let result = self
+            .bind_defaults(method, &bound)
+            .and_then(|()| self.run_chunk(&chunk));
+        self.pop_stack_frame(stack_id);
+        result
+    }
+
+    fn compiled_chunk(&mut self, method: &Method, body: &Node<Block>) -> Rc<Chunk> {
+        if let Some(chunk) = method.compiled.get() {
+            return chunk.clone();
+        }
+        let params: Vec<String> = method
+            .params
+            .iter()
+            .map(|param| param.name().to_string())
+            .collect();
+        let chunk = Rc::new(Compiler::compile_method(&params, body));
+        method.compiled.set(chunk.clone()).unwrap();
+        chunk
+    }
+
+    fn scope_slots(chunk: &Chunk, scope: usize, values: Vec<ObjectRef>) -> Vec<Option<ObjectRef>> {
+        let mut slots: Vec<_> = values.into_iter().map(Some).collect();
+        slots.resize(chunk.scopes[scope].len(), None);
+        slots
+    }
+
+    fn run_chunk(&mut self, chunk: &Chunk) -> Result<ObjectRef> {
+        let base = self.stack.len() - 1;
+        let handlers = self.handlers;
+        let result = self.run_chunk_from(chunk, base);
+        // the tries of the chunk are over, however it left them
+        self.handlers = handlers;
+        if result.is_err() {
+            self.stack.truncate(base + 1);
+        }
+        result
+    }
+
+    fn run_chunk_from(&mut self, chunk: &Chunk, base: usize) -> Result<ObjectRef> {
+        let mut values: Vec<ObjectRef> = Vec::new();
+        let mut callees: Vec<(ObjectRef, MethodRef)> = Vec::new();
+        let mut loops: Vec<LoopState> = Vec::new();
+        let mut tries: Vec<TryState> = Vec::new();
+        let mut caught: Vec<Error> = Vec::new();
+        let mut ip = 0;
+        'run: while ip < chunk.code.len() {
+            let instruction = chunk.code[ip];
+            ip += 1;
+            let result = self.step(
+                chunk,
+                base,
+                instruction,
+                &mut ip,
+                &mut values,
+                &mut callees,
+                &mut loops,
+                &mut tries,
+                &mut caught,
+            );
+            let error = match result {
+                Ok(()) => continue,
+                Err(error) => error,
+            };
+            while let Some(state) = tries.last() {
+                // a loop inside the try sees break and continue first
+                if matches!(error, Error::ControlFlow(_)) && state.loops < loops.len() {
+                    break;
+                }
+                let state = tries.pop().unwrap();
+                self.handlers -= 1;
+                if !state.catches(&error) {
+                    continue;
+                }
+                values.truncate(state.values);
+                callees.truncate(state.callees);
+                self.stack.truncate(state.frames);
+                loops.truncate(state.loops);
+                caught.truncate(state.caught);
+                if !state.catches_all {
+                    let exception = self.exception_from_error(&error);
+                    values.push(exception);
+                }
+                caught.push(error);
+                ip = state.handler;
+                continue 'run;
+            }
+            let flow = match error {
+                Error::ControlFlow(flow) if !loops.is_empty() => flow,
+                error => return Err(error),
+            };
+            let state = loops.last().unwrap();
+            values.truncate(state.values);
+            callees.truncate(state.callees);
+            self.stack.truncate(state.frames + 1);
+            ip = match flow {
+                ControlFlow::Break(()) => state.exit,
+                ControlFlow::Continue(()) => state.next,
+            };
+        }
+        Ok(values.pop().unwrap_or_else(|| self.nil()))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn step(
+        &mut self,
+        chunk: &Chunk,
+        base: usize,
+        instruction: Instruction,
+        ip: &mut usize,
+        values: &mut Vec<ObjectRef>,
+        callees: &mut Vec<(ObjectRef, MethodRef)>,
+        loops: &mut Vec<LoopState>,
+        tries: &mut Vec<TryState>,
+        caught: &mut Vec<Error>,
+    ) -> Result<()> {
+        match instruction {
+            Instruction::Constant(index) => {
+                let value = match &chunk.constants[index] {
+                    Constant::String(string) => self.create_string(string.as_str()),
+                    Constant::Number(number) => self.create_number(number.clone()),
+                };
+                values.push(value);
+            }
+            Instruction::Nil => values.push(self.nil()),
+            Instruction::True => values.push(self.create_bool(true)),
+            Instruction::False => values.push(self.create_bool(false)),
+            Instruction::Pop => {
+                values.pop();
+            }
+            Instruction::LoadSelf => {
+                let value = self
+                    .resolve_variable(builtin::SELF)
+                    .unwrap_or_else(|| self.nil());
+                values.push(value);
+            }
+            Instruction::Load { target, meta } => {
+                let value = self.load(chunk, base, target, meta)?;
+                values.push(value);
+            }
+            Instruction::Store {
+                target,
+                update,
+                meta,
+            } => {
+                let mut value = values.pop().unwrap();
+                if let Some(update) = update {
+                    let lhs = self.load(chunk, base, target, meta)?;
+                    value = self.apply_update(chunk, update, lhs, value)?;
+                }
+                self.store(chunk, base, target, value);
+            }
+            Instruction::Define { slot } => {
+                let value = values.pop().unwrap();
+                self.stack.last_mut().unwrap().bind_slot(slot, value);
+            }
+            Instruction::Destructure { targets, meta } => {
+                let value = values.pop().unwrap();
+                let items = self.destructure_tuple(&value, chunk.meta(meta))?;
+                for (&target, item) in chunk.targets[targets].iter().zip(items) {
+                    self.store(chunk, base, target, item);
+                }
+            }
+            Instruction::BindItem { targets, meta } => {
+                let item = values.pop().unwrap();
+                let targets = &chunk.targets[targets];
+                let items = self.unpack_item(&item, targets.len(), chunk.meta(meta))?;
+                for (&target, item) in targets.iter().zip(items) {
+                    let Target::Slot { depth, slot } = target else {
+                        unreachable!("iteration bindings always have slots");
+                    };
+                    self.stack[base + depth].bind_slot(slot, item);
+                }
+            }
+            Instruction::GetProperty { name, meta } => {
+                let target = values.pop().unwrap();
+                let member = chunk.name(name);
+                let value = target.borrow().get_property(member);
+                let value = value.ok_or_else(|| UndefinedProperty {
+                    target: target.borrow().__debug__(),
+                    member: member.to_string(),
+                    node: chunk.meta(meta).clone(),
+                })?;
+                values.push(value);
+            }
+            Instruction::SetProperty { name, update } => {
+                let target = values.pop().unwrap();
+                let mut value = values.pop().unwrap();
+                let member = chunk.name(name);
+                if let Some(update) = update {
+                    let lhs =
+                        target
+                            .borrow()
+                            .get_property(member)
+                            .ok_or_else(|| NoSuchProperty {
+                                name: member.to_string(),
+                                node: chunk.meta(update.meta).clone(),
+                            })?;
+                    value = self.apply_update(chunk, update, lhs, value)?;
+                }
+                target.borrow_mut().set_property(member, value);
+            }
+            Instruction::GetItem { index, meta } => {
+                let target = values.pop().unwrap();
+                let item = self.tuple_item(&target, index, chunk.meta(meta))?;
+                values.push(item);
+            }
+            Instruction::SetIndex { update, meta } => {
+                let index = values.pop().unwrap();
+                let target = values.pop().unwrap();
+                let mut value = values.pop().unwrap();
+                if let Some(update) = update {
+                    let lhs = self.invoke(
+                        chunk,
+                        target.clone(),
+                        builtin::op::__index__,
+                        vec![index.clone()],
+                        meta,
+                    )?;
+                    value = self.apply_update(chunk, update, lhs, value)?;
+                }
+                self.invoke(
+                    chunk,
+                    target,
+                    builtin::op::__set_index__,
+                    vec![index, value],
+                    meta,
+                )?;
+            }
+            Instruction::CallMethod {
+                name,
+                argc,
+                keywords: None,
+                meta,
+            } => {
+                let arguments = values.split_off(values.len() - argc);
+                let receiver = values.pop().unwrap();
+                let result = self.invoke(chunk, receiver, chunk.name(name), arguments, meta)?;
+                values.push(result);
+            }
+            Instruction::CallMethod {
+                name,
+                argc,
+                keywords: Some(keywords),
+                meta,
+            } => {
+                let keywords = Self::pop_keywords(chunk, keywords, values);
+                let arguments = values.split_off(values.len() - argc);
+                let receiver = values.pop().unwrap();
+                let method = self.resolve_instance_method(&receiver, chunk.name(name), || {
+                    Some(chunk.meta(meta).clone())
+                })?;
+                let result = self
+                    .call_method_with_keywords(receiver, method, arguments, keywords)
+                    .map_err(|error| error.at(chunk.meta(meta)))?;
+                values.push(result);
+            }
+            Instruction::ResolveNamed {
+                target,
+                meta,
+                callee_meta,
+            } => {
+                let callee = match target {
+                    Target::Slot { depth, slot } => self.stack[base + depth].slots[slot].clone(),
+                    Target::Name(_) => None,
+                };
+                let callee = match callee {
+                    Some(value) => self.resolve_value_callee(value, chunk.meta(callee_meta))?,
+                    None => {
+                        let name = self.target_name(chunk, base, target);
+                        self.resolve_named_callee(&name, chunk.meta(callee_meta), chunk.meta(meta))?
+                    }
+                };
+                callees.push(callee);
+            }
+            Instruction::ResolvePath { path, meta } => {
+                let callee = self.resolve_path_callee(&chunk.paths[path], chunk.meta(meta))?;
+                callees.push(callee);
+            }
+            Instruction::ResolveSuper { method, meta } => {
+                let method = method.map(|name| chunk.name(name));
+                let callee = self.resolve_super_callee(method, chunk.meta(meta))?;
+                callees.push(callee);
+            }
+            Instruction::ResolveValue { meta } => {
+                let callable = values.pop().unwrap();
+                let callee = self.resolve_callable(callable, chunk.meta(meta))?;
+                callees.push(callee);
+            }
+            Instruction::Call {
+                argc,
+                keywords,
+                meta,
+            } => {
+                let keywords = match keywords {
+                    Some(keywords) => Self::pop_keywords(chunk, keywords, values),
+                    None => Vec::new(),
+                };
+                let arguments = values.split_off(values.len() - argc);
+                let (receiver, method) = callees.pop().unwrap();
+                let result = self
+                    .call_method_with_keywords(receiver, method, arguments, keywords)
+                    .map_err(|error| error.at(chunk.meta(meta)))?;
+                values.push(result);
+            }
+            Instruction::LoadPath(path) => {
+                let value = self.eval_path(&chunk.paths[path])?;
+                values.push(value);
+            }
+            Instruction::MakeClosure(index) => {
+                let prototype = &chunk.closures[index];
+                let closure = self.create_closure(&prototype.node)?;
+                let compiled = prototype.compiled.get_or_init(|| {
+                    let params: Vec<String> = prototype
+                        .node
+                        .v
+                        .binding
+                        .iter()
+                        .map(|var| var.v.ident.v.name.clone())
+                        .collect();
+                    Rc::new(Compiler::compile_method(&params, &prototype.node.v.body))
+                });
+                let method = closure
+                    .borrow()
+                    .resolve_own_method(builtin::op::__call__)
+                    .unwrap();
+                let _ = method.compiled.set(compiled.clone());
+                values.push(closure);
+            }
+            Instruction::BuildArray(count) => {
+                let elements = values.split_off(values.len() - count);
+                let array = self.create_array(elements);
+                values.push(array);
+            }
+            Instruction::BuildTuple(count) => {
+                let items = values.split_off(values.len() - count);
+                let tuple = self.create_tuple(items);
+                values.push(tuple);
+            }
+            Instruction::BuildDictionary(count) => {
+                let entries = values
+                    .split_off(values.len() - 2 * count)
+                    .into_iter()
+                    .array_chunks()
+                    .map(|[key, value]| (key, value))
+                    .collect();
+                let dictionary = self.build_dictionary(entries)?;
+                values.push(dictionary);
+            }
+            Instruction::Propagate { meta } => {
+                let value = values.pop().unwrap();
+                values.push(self.propagate(value, chunk.meta(meta))?);
+            }
+            Instruction::BuildString(count) => {
+                let parts = values.split_off(values.len() - count);
+                let string = self.join_strings(parts)?;
+                values.push(string);
+            }
+            Instruction::MatchCase { pattern, targets } => {
+                let pattern = &chunk.patterns[pattern];
+                let subject = values.last().unwrap().clone();
+                let mut bindings = Vec::new();
+                let matched = self.match_pattern(pattern, &subject, &mut bindings)?;
+                if matched {
+                    let names = pattern.v.bound_names();
+                    for (&target, name) in chunk.targets[targets].iter().zip(names) {
+                        let Target::Slot { slot, .. } = target else {
+                            unreachable!("{target:?} binds a pattern");
+                        };
+                        let (_, value) = bindings.iter().find(|(bound, _)| bound == name).unwrap();
+                        self.stack.last_mut().unwrap().bind_slot(slot, value.clone());
+                    }
+                }
+                values.push(self.create_bool(matched));
+            }
+            Instruction::Jump(to) => *ip = to,
+            Instruction::JumpIfFalse(to) => {
+                let condition = values.pop().unwrap();
+                if self.is_falsy(&condition) {
+                    *ip = to;
+                }
+            }
+            Instruction::JumpIfTrueOrPop(to) => {
+                if self.is_truthy(values.last().unwrap()) {
+                    *ip = to;
+                } else {
+                    values.pop();
+                }
+            }
+            Instruction::JumpIfFalseOrPop(to) => {
+                if self.is_falsy(values.last().unwrap()) {
+                    *ip = to;
+                } else {
+                    values.pop();
+                }
+            }
+            Instruction::PushScope(scope) => {
+                self.push_stack_frame(StackFrame {
+                    _context: "loop",
+                    slot_names: chunk.scopes[scope].clone(),
+                    slots: Self::scope_slots(chunk, scope, Vec::new()),
+                    ..StackFrame::default()
+                });
+            }
+            Instruction::PopScope => {
+                let stack_id = self.stack.last().unwrap().id;
+                self.pop_stack_frame(stack_id);
+            }
+            Instruction::EnterLoop {
+                next,
+                exit,
+                iterate,
+            } => {
+                let iterator = match iterate {
+                    Some(meta) => {
+                        let iterable = values.pop().unwrap();
+                        Some(self.resolve_iterator(iterable, chunk.meta(meta))?)
+                    }
+                    None => None,
+                };
+                loops.push(LoopState {
+                    next,
+                    exit,
+                    values: values.len(),
+                    callees: callees.len(),
+                    frames: self.stack.len(),
+                    iterator,
+                });
+            }
+            Instruction::ExitLoop => {
+                loops.pop();
+            }
+            Instruction::Next { exit, meta } => {
+                let (iterator, next_method) = loops.last().unwrap().iterator.clone().unwrap();
+                match self.next_item(&iterator, &next_method, chunk.meta(meta))? {
+                    Some(item) => values.push(item),
+                    None => *ip = exit,
+                }
+            }
+            Instruction::Break => return Err(Error::ControlFlow(ControlFlow::Break(()))),
+            Instruction::Continue => return Err(Error::ControlFlow(ControlFlow::Continue(()))),
+            Instruction::Return { has_value, meta } => {
+                let retval = has_value.then(|| values.pop().unwrap());
+                return Err(ReturnFromMethod {
+                    retval,
+                    node: chunk.meta(meta).clone(),
+                });
+            }
+            Instruction::Raise { meta } => {
+                let value = values.pop().unwrap();
+                return Err(self.raise(value, chunk.meta(meta)));
+            }
+            Instruction::EnterTry {
+                handler,
+                catches_all,
+            } => {
+                tries.push(TryState {
+                    handler,
+                    catches_all,
+                    values: values.len(),
+                    callees: callees.len(),
+                    frames: self.stack.len(),
+                    loops: loops.len(),
+                    caught: caught.len(),
+                });
+                self.handlers += 1;
+            }
+            Instruction::ExitTry => {
+                tries.pop();
+                self.handlers -= 1;
+            }
+            Instruction::Rescues { meta } => {
+                let class = values.pop().unwrap();
+                let exception = values.last().unwrap();
+                let rescues = self.rescues(&class, exception, chunk.meta(meta))?;
+                values.push(self.create_bool(rescues));
+            }
+            Instruction::DropCaught => {
+                caught.pop();
+            }
+            Instruction::Reraise => return Err(caught.pop().unwrap()),
+            Instruction::Exec(statement) => self.exec(chunk.statements[statement].clone())?,
+            Instruction::Eval(expression) => {
+                let value = self.eval(chunk.expressions[expression].clone())?;
+                values.push(value);
+            }
+        }
+        Ok(())
+    }
+
+    fn target_name(&self, chunk: &Chunk, base: usize, target: Target) -> String {
+        match target {
+            Target::Slot { depth, slot } => self.stack[base + depth].slot_names[slot].clone(),
+            Target::Name(name) => chunk.name(name).to_string(),
+        }
+    }
+
+    /// Reads a variable, falling back to the dynamic lookup when its slot was never assigned.
+    fn load(
+        &mut self,
+        chunk: &Chunk,
+        base: usize,
+        target: Target,
+        meta: usize,
+    ) -> Result<ObjectRef> {
+        if let Target::Slot { depth, slot } = target
+            && let Some(value) = &self.stack[base + depth].slots[slot]
+        {
+            return Ok(value.clone());
+        }
+        let name = self.target_name(chunk, base, target);
+        self.resolve_variable(&name)
+            .ok_or_else(|| self.no_such_variable(name, chunk.meta(meta)))
+    }
+
+    /// Assigns a variable. Like `Runtime::assign_variable`, a variable that already exists
+    /// further up the stack is reassigned there rather than shadowed.
+    fn store(&mut self, chunk: &Chunk, base: usize, target: Target, value: ObjectRef) {
+        match target {
+            Target::Slot { depth, slot } => {
+                let frame = &mut self.stack[base + depth];
+                if frame.slots[slot].is_some() {
+                    frame.slots[slot] = Some(value);
+                    return;
+                }
+                let name = frame.slot_names[slot].clone();
+                if let Err(value) = self.assign_existing_variable(&name, value) {
+                    self.stack[base + depth].slots[slot] = Some(value);
+                }
+            }
+            Target::Name(name) => self.assign_variable(chunk.name(name).to_string(), value),
+        }
+    }
+
+    fn apply_update(
+        &mut self,
+        chunk: &Chunk,
+        update: Update,
+        lhs: ObjectRef,
+        value: ObjectRef,
+    ) -> Result<ObjectRef> {
+        let method_name = chunk.name(update.method);
+        self.invoke(chunk, lhs, method_name, vec![value], update.meta)
+    }
+
+    /// Takes the values of a call's keyword arguments off the stack, paired with their names.
+    fn pop_keywords(
+        chunk: &Chunk,
+        keywords: usize,
+        values: &mut Vec<ObjectRef>,
+    ) -> Vec<(String, ObjectRef)> {
+        let names = &chunk.keyword_names[keywords];
+        let keyword_values = values.split_off(values.len() - names.len());
+        names.iter().cloned().zip(keyword_values).collect()
+    }
+
+    fn invoke(
+        &mut self,
+        chunk: &Chunk,
+        receiver: ObjectRef,
+        method_name: &str,
+        arguments: Vec<ObjectRef>,
+        meta: usize,
+    ) -> Result<ObjectRef> {
+        let method = self
+            .resolve_instance_method(&receiver, method_name, || Some(chunk.meta(meta).clone()))?;
+        self.call_method(receiver, method, arguments)
+            .map_err(|error| error.at(chunk.meta(meta)))
+    }
+}
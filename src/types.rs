@@ -1,5 +1,7 @@
+use std::cell::RefCell;
 use std::fmt::{Debug, Display, Formatter};
 use std::io;
+use std::rc::Rc;
 
 use pest::iterators::Pair;
 
@@ -17,16 +19,78 @@ pub enum TopError {
     IO(#[from] io::Error),
 }
 
+impl TopError {
+    /// The call stack of a runtime error, if it was captured.
+    pub fn traceback(&self) -> Option<&runtime::Traceback> {
+        match self {
+            TopError::Runtime(error) => error.traceback(),
+            _ => None,
+        }
+    }
+}
+
+/// The text a program was parsed from, and the name of the file it came from.
+#[derive(Debug)]
+pub struct SourceFile {
+    pub name: String,
+    pub text: String,
+}
+
+impl SourceFile {
+    /// The text of a line, counting from 1.
+    pub fn line(&self, line: usize) -> Option<&str> {
+        self.text.lines().nth(line.checked_sub(1)?)
+    }
+}
+
+thread_local! {
+    /// The file being parsed, which every node created from a pest pair refers to.
+    static PARSED_FILE: RefCell<Option<Rc<SourceFile>>> = const { RefCell::new(None) };
+}
+
+/// Runs `parse` with `file` as the file that the nodes it creates come from.
+pub fn with_parsed_file<T>(file: Rc<SourceFile>, parse: impl FnOnce() -> T) -> T {
+    let previous = PARSED_FILE.replace(Some(file));
+    let result = parse();
+    PARSED_FILE.set(previous);
+    result
+}
+
 #[derive(Debug, Clone)]
 pub struct NodeMeta {
     pub source: String,
-    pub rule: Rule,
     pub line_col: (usize, usize),
+    pub file: Rc<SourceFile>,
+}
+
+/// What diagnostics call the file of nodes that stand for calls made by the host application.
+pub const HOST_FILE_NAME: &str = "<host>";
+
+impl NodeMeta {
+    /// A node standing for something the application embedding the runtime did, such as
+    /// calling a function by name.
+    pub fn host(source: impl Into<String>) -> Self {
+        let source = source.into();
+        NodeMeta {
+            file: Rc::new(SourceFile {
+                name: HOST_FILE_NAME.into(),
+                text: source.clone(),
+            }),
+            source,
+            line_col: (1, 1),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct MaybeNodeMeta(Option<NodeMeta>);
 
+impl MaybeNodeMeta {
+    pub fn get(&self) -> Option<&NodeMeta> {
+        self.0.as_ref()
+    }
+}
+
 impl Display for MaybeNodeMeta {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match &self.0 {
@@ -50,12 +114,8 @@ impl From<Option<NodeMeta>> for MaybeNodeMeta {
 
 impl Display for NodeMeta {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let NodeMeta {
-            source,
-            rule,
-            line_col: (line, col),
-        } = self;
-        write!(f, "'{source}' at {line}:{col} ({rule:?})")
+        let (line, col) = self.line_col;
+        write!(f, "{}:{line}:{col}", self.file.name)
     }
 }
 
@@ -69,8 +129,10 @@ impl From<&Pair<'_, Rule>> for NodeMeta {
     fn from(pair: &Pair<Rule>) -> Self {
         Self {
             source: pair.as_str().to_string(),
-            rule: pair.as_rule(),
             line_col: pair.line_col(),
+            file: PARSED_FILE
+                .with_borrow(Option::clone)
+                .expect("nodes are only created while parsing a file"),
         }
     }
 }
@@ -82,6 +144,15 @@ pub trait NodeVariant: Sized + Debug + Clone {
             v: self,
         }
     }
+
+    /// A node made up by the parser or runtime rather than read from the source, which points
+    /// at `meta`.
+    fn at(self, meta: &NodeMeta) -> Node<Self> {
+        Node {
+            meta: meta.clone(),
+            v: self,
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -98,6 +169,13 @@ pub enum Operator {
     Star,
     Percent,
     Slash,
+    SlashSlash,
+    Pipe,
+    Ampersand,
+    Caret,
+    ShiftLeft,
+    ShiftRight,
+    Tilde,
     PlusEqual,
     MinusEqual,
     StarEqual,
@@ -109,11 +187,30 @@ pub enum Operator {
 
 impl NodeVariant for Operator {}
 
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub enum ParameterKind {
+    /// `a`, or `a = default` when the argument may be left out.
+    Positional,
+    /// `*rest`, which collects the positional arguments left over.
+    Rest,
+    /// `key: default`, passed by name. Without a default it must be given.
+    Keyword,
+}
+
 define_node_types! {
     Ident {
         name: String,
     }
-    Number {
+    Int {
+        value: i64,
+    }
+    BigInt {
+        value: num_bigint::BigInt,
+    }
+    Rational {
+        value: num_rational::BigRational,
+    }
+    Float {
         value: f64,
     }
     Boolean {
@@ -122,6 +219,9 @@ define_node_types! {
     StringLit {
         value: String,
     }
+    InterpolatedString {
+        parts: Vec<Node<Expression>>,
+    }
     Array {
         elements: Vec<Node<Expression>>,
     }
@@ -129,11 +229,12 @@ define_node_types! {
         items: Vec<Node<Expression>>,
     }
     Dictionary {
-        entries: Vec<(Node<Ident>, Node<Expression>)>,
+        entries: Vec<(Node<Expression>, Node<Expression>)>,
     }
 
     Program {
         body: Node<Block>,
+        warnings: Vec<parse::Warning>,
     }
     IfElse {
         condition: Box<Node<Expression>>,
@@ -145,6 +246,19 @@ define_node_types! {
         iterable: Node<Expression>,
         body: Node<Block>,
     }
+    Raise {
+        value: Node<Expression>,
+    }
+    Try {
+        body: Node<Block>,
+        rescues: Vec<Node<Rescue>>,
+        ensure: Option<Node<Block>>,
+    }
+    Rescue {
+        binding: Option<Node<Variable>>,
+        class: Option<Node<Expression>>,
+        body: Node<Block>,
+    }
     Break {}
     Continue {}
     Nil {}
@@ -155,6 +269,37 @@ define_node_types! {
         condition: Node<Expression>,
         body: Node<Block>,
     }
+    Match {
+        subject: Box<Node<Expression>>,
+        cases: Vec<Node<MatchCase>>,
+    }
+    MatchCase {
+        pattern: Node<Pattern>,
+        guard: Option<Node<Expression>>,
+        body: Node<Block>,
+    }
+    Wildcard {}
+    ValuePattern {
+        variable: Node<Variable>,
+    }
+    TuplePattern {
+        items: Vec<Node<Pattern>>,
+    }
+    ArrayPattern {
+        before: Vec<Node<Pattern>>,
+        rest: Option<Box<Node<Pattern>>>,
+        after: Vec<Node<Pattern>>,
+    }
+    DictionaryPattern {
+        entries: Vec<(Node<Literal>, Node<Pattern>)>,
+    }
+    ClassPattern {
+        class: Node<Expression>,
+        fields: Vec<Node<Pattern>>,
+    }
+    AlternativePattern {
+        alternatives: Vec<Node<Pattern>>,
+    }
     Binary {
         lhs: Box<Node<Expression>>,
         op: Node<Operator>,
@@ -164,6 +309,9 @@ define_node_types! {
         op: Node<Operator>,
         rhs: Box<Node<Expression>>,
     }
+    Propagate {
+        value: Box<Node<Expression>>,
+    }
     Access {
         target: Box<Node<Expression>>,
         member: Box<Node<Expression>>,
@@ -184,6 +332,7 @@ define_node_types! {
     Call {
         target: Box<Node<Expression>>,
         arguments: Vec<Node<Expression>>,
+        keywords: Vec<(Node<Ident>, Node<Expression>)>,
     }
     Binding {
         variables: Vec<Node<Variable>>,
@@ -191,22 +340,37 @@ define_node_types! {
     Variable {
         ident: Node<Ident>,
     }
+    Super {
+        method: Option<Node<Ident>>,
+    }
     Path {
         components: Vec<Node<Variable>>,
     }
     Use {
         path: Node<Path>,
     }
+    Import {
+        path: Node<Path>,
+    }
     Block {
         statements: Vec<Node<Statement>>,
     }
     ClassDefinition {
         name: Node<Ident>,
         fields: Vec<Node<Parameter>>,
+        superclass: Option<Node<Expression>>,
+        body: Node<Block>,
+    }
+    TraitDefinition {
+        name: Node<Ident>,
         body: Node<Block>,
     }
+    Include {
+        target: Node<Expression>,
+    }
     Parameter {
         name: Node<Ident>,
+        kind: ParameterKind,
         default: Option<Node<Expression>>,
     }
     MethodDefinition {
@@ -217,6 +381,151 @@ define_node_types! {
     }
 }
 
+impl Parameter {
+    /// Where the parameter goes in a list: required ones come first, then optional ones, the
+    /// rest parameter and keyword parameters.
+    pub fn rank(&self) -> u8 {
+        match self.kind {
+            ParameterKind::Positional if self.default.is_none() => 0,
+            ParameterKind::Positional => 1,
+            ParameterKind::Rest => 2,
+            ParameterKind::Keyword => 3,
+        }
+    }
+}
+
+impl Pattern {
+    /// The names the pattern binds, in the order they appear. The alternatives of a pattern
+    /// bind the same names, so only those of the first count.
+    pub fn bound_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_names(&mut names);
+        names
+    }
+
+    fn collect_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        let subpatterns: Vec<&Node<Pattern>> = match self {
+            Pattern::Variable(var) => return names.push(&var.v.ident.v.name),
+            Pattern::Literal(_) | Pattern::Wildcard(_) | Pattern::ValuePattern(_) => return,
+            Pattern::TuplePattern(tuple) => tuple.v.items.iter().collect(),
+            Pattern::ArrayPattern(array) => {
+                let ArrayPattern {
+                    before,
+                    rest,
+                    after,
+                } = &array.v;
+                before.iter().chain(rest.as_deref()).chain(after).collect()
+            }
+            Pattern::DictionaryPattern(dictionary) => dictionary
+                .v
+                .entries
+                .iter()
+                .map(|(_, value)| value)
+                .collect(),
+            Pattern::ClassPattern(class) => class.v.fields.iter().collect(),
+            Pattern::AlternativePattern(alternatives) => vec![&alternatives.v.alternatives[0]],
+        };
+        for subpattern in subpatterns {
+            subpattern.v.collect_names(names);
+        }
+    }
+
+    /// Whether every value `other` matches, this pattern matches too. It errs towards `false`
+    /// where telling would mean running code, such as comparing numbers of different classes.
+    pub fn covers(&self, other: &Pattern) -> bool {
+        match (self, other) {
+            (Pattern::Variable(_) | Pattern::Wildcard(_), _) => true,
+            (_, Pattern::AlternativePattern(other)) => other
+                .v
+                .alternatives
+                .iter()
+                .all(|alternative| self.covers(&alternative.v)),
+            (Pattern::AlternativePattern(this), _) => this
+                .v
+                .alternatives
+                .iter()
+                .any(|alternative| alternative.v.covers(other)),
+            (Pattern::Literal(this), Pattern::Literal(other)) => same_literal(&this.v, &other.v),
+            (Pattern::ValuePattern(this), Pattern::ValuePattern(other)) => {
+                this.v.variable.v.ident.v.name == other.v.variable.v.ident.v.name
+            }
+            (Pattern::TuplePattern(this), Pattern::TuplePattern(other)) => {
+                all_cover(&this.v.items, &other.v.items)
+            }
+            (Pattern::ArrayPattern(this), Pattern::ArrayPattern(other)) => {
+                let (this, other) = (&this.v, &other.v);
+                let items: Vec<_> = other.before.iter().chain(&other.after).collect();
+                let (prefix, suffix, fits) = match (&this.rest, &other.rest) {
+                    (None, None) => return all_cover(&this.before, &other.before),
+                    // this pattern fixes a length that the other's rest leaves open
+                    (None, Some(_)) => return false,
+                    (Some(_), None) => {
+                        let fits = this.before.len() + this.after.len() <= items.len();
+                        (&items[..], &items[..], fits)
+                    }
+                    (Some(_), Some(_)) => {
+                        let (prefix, suffix) = items.split_at(other.before.len());
+                        let fits =
+                            this.before.len() <= prefix.len() && this.after.len() <= suffix.len();
+                        (prefix, suffix, fits)
+                    }
+                };
+                fits && this
+                    .before
+                    .iter()
+                    .zip(prefix)
+                    .all(|(a, b)| a.v.covers(&b.v))
+                    && (this.after.iter().rev())
+                        .zip(suffix.iter().rev())
+                        .all(|(a, b)| a.v.covers(&b.v))
+            }
+            (Pattern::DictionaryPattern(this), Pattern::DictionaryPattern(other)) => {
+                this.v.entries.iter().all(|(key, value)| {
+                    other.v.entries.iter().any(|(other_key, other_value)| {
+                        same_literal(&key.v, &other_key.v) && value.v.covers(&other_value.v)
+                    })
+                })
+            }
+            (Pattern::ClassPattern(this), Pattern::ClassPattern(other)) => {
+                this.v.class.meta.source == other.v.class.meta.source
+                    && this.v.fields.len() <= other.v.fields.len()
+                    && all_cover(&this.v.fields, &other.v.fields[..this.v.fields.len()])
+            }
+            _ => false,
+        }
+    }
+}
+
+fn all_cover(patterns: &[Node<Pattern>], others: &[Node<Pattern>]) -> bool {
+    patterns.len() == others.len()
+        && patterns
+            .iter()
+            .zip(others)
+            .all(|(pattern, other)| pattern.v.covers(&other.v))
+}
+
+/// Whether two literal patterns are written to the same value.
+fn same_literal(a: &Literal, b: &Literal) -> bool {
+    match (a, b) {
+        (Literal::Int(a), Literal::Int(b)) => a.v.value == b.v.value,
+        (Literal::BigInt(a), Literal::BigInt(b)) => a.v.value == b.v.value,
+        (Literal::Rational(a), Literal::Rational(b)) => a.v.value == b.v.value,
+        (Literal::Float(a), Literal::Float(b)) => a.v.value == b.v.value,
+        (Literal::StringLit(a), Literal::StringLit(b)) => a.v.value == b.v.value,
+        (Literal::Boolean(a), Literal::Boolean(b)) => a.v.value == b.v.value,
+        (Literal::Nil(_), Literal::Nil(_)) => true,
+        _ => false,
+    }
+}
+
+impl Call {
+    /// The expressions of the positional arguments, then those of the keyword arguments.
+    pub fn argument_expressions(&self) -> impl Iterator<Item = &Node<Expression>> {
+        let keyword_values = self.keywords.iter().map(|(_, value)| value);
+        self.arguments.iter().chain(keyword_values)
+    }
+}
+
 define_collector_enums! {
     Statement {
         ForIn,
@@ -224,11 +533,16 @@ define_collector_enums! {
         Break,
         Continue,
         Return,
+        Raise,
+        Try,
         Assignment,
         Expression,
         MethodDefinition,
         ClassDefinition,
+        TraitDefinition,
+        Include,
         Use,
+        Import,
     }
     Expression {
         Index,
@@ -237,17 +551,24 @@ define_collector_enums! {
         Literal,
         Path,
         IfElse,
+        Match,
         Binary,
         Unary,
+        Propagate,
         Closure,
         Variable,
+        Super,
     }
     Literal {
         Array,
         Tuple,
         Dictionary,
         StringLit,
-        Number,
+        InterpolatedString,
+        Int,
+        BigInt,
+        Rational,
+        Float,
         Boolean,
         Nil,
     }
@@ -256,6 +577,17 @@ define_collector_enums! {
         Index,
         Binding,
     }
+    Pattern {
+        Literal,
+        Variable,
+        Wildcard,
+        ValuePattern,
+        TuplePattern,
+        ArrayPattern,
+        DictionaryPattern,
+        ClassPattern,
+        AlternativePattern,
+    }
 }
 
 macro define_node_types(
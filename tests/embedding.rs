@@ -0,0 +1,285 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use concorde::{Error, ExecMode, NativeClass, NativeClassBuilder, Runtime, Value};
+
+const EXEC_MODES: [ExecMode; 2] = [ExecMode::Bytecode, ExecMode::TreeWalk];
+
+fn runtime(exec_mode: ExecMode) -> Runtime {
+    let mut runtime = Runtime::new();
+    runtime.set_exec_mode(exec_mode);
+    runtime
+}
+
+/// Rust values survive a round trip through concorde objects.
+#[test]
+fn values_convert_to_and_from_rust() {
+    let mut runtime = Runtime::new();
+    let number = runtime.value(1.5);
+    assert_eq!(number.get::<f64>(&runtime).unwrap(), 1.5);
+    assert!(number.get::<i64>(&runtime).is_err());
+    assert!(number.get::<String>(&runtime).is_err());
+
+    let list = runtime.value(vec![Some("a"), None]);
+    assert_eq!(list.class_name(), "Array");
+    assert_eq!(
+        list.get::<Vec<Option<String>>>(&runtime).unwrap(),
+        [Some("a".to_string()), None]
+    );
+
+    let map = runtime.value(HashMap::from([("answer".to_string(), 42_i64)]));
+    let map = map.get::<HashMap<String, i64>>(&runtime).unwrap();
+    assert_eq!(map["answer"], 42);
+
+    let yes = runtime.value(true);
+    assert!(yes.get::<bool>(&runtime).unwrap());
+}
+
+/// Scripts can call functions and methods the application defines in Rust, and rescue the
+/// errors they fail with.
+#[test]
+fn scripts_call_host_functions() {
+    for exec_mode in EXEC_MODES {
+        let mut runtime = runtime(exec_mode);
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let host_log = log.clone();
+        runtime
+            .define_function("record", move |runtime, args| {
+                for arg in args {
+                    host_log.borrow_mut().push(arg.get::<String>(runtime)?);
+                }
+                Ok(runtime.value(()))
+            })
+            .unwrap();
+        runtime
+            .define_function("fail", |_runtime, _args| Err(Error::host("host failure")))
+            .unwrap();
+        let counter = runtime.define_class("Counter");
+        runtime
+            .define_method(&counter, "bump", |runtime, this, args| {
+                let step = match args.first() {
+                    Some(step) => step.get::<f64>(runtime)?,
+                    None => 1.0,
+                };
+                let count = this
+                    .property("count")
+                    .map_or(Ok(0.0), |count| count.get(runtime))?;
+                let count = runtime.value(count + step);
+                this.set_property("count", count.clone());
+                Ok(count)
+            })
+            .unwrap();
+
+        let source = "\
+record(\"start\")
+counter = Counter()
+counter.bump()
+counter.bump(2)
+try
+  fail()
+rescue error: RuntimeError
+  record(error.message)
+end
+counter.count
+";
+        let result = runtime.eval_source(source, "<test>").unwrap();
+        assert_eq!(result.get::<f64>(&runtime).unwrap(), 3.0, "{exec_mode:?}");
+        assert_eq!(*log.borrow(), ["start", "host failure"], "{exec_mode:?}");
+    }
+}
+
+/// The application can call concorde functions and methods by name and read globals.
+#[test]
+fn host_calls_script_functions_and_methods() {
+    for exec_mode in EXEC_MODES {
+        let mut runtime = runtime(exec_mode);
+        let source = "\
+def greet(name) = \"hello \" + name
+
+class Greeter(greeting)
+  def greet(name) = greeting + \" \" + name
+end
+
+double = fn x -> x * 2 end
+";
+        runtime.eval_source(source, "<test>").unwrap();
+
+        let name = runtime.value("world");
+        let greeting = runtime.call_function("greet", vec![name.clone()]).unwrap();
+        assert_eq!(greeting.get::<String>(&runtime).unwrap(), "hello world");
+
+        let hi = runtime.value("hi");
+        let greeter = runtime.call_function("Greeter", vec![hi]).unwrap();
+        let greeting = runtime
+            .call_method_named(&greeter, "greet", vec![name])
+            .unwrap();
+        assert_eq!(greeting.get::<String>(&runtime).unwrap(), "hi world");
+
+        let double: Value = runtime.global("double").unwrap();
+        let four = runtime.value(4_i64);
+        let eight = runtime.call_value(&double, vec![four]).unwrap();
+        assert_eq!(eight.get::<i64>(&runtime).unwrap(), 8);
+
+        // a failed call leaves the runtime usable
+        assert!(runtime.call_function("no_such_function", vec![]).is_err());
+        assert!(runtime.call_function("greet", vec![]).is_err());
+        let answer = runtime.eval_source("21 * 2\n", "<test>").unwrap();
+        assert_eq!(answer.get::<i64>(&runtime).unwrap(), 42, "{exec_mode:?}");
+    }
+}
+
+/// `Option` and `Result` are builtins, so that `?` works without the prelude too.
+#[test]
+fn options_work_without_the_prelude() {
+    for exec_mode in EXEC_MODES {
+        let mut runtime = Runtime::without_prelude();
+        runtime.set_exec_mode(exec_mode);
+        let source = "\
+def first(xs)
+  if xs.len() == 0 then return None
+  Some(xs[0])
+end
+
+def next_of_first(xs) = Ok(first(xs)? + 1)
+
+match next_of_first([41])
+  case Ok(value) if value == 42 then next_of_first([]) == None
+  case _ then false
+end
+";
+        let result = runtime.eval_source(source, "<test>").unwrap();
+        assert!(result.get::<bool>(&runtime).unwrap(), "{exec_mode:?}");
+    }
+}
+
+struct Matrix {
+    rows: usize,
+    columns: usize,
+    cells: Vec<f64>,
+}
+
+impl NativeClass for Matrix {
+    const NAME: &'static str = "Matrix";
+
+    fn init(runtime: &mut Runtime, this: &Value, args: Vec<Value>) -> Result<Self, Error> {
+        let [rows, columns] = args.as_slice() else {
+            return Err(Error::host("Matrix(rows, columns)"));
+        };
+        this.set_property("rows", rows.clone());
+        this.set_property("columns", columns.clone());
+        let rows = rows.get::<i64>(runtime)? as usize;
+        let columns = columns.get::<i64>(runtime)? as usize;
+        Ok(Matrix {
+            rows,
+            columns,
+            cells: vec![0.0; rows * columns],
+        })
+    }
+
+    fn define(class: &mut NativeClassBuilder<Self>) -> Result<(), Error> {
+        class
+            .property("version", 1_i64)
+            .method("set", |runtime, matrix, args| {
+                let [row, column, value] = args.as_slice() else {
+                    return Err(Error::host("set(row, column, value)"));
+                };
+                let index = matrix.index(row.get(runtime)?, column.get(runtime)?)?;
+                matrix.cells[index] = value.get(runtime)?;
+                Ok(runtime.value(()))
+            })?
+            .method("get", |runtime, matrix, args| {
+                let [row, column] = args.as_slice() else {
+                    return Err(Error::host("get(row, column)"));
+                };
+                let index = matrix.index(row.get(runtime)?, column.get(runtime)?)?;
+                Ok(runtime.value(matrix.cells[index]))
+            })?
+            .class_method("identity", |runtime, args| {
+                let size = args[0].clone();
+                let identity = runtime.call_function("Matrix", vec![size.clone(), size])?;
+                identity.with_native(|matrix: &mut Matrix| {
+                    for i in 0..matrix.rows {
+                        matrix.cells[i * matrix.columns + i] = 1.0;
+                    }
+                })?;
+                Ok(identity)
+            })?;
+        Ok(())
+    }
+}
+
+impl Matrix {
+    fn index(&self, row: i64, column: i64) -> Result<usize, Error> {
+        let (row, column) = (row as usize, column as usize);
+        if row >= self.rows || column >= self.columns {
+            return Err(Error::host(format!("no cell ({row}, {column})")));
+        }
+        Ok(row * self.columns + column)
+    }
+}
+
+/// A native class is constructed from concorde like any other, and its methods work on the
+/// Rust value inside the instance.
+#[test]
+fn native_classes_carry_a_rust_payload() {
+    for exec_mode in EXEC_MODES {
+        let mut runtime = runtime(exec_mode);
+        runtime.define_native_class::<Matrix>().unwrap();
+        let source = "\
+m = Matrix(2, 3)
+m.set(1, 2, 5)
+i = Matrix::identity(2)
+message = nil
+try
+  m.get(2, 0)
+rescue e
+  message = e.message
+end
+(m.rows, m.columns, m.get(1, 2), i.get(1, 1), i.get(0, 1), Matrix::version, message)
+";
+        let result = runtime.eval_source(source, "<test>").unwrap();
+        let values: Vec<Value> = result.get(&runtime).unwrap();
+        let numbers: Vec<f64> = values[..6]
+            .iter()
+            .map(|value| value.get(&runtime).unwrap())
+            .collect();
+        assert_eq!(numbers, [2.0, 3.0, 5.0, 1.0, 0.0, 1.0], "{exec_mode:?}");
+        assert_eq!(values[6].get::<String>(&runtime).unwrap(), "no cell (2, 0)");
+
+        let m = runtime.global("m").unwrap();
+        let cell = m
+            .with_native(|matrix: &mut Matrix| matrix.cells[5])
+            .unwrap();
+        assert_eq!(cell, 5.0);
+        let not_a_matrix = runtime.value("m");
+        assert!(not_a_matrix.with_native(|_: &mut Matrix| ()).is_err());
+    }
+}
+
+/// Warnings about a script go to the handler the application sets, before the script runs.
+#[test]
+fn warnings_go_to_the_host() {
+    for exec_mode in EXEC_MODES {
+        let mut runtime = runtime(exec_mode);
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let host_log = log.clone();
+        runtime.set_warning_handler(move |warning| {
+            let line = warning.node.line_col.0;
+            host_log.borrow_mut().push(format!("{line}: {}", warning.message));
+        });
+        let source = "\
+x = match 1
+  case _ then \"any\"
+  case 1 then \"one\"
+end
+x
+";
+        let result = runtime.eval_source(source, "warnings").unwrap();
+        assert_eq!(result.get::<String>(&runtime).unwrap(), "any");
+        assert_eq!(
+            *log.borrow(),
+            ["3: unreachable case, an earlier case matches everything it does"]
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+const SCRIPTS_DIR: &str = "tests/scripts";
+const EXEC_MODES: [&[&str]; 2] = [&[], &["--tree-walk"]];
+
+fn run_script(script: &Path, extra_args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_concorde"))
+        .args(extra_args)
+        .arg(script)
+        .output()
+        .expect("failed to run concorde")
+}
+
+/// Every script must print its `.out` file under both the bytecode VM and the tree-walker.
+#[test]
+fn scripts_match_expected_output_in_all_exec_modes() {
+    let mut scripts: Vec<_> = fs::read_dir(SCRIPTS_DIR)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "concorde"))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty());
+    for script in scripts {
+        let expected = fs::read_to_string(script.with_extension("out")).unwrap();
+        for mode in EXEC_MODES {
+            let output = run_script(&script, mode);
+            let actual = String::from_utf8(output.stdout).expect("non-utf8 output");
+            assert_eq!(actual, expected, "{} {mode:?}", script.display());
+        }
+    }
+}
+
+/// An uncaught error prints the call stack it unwound through, innermost call last, and then
+/// the error pointing at its source line.
+#[test]
+fn uncaught_errors_print_a_traceback_and_diagnostic() {
+    let mut scripts: Vec<_> = fs::read_dir(Path::new(SCRIPTS_DIR).join("errors"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "concorde"))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty());
+    for script in scripts {
+        let expected = fs::read_to_string(script.with_extension("err")).unwrap();
+        for mode in EXEC_MODES {
+            let output = run_script(&script, mode);
+            let actual = String::from_utf8(output.stderr).expect("non-utf8 output");
+            assert_eq!(actual, expected, "{} {mode:?}", script.display());
+        }
+    }
+}
+
+/// Arguments after the code reach it as `Core::argv`, and an uncaught error fails the process.
+/// `check` fails on a syntax error anywhere in the file, not only in its first statement.
+#[test]
+fn command_line_passes_arguments_and_exit_status() {
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_concorde"))
+            .args(args)
+            .output()
+            .expect("failed to run concorde")
+    };
+    let output = run(&["-e", "IO::println(Core::argv)", "a", "--b"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[\"a\", \"--b\"]\n"
+    );
+
+    assert_eq!(run(&["-e", "raise \"failed\""]).status.code(), Some(1));
+    assert_eq!(run(&["--no-such-option"]).status.code(), Some(2));
+    assert!(run(&["check", "tests/scripts/language.concorde"])
+        .status
+        .success());
+    assert_eq!(
+        run(&["check", "tests/scripts/errors/syntax.concorde"])
+            .status
+            .code(),
+        Some(1)
+    );
+}